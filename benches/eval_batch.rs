@@ -0,0 +1,110 @@
+// Compares eval_batch (bit-sliced, 64 evaluations per u64 wire, on a circuit prepared up front)
+// with calling eval once per input set, for batches of 64. Fails if eval_batch isn't at least 32x
+// faster than eval on the 8-bit adder.
+//
+// cargo bench --bench eval_batch
+//
+// Baseline (time per batch of 64, release build, range over a few runs):
+//
+// | Circuit    | Gates | eval x 64    | eval_batch   | Speedup   |
+// | ---------- | ----- | ------------ | ------------ | --------- |
+// | 8-bit add  | 34    | 50 - 51 µs   | 0.9 - 1.0 µs | 53 - 56x  |
+// | 32-bit mul | 2,824 | 740 - 820 µs | 7.7 - 8.1 µs | 92 - 102x |
+//
+// The gates themselves get the full 64x. What keeps the 8-bit adder from it is packing the input
+// bits into lanes and unpacking the outputs, which for 34 gates is a good part of the work. Taking
+// the values as one column per input (and returning one per output) rather than a HashMap per
+// evaluation matters here: building 64 output HashMaps alone takes several times longer than the
+// whole batch.
+
+use std::{collections::HashMap, hint::black_box, time::Instant};
+
+use boolify::{
+    eval, eval_batch, generate_bristol, CircuitOutput, IdGenerator, PreparedCircuit, ValueWire,
+};
+use bristol_circuit::BristolCircuit;
+
+const BATCH_SIZE: usize = 64;
+
+fn binary_op_circuit(width: usize, op: fn(&ValueWire, &ValueWire) -> ValueWire) -> BristolCircuit {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", width, &id_gen);
+    let b = ValueWire::new_input("b", width, &id_gen);
+
+    generate_bristol(&[CircuitOutput::new("c", op(&a, &b))]).unwrap()
+}
+
+// Returns the speedup of eval_batch over eval
+fn bench(name: &str, circuit: &BristolCircuit, mask: usize, rounds: usize) -> f64 {
+    let inputs = (0..BATCH_SIZE)
+        .map(|i| {
+            HashMap::from([
+                ("a".to_string(), (i * 0x9e37_79b9) & mask),
+                ("b".to_string(), !(i * 0x85eb_ca6b) & mask),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    let start = Instant::now();
+
+    for _ in 0..rounds {
+        for inputs in &inputs {
+            black_box(eval(circuit, inputs).unwrap());
+        }
+    }
+
+    let individual = start.elapsed();
+
+    // Preparing is done once per circuit, so it isn't part of the time per batch
+    let prepared = PreparedCircuit::new(circuit).unwrap();
+
+    let columns = ["a", "b"]
+        .iter()
+        .map(|name| {
+            let column = inputs.iter().map(|inputs| inputs[*name]).collect();
+            (name.to_string(), column)
+        })
+        .collect::<HashMap<_, Vec<_>>>();
+
+    let start = Instant::now();
+
+    for _ in 0..rounds {
+        black_box(eval_batch(&prepared, &columns).unwrap());
+    }
+
+    let batched = start.elapsed();
+
+    let speedup = individual.as_secs_f64() / batched.as_secs_f64();
+
+    println!(
+        "{} ({} gates): eval {:?}, eval_batch {:?} ({:.1}x)",
+        name,
+        circuit.gates.len(),
+        individual / rounds as u32,
+        batched / rounds as u32,
+        speedup
+    );
+
+    speedup
+}
+
+fn main() {
+    let add_speedup = bench(
+        "8-bit add",
+        &binary_op_circuit(8, ValueWire::add),
+        0xff,
+        10_000,
+    );
+    bench(
+        "32-bit mul",
+        &binary_op_circuit(32, ValueWire::mul),
+        0xffff_ffff,
+        100,
+    );
+
+    assert!(
+        add_speedup >= 32.0,
+        "eval_batch should be at least 32x faster than eval on the 8-bit adder"
+    );
+}
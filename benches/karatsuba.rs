@@ -0,0 +1,50 @@
+// Compares ValueWire::karatsuba_mul with schoolbook ValueWire::mul for full-width products (the
+// operands are resized to 2n bits so mul doesn't wrap).
+//
+// cargo bench --bench karatsuba
+//
+// | Width | Karatsuba (gates/AND) | Schoolbook (gates/AND) |
+// | ----- | --------------------- | ---------------------- |
+// | 8     | 328 / 172             | 328 / 172              |
+// | 16    | 1476 / 697            | 1446 / 739             |
+// | 32    | 5464 / 2483           | 6010 / 3037            |
+// | 64    | 18410 / 8200          | 24396 / 12262          |
+//
+// 8 bits is below the threshold, so both are schoolbook. The sums and the carry-save correction
+// add XOR gates, so at 16 bits Karatsuba has fewer AND gates but slightly more gates in total.
+
+use boolify::{generate_bristol, CircuitOutput, IdGenerator, ValueWire};
+use bristol_circuit::BristolCircuit;
+
+type Mul = fn(&ValueWire, &ValueWire) -> ValueWire;
+
+fn product(mul: Mul, width: usize) -> BristolCircuit {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", width, &id_gen);
+    let b = ValueWire::new_input("b", width, &id_gen);
+
+    generate_bristol(&[CircuitOutput::new("c", mul(&a, &b))]).unwrap()
+}
+
+fn stats(circuit: &BristolCircuit) -> String {
+    let and_count = circuit.gates.iter().filter(|gate| gate.op == "AND").count();
+
+    format!("{} / {}", circuit.gates.len(), and_count)
+}
+
+fn main() {
+    let schoolbook: Mul = |a, b| {
+        let size = a.bits.len() + b.bits.len();
+        ValueWire::mul(&a.resize(size), &b.resize(size))
+    };
+
+    for width in [8, 16, 32, 64] {
+        println!(
+            "{} bits: Karatsuba {}, schoolbook {}",
+            width,
+            stats(&product(ValueWire::karatsuba_mul, width)),
+            stats(&product(schoolbook, width))
+        );
+    }
+}
@@ -0,0 +1,93 @@
+// Compares BoolWire::or (De Morgan: !(!a & !b)) with the XOR form a ^ b ^ (a & b) on OR-heavy
+// circuits. Both use one AND per OR, so this is about the free gates and depth.
+//
+// cargo bench --bench or_representation
+//
+// | Circuit            | De Morgan (gates/AND/depth) | XOR form (gates/AND/depth) |
+// | ------------------ | --------------------------- | -------------------------- |
+// | a | b (1 bit)      | 4 / 1 / 3                   | 3 / 1 / 2                  |
+// | a | b (16 bits)    | 64 / 16 / 3                 | 48 / 16 / 2                |
+// | any bit (64 bits)  | 128 / 63 / 8                | 189 / 63 / 12              |
+// | any bit (chained)  | 128 / 63 / 65               | 189 / 63 / 126             |
+//
+// The XOR form wins for isolated ORs, but De Morgan wins as soon as ORs feed into each other,
+// because the INV on each result cancels with the INV on the next OR's input. OR reductions (eg
+// ValueWire::to_bool, and so bool_or, not_equal, etc) are the common case here, so BoolWire::or
+// keeps the De Morgan form.
+
+use std::rc::Rc;
+
+use boolify::{
+    circuit_analysis::compute_depth, generate_bristol, BoolWire, CircuitOutput, IdGenerator,
+    ValueWire,
+};
+use bristol_circuit::BristolCircuit;
+
+type Or = fn(&Rc<BoolWire>, &Rc<BoolWire>) -> Rc<BoolWire>;
+type Build = fn(Or) -> BristolCircuit;
+
+fn xor_form_or(a: &Rc<BoolWire>, b: &Rc<BoolWire>) -> Rc<BoolWire> {
+    BoolWire::xor(&BoolWire::xor(a, b), &BoolWire::and(a, b))
+}
+
+fn bitwise(or: Or, width: usize) -> BristolCircuit {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", width, &id_gen);
+    let b = ValueWire::new_input("b", width, &id_gen);
+
+    generate_bristol(&[CircuitOutput::new("c", ValueWire::zip_with(&a, &b, or))]).unwrap()
+}
+
+fn any_bit(or: Or, chained: bool) -> BristolCircuit {
+    let id_gen = IdGenerator::new_rc_refcell();
+    let a = ValueWire::new_input("a", 64, &id_gen);
+
+    let result = match chained {
+        true => a.bits[1..]
+            .iter()
+            .fold(a.bits[0].clone(), |acc, bit| or(&acc, bit)),
+        false => or_tree(or, &a.bits),
+    };
+
+    generate_bristol(&[CircuitOutput::new("c", BoolWire::as_value(&result))]).unwrap()
+}
+
+fn or_tree(or: Or, bits: &[Rc<BoolWire>]) -> Rc<BoolWire> {
+    if bits.len() == 1 {
+        return bits[0].clone();
+    }
+
+    let (left, right) = bits.split_at(bits.len() / 2);
+
+    or(&or_tree(or, left), &or_tree(or, right))
+}
+
+fn stats(circuit: &BristolCircuit) -> String {
+    let and_count = circuit.gates.iter().filter(|gate| gate.op == "AND").count();
+
+    format!(
+        "{} / {} / {}",
+        circuit.gates.len(),
+        and_count,
+        compute_depth(circuit).unwrap()
+    )
+}
+
+fn main() {
+    let circuits: [(&str, Build); 4] = [
+        ("a | b (1 bit)", |or| bitwise(or, 1)),
+        ("a | b (16 bits)", |or| bitwise(or, 16)),
+        ("any bit (64 bits)", |or| any_bit(or, false)),
+        ("any bit (chained)", |or| any_bit(or, true)),
+    ];
+
+    for (name, build) in circuits {
+        println!(
+            "{}: De Morgan {}, XOR form {}",
+            name,
+            stats(&build(BoolWire::or)),
+            stats(&build(xor_form_or))
+        );
+    }
+}
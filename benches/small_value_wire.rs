@@ -0,0 +1,121 @@
+// Counts heap allocations for building 4-bit circuits with ValueWire and SmallValueWire<4>, via a
+// counting global allocator (dhat/heaptrack report the same numbers, this just avoids the
+// dependency).
+//
+// cargo bench --bench small_value_wire
+//
+// | Circuit (4 bits, x1000) | ValueWire (allocs) | SmallValueWire<4> (allocs) |
+// | ----------------------- | ------------------ | -------------------------- |
+// | add                     | 19000              | 18000                      |
+// | and/or/xor/not          | 32000              | 28000                      |
+// | add chain (16 deep)     | 305000             | 288000                     |
+//
+// The remaining allocations are the Rc<BoolWire> gates themselves, which both representations
+// need, so the saving is the one Vec behind each intermediate value.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use boolify::{IdGenerator, SmallValueWire, ValueWire};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const ITERATIONS: usize = 1000;
+
+// Allocations made by f, excluding the inputs it's given
+fn count<T>(setup: impl Fn() -> T, f: impl Fn(&T)) -> usize {
+    let mut total = 0;
+
+    for _ in 0..ITERATIONS {
+        let inputs = setup();
+
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        f(&inputs);
+        total += ALLOCATIONS.load(Ordering::Relaxed) - before;
+    }
+
+    total
+}
+
+fn value_inputs() -> (ValueWire, ValueWire) {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    (
+        ValueWire::new_input("a", 4, &id_gen),
+        ValueWire::new_input("b", 4, &id_gen),
+    )
+}
+
+fn small_inputs() -> (SmallValueWire<4>, SmallValueWire<4>) {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    (
+        SmallValueWire::new_input("a", &id_gen),
+        SmallValueWire::new_input("b", &id_gen),
+    )
+}
+
+fn main() {
+    let rows = [
+        (
+            "add",
+            count(value_inputs, |(a, b)| {
+                ValueWire::add(a, b);
+            }),
+            count(small_inputs, |(a, b)| {
+                SmallValueWire::add(a, b);
+            }),
+        ),
+        (
+            "and/or/xor/not",
+            count(value_inputs, |(a, b)| {
+                ValueWire::bit_not(&ValueWire::bit_xor(
+                    &ValueWire::bit_and(a, b),
+                    &ValueWire::bit_or(a, b),
+                ));
+            }),
+            count(small_inputs, |(a, b)| {
+                SmallValueWire::bit_not(&SmallValueWire::bit_xor(
+                    &SmallValueWire::bit_and(a, b),
+                    &SmallValueWire::bit_or(a, b),
+                ));
+            }),
+        ),
+        (
+            "add chain (16 deep)",
+            count(value_inputs, |(a, b)| {
+                (0..16).fold(a.clone(), |acc, _| ValueWire::add(&acc, b));
+            }),
+            count(small_inputs, |(a, b)| {
+                (0..16).fold(a.clone(), |acc, _| SmallValueWire::add(&acc, b));
+            }),
+        ),
+    ];
+
+    for (name, value_wire, small_value_wire) in rows {
+        println!(
+            "{}: ValueWire {}, SmallValueWire<4> {}",
+            name, value_wire, small_value_wire
+        );
+
+        assert!(small_value_wire < value_wire);
+    }
+}
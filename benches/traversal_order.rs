@@ -0,0 +1,83 @@
+// Compares eval throughput for the same circuit with the gates in the default DFS order vs the
+// breadth-first order from GenerateBristolOptions::topological_order.
+//
+// cargo bench --bench traversal_order
+//
+// Baseline (ChaCha20 block, 1000 evaluations each, release build, typical of a few runs):
+//
+// | Order | Gates  | Time per eval |
+// | ----- | ------ | ------------- |
+// | DFS   | 61,440 | 0.74 ms       |
+// | BFS   | 61,444 | 0.89 ms       |
+//
+// (BFS has 4 extra COPY gates for outputs that other gates also read.)
+//
+// DFS keeps each gate close to the gates producing its inputs, so the wire values it reads tend to
+// still be in cache. BFS spreads dependency chains across whole levels and is about 20% slower
+// here, so eval performance is no reason to change the default order.
+
+use std::{collections::HashMap, hint::black_box, time::Instant};
+
+use boolify::{
+    circuits::chacha20, eval, generate_bristol_with_options, CircuitOutput, GenerateBristolOptions,
+    IdGenerator, ValueWire,
+};
+use bristol_circuit::BristolCircuit;
+
+const EVALUATIONS: usize = 1000;
+
+fn chacha20_circuit(options: &GenerateBristolOptions) -> BristolCircuit {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let key = std::array::from_fn(|i| ValueWire::new_input(&format!("k{}", i), 32, &id_gen));
+    let nonce = std::array::from_fn(|i| ValueWire::new_input(&format!("n{}", i), 32, &id_gen));
+    let counter = ValueWire::new_input("counter", 32, &id_gen);
+
+    let outputs = chacha20::block(&key, &nonce, &counter)
+        .into_iter()
+        .enumerate()
+        .map(|(i, word)| CircuitOutput::new(&format!("out{}", i), word))
+        .collect::<Vec<_>>();
+
+    generate_bristol_with_options(&outputs, options).unwrap()
+}
+
+fn bench(name: &str, circuit: &BristolCircuit) {
+    let mut inputs = HashMap::<String, usize>::new();
+
+    for i in 0..8 {
+        inputs.insert(format!("k{}", i), 0x01234567 * (i + 1));
+    }
+
+    for i in 0..3 {
+        inputs.insert(format!("n{}", i), 0x89abcdef ^ i);
+    }
+
+    let start = Instant::now();
+
+    for i in 0..EVALUATIONS {
+        inputs.insert("counter".to_string(), i);
+        black_box(eval(circuit, &inputs).unwrap());
+    }
+
+    let elapsed = start.elapsed();
+
+    println!(
+        "{}: {} gates, {:?} per eval",
+        name,
+        circuit.gates.len(),
+        elapsed / EVALUATIONS as u32
+    );
+}
+
+fn main() {
+    let dfs = chacha20_circuit(&GenerateBristolOptions::default());
+
+    let bfs = chacha20_circuit(&GenerateBristolOptions {
+        topological_order: true,
+        ..Default::default()
+    });
+
+    bench("DFS", &dfs);
+    bench("BFS", &bfs);
+}
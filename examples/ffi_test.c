@@ -0,0 +1,61 @@
+/*
+ * Roundtrips a 2-bit adder through the C API:
+ *
+ *   cargo build --release
+ *   cc examples/ffi_test.c -Iinclude -Ltarget/release -lboolify -o ffi_test
+ *   LD_LIBRARY_PATH=target/release ./ffi_test
+ */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "boolify.h"
+
+int main(void) {
+    const char *arith_circuit =
+        "{"
+        "\"info\": {"
+        "\"constants\": [],"
+        "\"inputs\": ["
+        "{\"name\": \"a\", \"type\": \"number\", \"address\": 0, \"width\": 1},"
+        "{\"name\": \"b\", \"type\": \"number\", \"address\": 1, \"width\": 1}"
+        "],"
+        "\"outputs\": [{\"name\": \"c\", \"type\": \"number\", \"address\": 2, \"width\": 1}]"
+        "},"
+        "\"bristol\": \"1 3\\n2 1 1\\n1 1\\n\\n2 1 0 1 2 AAdd\\n\""
+        "}";
+
+    const char *expected_gates[] = {
+        "2 1 0 2 6 XOR",
+        "2 1 1 3 4 XOR",
+        "2 1 0 2 5 AND",
+        "2 1 4 5 7 XOR",
+    };
+
+    char *bool_circuit = NULL;
+    size_t len = 0;
+
+    if (boolify_circuit(arith_circuit, 2, &bool_circuit, &len) != 0) {
+        fprintf(stderr, "boolify_circuit failed\n");
+        return 1;
+    }
+
+    int failed = strlen(bool_circuit) != len;
+
+    for (size_t i = 0; i < sizeof(expected_gates) / sizeof(expected_gates[0]); i++) {
+        if (strstr(bool_circuit, expected_gates[i]) == NULL) {
+            fprintf(stderr, "missing gate: %s\n", expected_gates[i]);
+            failed = 1;
+        }
+    }
+
+    if (boolify_circuit("not json", 2, &bool_circuit, &len) != -1) {
+        fprintf(stderr, "expected an error for invalid input\n");
+        failed = 1;
+    }
+
+    printf("%s\n", bool_circuit);
+    free_bristol_string(bool_circuit);
+
+    return failed;
+}
@@ -0,0 +1,30 @@
+#ifndef BOOLIFY_H
+#define BOOLIFY_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Circuits are exchanged as JSON of the form
+ *
+ *   {"info": <circuit info>, "bristol": "<bristol text>"}
+ *
+ * where the circuit info matches circuit_info.json as used by the CLI.
+ *
+ * Converts the arithmetic circuit into a boolean circuit using bit_width-bit numbers. On success
+ * returns 0 and sets *out_bristol to a NUL-terminated JSON string (in the format above) of length
+ * *out_len, which must be released with free_bristol_string. Returns -1 on error.
+ */
+int boolify_circuit(const char *arith_circuit_json, size_t bit_width, char **out_bristol,
+                    size_t *out_len);
+
+void free_bristol_string(char *ptr);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
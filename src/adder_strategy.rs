@@ -0,0 +1,14 @@
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AdderStrategy {
+    #[default]
+    RippleCarry,
+
+    // Ripples between 4-bit blocks, with the carries inside each block computed directly from the
+    // block's carry in
+    CarryLookahead,
+
+    // Parallel prefix networks, all with logarithmic depth
+    KoggeStone,
+    Sklansky,
+    BrentKung,
+}
@@ -0,0 +1,22 @@
+use crate::ValueWire;
+
+// Element-wise operations for describing SIMD-style circuits over arrays of values
+impl ValueWire {
+    pub fn array_map(a: &[ValueWire], f: fn(&ValueWire) -> ValueWire) -> Vec<ValueWire> {
+        a.iter().map(f).collect()
+    }
+
+    pub fn array_zip(
+        a: &[ValueWire],
+        b: &[ValueWire],
+        f: fn(&ValueWire, &ValueWire) -> ValueWire,
+    ) -> Vec<ValueWire> {
+        assert_eq!(a.len(), b.len(), "arrays should have the same length");
+
+        a.iter().zip(b).map(|(a, b)| f(a, b)).collect()
+    }
+
+    pub fn array_add(a: &[ValueWire], b: &[ValueWire]) -> Vec<ValueWire> {
+        ValueWire::array_zip(a, b, ValueWire::add)
+    }
+}
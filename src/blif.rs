@@ -0,0 +1,113 @@
+use std::{collections::HashMap, fmt::Write};
+
+use bristol_circuit::BristolCircuit;
+
+use crate::export_error::{check_gate, check_io_ranges, ExportError};
+
+// Berkeley Logic Interchange Format, as read by ABC and other logic synthesis tools. Each gate
+// becomes a .names entry listing the input patterns that produce 1.
+pub fn to_blif(circuit: &BristolCircuit) -> Result<String, ExportError> {
+    check_io_ranges(circuit)?;
+
+    let mut blif = String::new();
+    let mut names = HashMap::<usize, String>::new();
+
+    for input in &circuit.info.inputs {
+        for bit in 0..input.width {
+            names.insert(input.address + bit, format!("{}[{}]", input.name, bit));
+        }
+    }
+
+    let output_names = circuit
+        .info
+        .outputs
+        .iter()
+        .flat_map(|output| {
+            (0..output.width)
+                .map(move |bit| (output.address + bit, format!("{}[{}]", output.name, bit)))
+        })
+        .collect::<Vec<_>>();
+
+    // Outputs that are also inputs keep the input name and get a buffer below
+    for (wire, name) in &output_names {
+        names.entry(*wire).or_insert_with(|| name.clone());
+    }
+
+    let name = |wire: &usize| {
+        names
+            .get(wire)
+            .cloned()
+            .unwrap_or_else(|| format!("w{}", wire))
+    };
+
+    writeln!(blif, ".model circuit").unwrap();
+
+    write!(blif, ".inputs").unwrap();
+
+    for input in &circuit.info.inputs {
+        for wire in input.address..input.address + input.width {
+            write!(blif, " {}", name(&wire)).unwrap();
+        }
+    }
+
+    writeln!(blif).unwrap();
+
+    write!(blif, ".outputs").unwrap();
+
+    for (_, output_name) in &output_names {
+        write!(blif, " {}", output_name).unwrap();
+    }
+
+    writeln!(blif).unwrap();
+
+    // A constant is a .names with no inputs, which is 1 only if it lists the empty pattern
+    for constant in &circuit.info.constants {
+        writeln!(blif, ".names {}", name(&constant.address)).unwrap();
+
+        let value = constant
+            .value
+            .as_bool()
+            .ok_or(ExportError::InvalidConstant(constant.address))?;
+
+        if value {
+            writeln!(blif, "1").unwrap();
+        }
+    }
+
+    for (i, gate) in circuit.gates.iter().enumerate() {
+        check_gate(i, gate)?;
+
+        let rows: &[&str] = match gate.op.as_str() {
+            "AND" => &["11 1"],
+            "XOR" => &["01 1", "10 1"],
+            "INV" => &["0 1"],
+            // COPY, since check_gate allows nothing else
+            _ => &["1 1"],
+        };
+
+        for output in &gate.outputs {
+            write!(blif, ".names").unwrap();
+
+            for input in &gate.inputs {
+                write!(blif, " {}", name(input)).unwrap();
+            }
+
+            writeln!(blif, " {}", name(output)).unwrap();
+
+            for row in rows {
+                writeln!(blif, "{}", row).unwrap();
+            }
+        }
+    }
+
+    for (wire, output_name) in &output_names {
+        if names[wire] != *output_name {
+            writeln!(blif, ".names {} {}", names[wire], output_name).unwrap();
+            writeln!(blif, "1 1").unwrap();
+        }
+    }
+
+    writeln!(blif, ".end").unwrap();
+
+    Ok(blif)
+}
@@ -1,4 +1,4 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::{circuit_input::CircuitInput, id_generator::IdGenerator, value_wire::ValueWire};
 
@@ -8,6 +8,7 @@ pub enum BoolData {
     And(usize, Rc<BoolWire>, Rc<BoolWire>),
     Inv(usize, Rc<BoolWire>), // Aka NOT
     Xor(usize, Rc<BoolWire>, Rc<BoolWire>),
+    Copy(usize, Rc<BoolWire>),
 }
 
 pub struct BoolWire {
@@ -30,6 +31,7 @@ impl BoolWire {
             BoolData::And(id, _, _) => Some(*id),
             BoolData::Inv(id, _) => Some(*id),
             BoolData::Xor(id, _, _) => Some(*id),
+            BoolData::Copy(id, _) => Some(*id),
         }
     }
 
@@ -75,6 +77,21 @@ impl BoolWire {
         })
     }
 
+    // Like and/or, but b is only built if a isn't a constant that already decides the result
+    pub fn lazy_and(a: &Rc<BoolWire>, b_thunk: impl FnOnce() -> Rc<BoolWire>) -> Rc<BoolWire> {
+        match &a.data {
+            BoolData::Const(false) => a.clone(),
+            _ => BoolWire::and(a, &b_thunk()),
+        }
+    }
+
+    pub fn lazy_or(a: &Rc<BoolWire>, b_thunk: impl FnOnce() -> Rc<BoolWire>) -> Rc<BoolWire> {
+        match &a.data {
+            BoolData::Const(true) => a.clone(),
+            _ => BoolWire::or(a, &b_thunk()),
+        }
+    }
+
     pub fn inv(a: &Rc<BoolWire>) -> Rc<BoolWire> {
         match &a.data {
             BoolData::Const(b) => {
@@ -90,6 +107,8 @@ impl BoolWire {
         BoolWire::inv_with_new_id(a)
     }
 
+    // Always creates a new INV, even of an INV or a constant. This is what inv does once folding
+    // doesn't apply, so outside of that it's only for when a distinct wire is actually needed.
     pub fn inv_with_new_id(a: &Rc<BoolWire>) -> Rc<BoolWire> {
         let id = a.id_gen.borrow_mut().gen();
 
@@ -120,11 +139,154 @@ impl BoolWire {
         })
     }
 
-    pub fn copy_with_new_id(a: &Rc<BoolWire>) -> Rc<BoolWire> {
-        if let BoolData::Inv(_, inv_a) = &a.data {
-            return BoolWire::inv_with_new_id(inv_a);
+    pub fn nand(a: &Rc<BoolWire>, b: &Rc<BoolWire>) -> Rc<BoolWire> {
+        BoolWire::inv(&BoolWire::and(a, b))
+    }
+
+    // or is built as INV(AND(INV, INV)), so the inversion here cancels and this is a single AND
+    pub fn nor(a: &Rc<BoolWire>, b: &Rc<BoolWire>) -> Rc<BoolWire> {
+        BoolWire::inv(&BoolWire::or(a, b))
+    }
+
+    pub fn xnor(a: &Rc<BoolWire>, b: &Rc<BoolWire>) -> Rc<BoolWire> {
+        BoolWire::inv(&BoolWire::xor(a, b))
+    }
+
+    // if cond { a } else { b }, as b ^ (cond & (a ^ b)) so it needs a single AND
+    pub fn mux(cond: &Rc<BoolWire>, a: &Rc<BoolWire>, b: &Rc<BoolWire>) -> Rc<BoolWire> {
+        match &cond.data {
+            BoolData::Const(true) => return a.clone(),
+            BoolData::Const(false) => return b.clone(),
+            _ => (),
+        }
+
+        if Rc::ptr_eq(a, b) {
+            return a.clone();
+        }
+
+        BoolWire::xor(b, &BoolWire::and(cond, &BoolWire::xor(a, b)))
+    }
+
+    // At least two of a, b, c. Uses ((a ^ c) & (b ^ c)) ^ c, which needs a single AND.
+    pub fn majority3(a: &Rc<BoolWire>, b: &Rc<BoolWire>, c: &Rc<BoolWire>) -> Rc<BoolWire> {
+        for (x, y, z) in [(a, b, c), (b, a, c), (c, a, b)] {
+            match &x.data {
+                BoolData::Const(true) => return BoolWire::or(y, z),
+                BoolData::Const(false) => return BoolWire::and(y, z),
+                _ => (),
+            }
+        }
+
+        BoolWire::xor(
+            &BoolWire::and(&BoolWire::xor(a, c), &BoolWire::xor(b, c)),
+            c,
+        )
+    }
+
+    // Balanced trees over any number of inputs, so the depth is log2(n) rather than n. With no
+    // inputs these give the identity of the operation (true for AND, false for OR and XOR). Panics if
+    // the inputs don't all come from id_gen, since ids from different generators overlap.
+    pub fn n_and(inputs: &[Rc<BoolWire>], id_gen: &Rc<RefCell<IdGenerator>>) -> Rc<BoolWire> {
+        check_id_gen(inputs, id_gen);
+        reduce_tree(inputs, id_gen, true, BoolWire::and)
+    }
+
+    pub fn n_or(inputs: &[Rc<BoolWire>], id_gen: &Rc<RefCell<IdGenerator>>) -> Rc<BoolWire> {
+        check_id_gen(inputs, id_gen);
+        reduce_tree(inputs, id_gen, false, BoolWire::or)
+    }
+
+    pub fn n_xor(inputs: &[Rc<BoolWire>], id_gen: &Rc<RefCell<IdGenerator>>) -> Rc<BoolWire> {
+        check_id_gen(inputs, id_gen);
+        reduce_tree(inputs, id_gen, false, BoolWire::xor)
+    }
+
+    // Longest chain of gates from an input or constant to this wire (inputs and constants are 0)
+    pub fn depth(wire: &Rc<BoolWire>) -> usize {
+        BoolWire::depth_memo(wire, &mut HashMap::new())
+    }
+
+    // depth, reusing (and filling in) depths already known by wire id, so that several wires that
+    // share subexpressions can be measured without revisiting them
+    pub fn depth_memo(wire: &Rc<BoolWire>, depths: &mut HashMap<usize, usize>) -> usize {
+        // Explicit stack like generate_gates, so that deep DAGs don't overflow the stack
+        let mut stack = vec![(wire, false)];
+
+        while let Some((bit, visited)) = stack.pop() {
+            let Some(id) = bit.id() else {
+                continue;
+            };
+
+            if depths.contains_key(&id) {
+                continue;
+            }
+
+            let children: Vec<&Rc<BoolWire>> = match &bit.data {
+                BoolData::Const(_) | BoolData::Input(_, _) => vec![],
+                BoolData::And(_, a, b) | BoolData::Xor(_, a, b) => vec![a, b],
+                BoolData::Inv(_, a) | BoolData::Copy(_, a) => vec![a],
+            };
+
+            if children.is_empty() {
+                depths.insert(id, 0);
+            } else if visited {
+                let depth = children
+                    .iter()
+                    .map(|child| child.id().map_or(0, |child_id| depths[&child_id]))
+                    .max()
+                    .unwrap_or(0);
+
+                depths.insert(id, depth + 1);
+            } else {
+                stack.push((bit, true));
+
+                for child in children {
+                    stack.push((child, false));
+                }
+            }
         }
 
-        BoolWire::inv_with_new_id(&BoolWire::inv_with_new_id(a))
+        wire.id().map_or(0, |id| depths[&id])
+    }
+
+    pub fn copy_with_new_id(a: &Rc<BoolWire>) -> Rc<BoolWire> {
+        let id = a.id_gen.borrow_mut().gen();
+
+        Rc::new(BoolWire {
+            id_gen: a.id_gen.clone(),
+            data: BoolData::Copy(id, a.clone()),
+        })
+    }
+}
+
+fn check_id_gen(inputs: &[Rc<BoolWire>], id_gen: &Rc<RefCell<IdGenerator>>) {
+    assert!(
+        inputs.iter().all(|input| Rc::ptr_eq(&input.id_gen, id_gen)),
+        "error: inputs from a different IdGenerator"
+    );
+}
+
+// The tree behind n_and, n_or and n_xor, without checking the IdGenerators. For callers like
+// ValueWire::to_bool, whose bits are trusted to share one.
+pub(crate) fn reduce_tree(
+    inputs: &[Rc<BoolWire>],
+    id_gen: &Rc<RefCell<IdGenerator>>,
+    identity: bool,
+    op: fn(&Rc<BoolWire>, &Rc<BoolWire>) -> Rc<BoolWire>,
+) -> Rc<BoolWire> {
+    match inputs {
+        [] => Rc::new(BoolWire {
+            id_gen: id_gen.clone(),
+            data: BoolData::Const(identity),
+        }),
+        [input] => input.clone(),
+        _ => {
+            let mid = inputs.len() / 2;
+
+            op(
+                &reduce_tree(&inputs[..mid], id_gen, identity, op),
+                &reduce_tree(&inputs[mid..], id_gen, identity, op),
+            )
+        }
     }
 }
@@ -1,11 +1,134 @@
 use core::panic;
-use std::{collections::HashSet, rc::Rc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 use bristol_circuit::{BristolCircuit, CircuitInfo};
 
-use crate::{generate_bristol, BoolWire, CircuitOutput, IdGenerator, ValueWire};
+use crate::{generate_bristol, BoolWire, CircuitOutput, GenerateError, IdGenerator, ValueWire};
+
+pub struct BoolifyOptions {
+    pub bit_width: usize,
+    // Called after each arithmetic gate with (gates processed, total gates)
+    pub progress_callback: Option<Box<dyn Fn(usize, usize)>>,
+    // Checked once per arithmetic gate
+    pub cancel_token: Option<Arc<AtomicBool>>,
+    // Widths for individual wires (by address), overriding bit_width for inputs and constants and
+    // resizing gate outputs
+    pub wire_widths: HashMap<usize, usize>,
+}
+
+impl BoolifyOptions {
+    pub fn new(bit_width: usize) -> Self {
+        BoolifyOptions {
+            bit_width,
+            progress_callback: None,
+            cancel_token: None,
+            wire_widths: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoolifyError {
+    Cancelled,
+    Generate(GenerateError),
+}
+
+impl fmt::Display for BoolifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoolifyError::Cancelled => write!(f, "boolify was cancelled"),
+            BoolifyError::Generate(error) => write!(f, "failed to generate circuit: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for BoolifyError {}
+
+pub fn boolify(
+    arith_circuit: &BristolCircuit,
+    bit_width: usize,
+) -> Result<BristolCircuit, BoolifyError> {
+    boolify_with_options(arith_circuit, BoolifyOptions::new(bit_width))
+}
+
+pub fn boolify_typed(
+    arith_circuit: &BristolCircuit,
+    default_width: usize,
+    wire_widths: &HashMap<usize, usize>,
+) -> Result<BristolCircuit, BoolifyError> {
+    let mut options = BoolifyOptions::new(default_width);
+    options.wire_widths = wire_widths.clone();
+
+    boolify_with_options(arith_circuit, options)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WireWidthsError {
+    InvalidJson(String),
+    InvalidAddress(String),
+    InvalidWidth(String),
+}
+
+impl fmt::Display for WireWidthsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireWidthsError::InvalidJson(error) => write!(f, "invalid info JSON: {}", error),
+            WireWidthsError::InvalidAddress(address) => {
+                write!(f, "wire address should be a number: {}", address)
+            }
+            WireWidthsError::InvalidWidth(address) => {
+                write!(f, "width of wire {} should be a number", address)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WireWidthsError {}
+
+// Reads the widths from circuit info JSON extended with a map from wire address to width, like
+// {"inputs": [...], ..., "wire_widths": {"0": 8, "1": 32}}
+pub fn wire_widths_from_info_json(
+    info_json: &str,
+) -> Result<HashMap<usize, usize>, WireWidthsError> {
+    let info: serde_json::Value = serde_json::from_str(info_json)
+        .map_err(|error| WireWidthsError::InvalidJson(error.to_string()))?;
+
+    let Some(wire_widths) = info["wire_widths"].as_object() else {
+        return Ok(HashMap::new());
+    };
+
+    wire_widths
+        .iter()
+        .map(|(address, width)| {
+            let parsed_address = address
+                .parse()
+                .map_err(|_| WireWidthsError::InvalidAddress(address.clone()))?;
+
+            let width = width
+                .as_u64()
+                .and_then(|width| usize::try_from(width).ok())
+                .ok_or_else(|| WireWidthsError::InvalidWidth(address.clone()))?;
+
+            Ok((parsed_address, width))
+        })
+        .collect()
+}
+
+pub fn boolify_with_options(
+    arith_circuit: &BristolCircuit,
+    options: BoolifyOptions,
+) -> Result<BristolCircuit, BoolifyError> {
+    let bit_width = options.bit_width;
+    let width_of = |address: usize| *options.wire_widths.get(&address).unwrap_or(&bit_width);
 
-pub fn boolify(arith_circuit: &BristolCircuit, bit_width: usize) -> BristolCircuit {
     if !io_widths_all_1s(&arith_circuit.info) {
         panic!("Arithmetic circuit widths should all be 1s");
     }
@@ -24,7 +147,7 @@ pub fn boolify(arith_circuit: &BristolCircuit, bit_width: usize) -> BristolCircu
         wires[input.address] = Some(ValueWire::new_input(
             input.name.as_str(),
             if input.type_ == "number" {
-                bit_width
+                width_of(input.address)
             } else if input.type_ == "bool" {
                 1
             } else {
@@ -36,8 +159,9 @@ pub fn boolify(arith_circuit: &BristolCircuit, bit_width: usize) -> BristolCircu
 
     for const_info in &arith_circuit.info.constants {
         if let Some(v) = const_info.value.as_f64() {
-            wires[const_info.address] =
-                Some(ValueWire::new_const_f64(v, &id_gen).resize(bit_width));
+            wires[const_info.address] = Some(
+                ValueWire::new_const_f64(v, &id_gen).truncate_silent(width_of(const_info.address)),
+            );
         } else if let Some(v) = const_info.value.as_bool() {
             wires[const_info.address] =
                 Some(ValueWire::new_const(if v { 1 } else { 0 }, &id_gen).resize(1));
@@ -60,9 +184,23 @@ pub fn boolify(arith_circuit: &BristolCircuit, bit_width: usize) -> BristolCircu
     .map(|s| s.to_string())
     .collect::<HashSet<_>>();
 
+    let ternary_ops = ["AMux"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<HashSet<_>>();
+
     let bool_to_value = |b: &Rc<BoolWire>| BoolWire::as_value(b).resize(1);
 
-    for gate in &arith_circuit.gates {
+    let total_gates = arith_circuit.gates.len();
+
+    for (gate_index, gate) in arith_circuit.gates.iter().enumerate() {
+        if let Some(cancel_token) = &options.cancel_token {
+            if cancel_token.load(Ordering::Relaxed) {
+                wires.reverse();
+                return Err(BoolifyError::Cancelled);
+            }
+        }
+
         if unary_ops.contains(&gate.op) {
             assert_eq!(gate.inputs.len(), 1);
             assert_eq!(gate.outputs.len(), 1);
@@ -116,9 +254,34 @@ pub fn boolify(arith_circuit: &BristolCircuit, bit_width: usize) -> BristolCircu
                 "AShiftR" => ValueWire::bit_shr(a, b),
                 _ => unreachable!(),
             });
+        } else if ternary_ops.contains(&gate.op) {
+            assert_eq!(gate.inputs.len(), 3);
+            assert_eq!(gate.outputs.len(), 1);
+
+            let [a, b, c] = [0, 1, 2].map(|i| {
+                wires[gate.inputs[i]]
+                    .as_ref()
+                    .expect("Required wire not assigned")
+            });
+
+            let out_id = gate.outputs[0];
+
+            wires[out_id] = Some(match gate.op.as_str() {
+                "AMux" => ValueWire::mux(&a.to_bool(), b, c),
+                _ => unreachable!(),
+            });
         } else {
             panic!("Unrecognized op: {}", &gate.op)
         }
+
+        if let Some(width) = options.wire_widths.get(&gate.outputs[0]) {
+            let value = wires[gate.outputs[0]].as_ref().unwrap();
+            wires[gate.outputs[0]] = Some(value.truncate_silent(*width));
+        }
+
+        if let Some(progress_callback) = &options.progress_callback {
+            progress_callback(gate_index + 1, total_gates);
+        }
     }
 
     let mut outputs = Vec::<CircuitOutput>::new();
@@ -141,7 +304,7 @@ pub fn boolify(arith_circuit: &BristolCircuit, bit_width: usize) -> BristolCircu
     wires.reverse();
     drop(wires);
 
-    circuit
+    circuit.map_err(BoolifyError::Generate)
 }
 
 fn io_widths_all_1s(info: &CircuitInfo) -> bool {
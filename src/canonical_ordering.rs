@@ -0,0 +1,50 @@
+use bristol_circuit::{BristolCircuit, CircuitInfo, Gate};
+
+use crate::{io_lookup::BristolCircuitExt, topological_ordering::gates_by_level};
+
+// Renumbers wires so that they only depend on the structure of the circuit, not the order the
+// gates happened to be generated in: leaf wires (inputs and constants) keep their order, then
+// gates are emitted level by level (sorted by op and inputs, with the inputs of commutative gates
+// sorted too), then the outputs keep their positions at the end.
+pub fn canonicalize_gates(
+    gates: Vec<Gate>,
+    info: &mut CircuitInfo,
+    wire_count: usize,
+) -> (Vec<Gate>, usize) {
+    let output_wires = info
+        .outputs
+        .iter()
+        .flat_map(BristolCircuit::wire_value_range)
+        .collect::<Vec<_>>();
+
+    let (gates_by_level, mut renumbering) = gates_by_level(gates, &output_wires, wire_count);
+
+    let mut result = Vec::<Gate>::new();
+
+    for mut level_gates in gates_by_level {
+        // Everything these gates read is on a lower level, so it has already been renumbered
+        for gate in &mut level_gates {
+            for input in &mut gate.inputs {
+                *input = renumbering.new_ids[*input];
+            }
+
+            if gate.op == "AND" || gate.op == "XOR" {
+                gate.inputs.sort();
+            }
+        }
+
+        level_gates.sort_by(|a, b| (&a.op, &a.inputs).cmp(&(&b.op, &b.inputs)));
+
+        for gate in &mut level_gates {
+            for output in &mut gate.outputs {
+                *output = renumbering.gate_output(*output);
+            }
+        }
+
+        result.extend(level_gates);
+    }
+
+    renumbering.apply_to_info(info);
+
+    (result, renumbering.next_id + output_wires.len())
+}
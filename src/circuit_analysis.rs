@@ -0,0 +1,71 @@
+use std::fmt;
+
+use bristol_circuit::BristolCircuit;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CircuitAnalysisError {
+    // A gate reads or writes a wire that isn't below wire_count
+    WireOutOfRange(usize),
+    // The named output extends past wire_count
+    IOOutOfRange(String),
+    // A gate op that isn't AND, XOR, INV or COPY, when counting or pricing gates by op
+    UnknownOp(String),
+}
+
+impl fmt::Display for CircuitAnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitAnalysisError::WireOutOfRange(wire) => {
+                write!(f, "gate uses wire {} which is out of range", wire)
+            }
+            CircuitAnalysisError::IOOutOfRange(name) => {
+                write!(f, "wires of {} are out of range", name)
+            }
+            CircuitAnalysisError::UnknownOp(op) => write!(f, "unknown gate operation: {}", op),
+        }
+    }
+}
+
+impl std::error::Error for CircuitAnalysisError {}
+
+// Longest chain of gates from an input to an output (every gate counts as one level, including
+// COPY). Gates in a Bristol circuit only read wires written by earlier gates, so one pass is enough.
+pub fn compute_depth(circuit: &BristolCircuit) -> Result<usize, CircuitAnalysisError> {
+    let mut depths = vec![0; circuit.wire_count];
+
+    let depth_of = |depths: &[usize], wire: usize| {
+        depths
+            .get(wire)
+            .copied()
+            .ok_or(CircuitAnalysisError::WireOutOfRange(wire))
+    };
+
+    for gate in &circuit.gates {
+        let mut depth = 0;
+
+        for input in &gate.inputs {
+            depth = depth.max(depth_of(&depths, *input)?);
+        }
+
+        for output in &gate.outputs {
+            *depths
+                .get_mut(*output)
+                .ok_or(CircuitAnalysisError::WireOutOfRange(*output))? = depth + 1;
+        }
+    }
+
+    let mut max_depth = 0;
+
+    for output in &circuit.info.outputs {
+        let wires = match output.address.checked_add(output.width) {
+            Some(end) if end <= circuit.wire_count => output.address..end,
+            _ => return Err(CircuitAnalysisError::IOOutOfRange(output.name.clone())),
+        };
+
+        for wire in wires {
+            max_depth = max_depth.max(depths[wire]);
+        }
+    }
+
+    Ok(max_depth)
+}
@@ -0,0 +1,60 @@
+use bristol_circuit::{CircuitInfo, IOInfo};
+use serde_json::json;
+
+// Builds the metadata for an existing bristol circuit, e.g. one produced by an external tool
+// without an info JSON.
+#[derive(Default)]
+pub struct CircuitInfoBuilder {
+    info: CircuitInfo,
+}
+
+impl CircuitInfoBuilder {
+    pub fn new() -> Self {
+        CircuitInfoBuilder::default()
+    }
+
+    pub fn add_input(mut self, name: &str, type_: &str, address: usize, width: usize) -> Self {
+        self.info.inputs.push(io_info(name, type_, address, width));
+        self
+    }
+
+    pub fn add_output(mut self, name: &str, type_: &str, address: usize, width: usize) -> Self {
+        self.info.outputs.push(io_info(name, type_, address, width));
+        self
+    }
+
+    pub fn build(self) -> CircuitInfo {
+        let mut ranges = self
+            .info
+            .inputs
+            .iter()
+            .chain(&self.info.outputs)
+            .map(|io| {
+                if io.width == 0 {
+                    panic!("error: {} has zero width", io.name);
+                }
+
+                (io.address, io.address + io.width, &io.name)
+            })
+            .collect::<Vec<_>>();
+
+        ranges.sort();
+
+        for pair in ranges.windows(2) {
+            if pair[1].0 < pair[0].1 {
+                panic!("error: {} overlaps {}", pair[0].2, pair[1].2);
+            }
+        }
+
+        self.info
+    }
+}
+
+fn io_info(name: &str, type_: &str, address: usize, width: usize) -> IOInfo {
+    IOInfo {
+        name: name.to_string(),
+        type_: json!(type_),
+        address,
+        width,
+    }
+}
@@ -1,3 +1,18 @@
+// The order in which ValueWire::new_input_with_order hands out the ids of an input's bits, which
+// decides where each bit ends up in the generated circuit. The circuit info doesn't record it: an
+// input's address is always its lowest wire, and eval (like the rest of the crate) reads a value
+// LSB-first from there. So a BigEndian input's value has to be given to eval with its bits
+// reversed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BitOrder {
+    // MSB has the lowest id, and so the lowest wire in the generated circuit (the input's address)
+    BigEndian,
+
+    // LSB has the lowest id and the lowest wire
+    #[default]
+    LittleEndian,
+}
+
 pub struct CircuitInput {
     pub name: String,
     pub id_start: usize,
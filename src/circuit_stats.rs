@@ -0,0 +1,36 @@
+use bristol_circuit::BristolCircuit;
+
+use crate::circuit_analysis::{compute_depth, CircuitAnalysisError};
+
+// Gate counts and depth (see compute_depth) of a generated circuit. COPY gates count towards the
+// depth, but not the per-op counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CircuitStats {
+    pub and_count: usize,
+    pub xor_count: usize,
+    pub inv_count: usize,
+    pub wire_count: usize,
+    pub depth: usize,
+}
+
+impl CircuitStats {
+    pub fn from_circuit(circuit: &BristolCircuit) -> Result<Self, CircuitAnalysisError> {
+        let mut stats = CircuitStats {
+            wire_count: circuit.wire_count,
+            depth: compute_depth(circuit)?,
+            ..Default::default()
+        };
+
+        for gate in &circuit.gates {
+            match gate.op.as_str() {
+                "AND" => stats.and_count += 1,
+                "XOR" => stats.xor_count += 1,
+                "INV" => stats.inv_count += 1,
+                "COPY" => {}
+                _ => return Err(CircuitAnalysisError::UnknownOp(gate.op.clone())),
+            }
+        }
+
+        Ok(stats)
+    }
+}
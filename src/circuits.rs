@@ -0,0 +1,4 @@
+pub mod adder_tree;
+pub mod adder_variants;
+pub mod blake3;
+pub mod chacha20;
@@ -0,0 +1,79 @@
+use std::rc::Rc;
+
+use crate::{AdderStrategy, BoolData, BoolWire, ValueWire};
+
+// Wallace-style reduction: carry-save adders take three values to two until only two remain, so
+// only the final addition has to propagate carries. Like ValueWire::add, the result wraps at the
+// width of the widest value.
+pub fn sum(values: &[ValueWire]) -> ValueWire {
+    sum_with(values, AdderStrategy::Sklansky)
+}
+
+pub fn sum_with(values: &[ValueWire], final_adder: AdderStrategy) -> ValueWire {
+    let id_gen = &values.first().expect("error: no values").id_gen;
+    let size = values.iter().map(|v| v.bits.len()).max().unwrap();
+
+    let mut values = values.iter().map(|v| v.resize(size)).collect::<Vec<_>>();
+
+    while values.len() > 2 {
+        let mut next = Vec::with_capacity(values.len() * 2 / 3 + 2);
+
+        for chunk in values.chunks(3) {
+            if let [a, b, c] = chunk {
+                let (sum, carry) = carry_save(a, b, c);
+                next.push(sum);
+                next.push(carry);
+            } else {
+                next.extend(chunk.iter().cloned());
+            }
+        }
+
+        values = next;
+    }
+
+    match values.as_slice() {
+        [a] => a.clone(),
+        [a, b] => ValueWire::add_with(a, b, final_adder),
+        _ => ValueWire::new_const(0, id_gen),
+    }
+}
+
+// Full adder on every bit position, returning (sum, carry) with the carry already shifted into
+// place
+fn carry_save(a: &ValueWire, b: &ValueWire, c: &ValueWire) -> (ValueWire, ValueWire) {
+    let size = a.bits.len();
+
+    let mut sum_bits = Vec::with_capacity(size);
+    let mut carry_bits = Vec::with_capacity(size);
+
+    carry_bits.push(Rc::new(BoolWire {
+        id_gen: a.id_gen.clone(),
+        data: BoolData::Const(false),
+    }));
+
+    for i in 0..size {
+        let a_bit = a.at(i);
+        let b_bit = b.at(i);
+        let c_bit = c.at(i);
+
+        let a_xor_c = BoolWire::xor(&a_bit, &c_bit);
+        sum_bits.push(BoolWire::xor(&a_xor_c, &b_bit));
+
+        if i + 1 < size {
+            // majority(a, b, c) with a single AND
+            let b_xor_c = BoolWire::xor(&b_bit, &c_bit);
+            carry_bits.push(BoolWire::xor(&BoolWire::and(&a_xor_c, &b_xor_c), &c_bit));
+        }
+    }
+
+    (
+        ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits: sum_bits,
+        },
+        ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits: carry_bits,
+        },
+    )
+}
@@ -0,0 +1,78 @@
+use crate::{
+    circuit_analysis::compute_depth, generate_bristol, AdderStrategy, CircuitOutput, IdGenerator,
+    ValueWire,
+};
+
+// All the adders side by side, with the same semantics as ValueWire::add (the result has the width
+// of the wider input, and the carry out is discarded). See compare_adders for the trade-offs.
+
+const BLOCK_SIZE: usize = 4;
+
+pub fn ripple_carry_adder(a: &ValueWire, b: &ValueWire) -> ValueWire {
+    ValueWire::add(a, b)
+}
+
+pub fn carry_lookahead_adder(a: &ValueWire, b: &ValueWire) -> ValueWire {
+    ValueWire::add_with(a, b, AdderStrategy::CarryLookahead)
+}
+
+// Sklansky: logarithmic depth with fewer gates than Kogge-Stone
+pub fn prefix_adder(a: &ValueWire, b: &ValueWire) -> ValueWire {
+    ValueWire::add_with(a, b, AdderStrategy::Sklansky)
+}
+
+pub fn carry_skip_adder(a: &ValueWire, b: &ValueWire) -> ValueWire {
+    ValueWire::carry_skip_adder(a, b, BLOCK_SIZE)
+}
+
+pub fn carry_select_adder(a: &ValueWire, b: &ValueWire) -> ValueWire {
+    ValueWire::add_carry_select(a, b, BLOCK_SIZE)
+}
+
+pub type Adder = fn(&ValueWire, &ValueWire) -> ValueWire;
+
+pub const VARIANTS: [(&str, Adder); 5] = [
+    ("ripple_carry", ripple_carry_adder),
+    ("carry_lookahead", carry_lookahead_adder),
+    ("prefix", prefix_adder),
+    ("carry_skip", carry_skip_adder),
+    ("carry_select", carry_select_adder),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdderStats {
+    pub name: &'static str,
+    pub gates: usize,
+    pub and_gates: usize,
+    pub depth: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdderComparison {
+    pub width: usize,
+    pub adders: Vec<AdderStats>,
+}
+
+pub fn compare_adders(width: usize) -> AdderComparison {
+    let adders = VARIANTS
+        .iter()
+        .map(|(name, adder)| {
+            let id_gen = IdGenerator::new_rc_refcell();
+
+            let a = ValueWire::new_input("a", width, &id_gen);
+            let b = ValueWire::new_input("b", width, &id_gen);
+
+            let circuit = generate_bristol(&[CircuitOutput::new("c", adder(&a, &b))])
+                .expect("adder circuit should generate");
+
+            AdderStats {
+                name,
+                gates: circuit.gates.len(),
+                and_gates: circuit.gates.iter().filter(|gate| gate.op == "AND").count(),
+                depth: compute_depth(&circuit).expect("generated circuits are well formed"),
+            }
+        })
+        .collect();
+
+    AdderComparison { width, adders }
+}
@@ -0,0 +1,96 @@
+use crate::ValueWire;
+
+// BLAKE3 compression function on 32-bit words, following the reference implementation
+// (https://github.com/BLAKE3-team/BLAKE3/blob/master/reference_impl/reference_impl.rs)
+
+pub const IV: [usize; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+pub const CHUNK_START: usize = 1 << 0;
+pub const CHUNK_END: usize = 1 << 1;
+pub const ROOT: usize = 1 << 3;
+
+const MSG_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+// Returns the full 16-word state; the first 8 words are the new chaining value. The counter is 64
+// bits, and is split into its low and high words like in the reference.
+pub fn compress(
+    chaining_value: &[ValueWire; 8],
+    block: &[ValueWire; 16],
+    counter: &ValueWire,
+    block_len: &ValueWire,
+    flags: &ValueWire,
+) -> [ValueWire; 16] {
+    let id_gen = &chaining_value[0].id_gen;
+    let word = |value: &ValueWire| value.truncate_silent(32);
+
+    let mut state: [ValueWire; 16] = [
+        word(&chaining_value[0]),
+        word(&chaining_value[1]),
+        word(&chaining_value[2]),
+        word(&chaining_value[3]),
+        word(&chaining_value[4]),
+        word(&chaining_value[5]),
+        word(&chaining_value[6]),
+        word(&chaining_value[7]),
+        ValueWire::new_const(IV[0], id_gen).resize(32),
+        ValueWire::new_const(IV[1], id_gen).resize(32),
+        ValueWire::new_const(IV[2], id_gen).resize(32),
+        ValueWire::new_const(IV[3], id_gen).resize(32),
+        word(counter),
+        word(&counter.shift_down_const(32)),
+        word(block_len),
+        word(flags),
+    ];
+
+    let mut block = block.clone().map(|m| word(&m));
+
+    for round_index in 0..7 {
+        round(&mut state, &block);
+
+        if round_index < 6 {
+            block = MSG_PERMUTATION.map(|i| block[i].clone());
+        }
+    }
+
+    for i in 0..8 {
+        state[i] = ValueWire::bit_xor(&state[i], &state[i + 8]);
+        state[i + 8] = ValueWire::bit_xor(&state[i + 8], &word(&chaining_value[i]));
+    }
+
+    state
+}
+
+fn round(state: &mut [ValueWire; 16], m: &[ValueWire; 16]) {
+    // Mix the columns
+    g(state, 0, 4, 8, 12, &m[0], &m[1]);
+    g(state, 1, 5, 9, 13, &m[2], &m[3]);
+    g(state, 2, 6, 10, 14, &m[4], &m[5]);
+    g(state, 3, 7, 11, 15, &m[6], &m[7]);
+
+    // Mix the diagonals
+    g(state, 0, 5, 10, 15, &m[8], &m[9]);
+    g(state, 1, 6, 11, 12, &m[10], &m[11]);
+    g(state, 2, 7, 8, 13, &m[12], &m[13]);
+    g(state, 3, 4, 9, 14, &m[14], &m[15]);
+}
+
+fn g(
+    state: &mut [ValueWire; 16],
+    a: usize,
+    b: usize,
+    c: usize,
+    d: usize,
+    mx: &ValueWire,
+    my: &ValueWire,
+) {
+    state[a] = ValueWire::add(&ValueWire::add(&state[a], &state[b]), mx);
+    state[d] = ValueWire::bit_xor(&state[d], &state[a]).rotate_right(16);
+    state[c] = ValueWire::add(&state[c], &state[d]);
+    state[b] = ValueWire::bit_xor(&state[b], &state[c]).rotate_right(12);
+    state[a] = ValueWire::add(&ValueWire::add(&state[a], &state[b]), my);
+    state[d] = ValueWire::bit_xor(&state[d], &state[a]).rotate_right(8);
+    state[c] = ValueWire::add(&state[c], &state[d]);
+    state[b] = ValueWire::bit_xor(&state[b], &state[c]).rotate_right(7);
+}
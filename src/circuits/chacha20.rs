@@ -0,0 +1,59 @@
+use crate::ValueWire;
+
+// ChaCha20 block function on 32-bit words (RFC 8439)
+
+// "expand 32-byte k"
+pub const CONSTANTS: [usize; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+pub fn quarter_round(
+    a: &ValueWire,
+    b: &ValueWire,
+    c: &ValueWire,
+    d: &ValueWire,
+) -> (ValueWire, ValueWire, ValueWire, ValueWire) {
+    let a = ValueWire::add(a, b);
+    let d = ValueWire::bit_xor(d, &a).rotate_left(16);
+    let c = ValueWire::add(c, &d);
+    let b = ValueWire::bit_xor(b, &c).rotate_left(12);
+    let a = ValueWire::add(&a, &b);
+    let d = ValueWire::bit_xor(&d, &a).rotate_left(8);
+    let c = ValueWire::add(&c, &d);
+    let b = ValueWire::bit_xor(&b, &c).rotate_left(7);
+
+    (a, b, c, d)
+}
+
+// 20 rounds (10 column rounds alternating with 10 diagonal rounds), then the initial state is
+// added back in
+pub fn block(key: &[ValueWire; 8], nonce: &[ValueWire; 3], counter: &ValueWire) -> [ValueWire; 16] {
+    let id_gen = &key[0].id_gen;
+    let word = |value: &ValueWire| value.truncate_silent(32);
+
+    let initial: [ValueWire; 16] = std::array::from_fn(|i| match i {
+        0..=3 => ValueWire::new_const(CONSTANTS[i], id_gen).resize(32),
+        4..=11 => word(&key[i - 4]),
+        12 => word(counter),
+        _ => word(&nonce[i - 13]),
+    });
+
+    let mut state = initial.clone();
+
+    for _ in 0..10 {
+        apply_quarter_round(&mut state, 0, 4, 8, 12);
+        apply_quarter_round(&mut state, 1, 5, 9, 13);
+        apply_quarter_round(&mut state, 2, 6, 10, 14);
+        apply_quarter_round(&mut state, 3, 7, 11, 15);
+
+        apply_quarter_round(&mut state, 0, 5, 10, 15);
+        apply_quarter_round(&mut state, 1, 6, 11, 12);
+        apply_quarter_round(&mut state, 2, 7, 8, 13);
+        apply_quarter_round(&mut state, 3, 4, 9, 14);
+    }
+
+    std::array::from_fn(|i| ValueWire::add(&state[i], &initial[i]))
+}
+
+fn apply_quarter_round(state: &mut [ValueWire; 16], a: usize, b: usize, c: usize, d: usize) {
+    (state[a], state[b], state[c], state[d]) =
+        quarter_round(&state[a], &state[b], &state[c], &state[d]);
+}
@@ -15,7 +15,7 @@ pub fn main() {
     let arith_circuit =
         BristolCircuit::read_info_and_bristol(&info, &mut BufReader::new(circuit_file)).unwrap();
 
-    let bool_circuit = boolify(&arith_circuit, 16);
+    let bool_circuit = boolify(&arith_circuit, 16).unwrap();
 
     fs::create_dir_all("output").unwrap();
 
@@ -0,0 +1,51 @@
+use bristol_circuit::BristolCircuit;
+
+use crate::circuit_analysis::CircuitAnalysisError;
+
+// Weights for each gate type, since backends price gates differently. COPY gates are just wiring,
+// so they're always free.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CostModel {
+    pub and_cost: f64,
+    pub xor_cost: f64,
+    pub inv_cost: f64,
+}
+
+impl CostModel {
+    // Free-XOR garbling: only AND gates need ciphertexts, so the cost is the number of AND gates
+    // (the multiplicative complexity)
+    pub fn garbled_circuit() -> Self {
+        CostModel {
+            and_cost: 1.0,
+            xor_cost: 0.0,
+            inv_cost: 0.0,
+        }
+    }
+
+    // Secret sharing over bits: XOR and INV are local, and each AND uses a Beaver triple which
+    // opens two shared bits
+    pub fn spdz() -> Self {
+        CostModel {
+            and_cost: 2.0,
+            xor_cost: 0.0,
+            inv_cost: 0.0,
+        }
+    }
+}
+
+pub fn circuit_cost(
+    circuit: &BristolCircuit,
+    model: &CostModel,
+) -> Result<f64, CircuitAnalysisError> {
+    circuit
+        .gates
+        .iter()
+        .map(|gate| match gate.op.as_str() {
+            "AND" => Ok(model.and_cost),
+            "XOR" => Ok(model.xor_cost),
+            "INV" => Ok(model.inv_cost),
+            "COPY" => Ok(0.0),
+            _ => Err(CircuitAnalysisError::UnknownOp(gate.op.clone())),
+        })
+        .sum()
+}
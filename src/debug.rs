@@ -0,0 +1,228 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Write,
+    rc::Rc,
+};
+
+use bristol_circuit::BristolCircuit;
+
+use crate::{
+    bool_wire::{BoolData, BoolWire},
+    circuit_output::CircuitOutput,
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RefStats {
+    pub total_nodes: usize,
+    pub shared_nodes: usize,
+    pub max_ref_count: usize,
+    pub max_depth: usize,
+}
+
+// Walks the BoolWire DAG reachable from outputs. Reference counts include references held outside
+// the DAG (eg by the outputs themselves), which is what matters for memory usage.
+pub fn wire_ref_stats(outputs: &[CircuitOutput]) -> RefStats {
+    let mut stats = RefStats::default();
+    let mut depths = HashMap::<*const BoolWire, usize>::new();
+
+    // Same (node, visited_flag) scheme as generate_gates, so that deep DAGs don't overflow the
+    // stack.
+    let mut stack: Vec<(&Rc<BoolWire>, bool)> = outputs
+        .iter()
+        .flat_map(|output| output.value.bits.iter().map(|bit| (bit, false)))
+        .collect();
+
+    while let Some((bit, visited)) = stack.pop() {
+        let ptr = Rc::as_ptr(bit);
+
+        if depths.contains_key(&ptr) {
+            continue;
+        }
+
+        let children: Vec<&Rc<BoolWire>> = match &bit.data {
+            BoolData::Const(_) | BoolData::Input(_, _) => vec![],
+            BoolData::And(_, a, b) | BoolData::Xor(_, a, b) => vec![a, b],
+            BoolData::Inv(_, a) | BoolData::Copy(_, a) => vec![a],
+        };
+
+        if visited {
+            let depth = children
+                .iter()
+                .map(|child| depths[&Rc::as_ptr(child)] + 1)
+                .max()
+                .unwrap_or(0);
+
+            depths.insert(ptr, depth);
+
+            let ref_count = Rc::strong_count(bit);
+
+            stats.total_nodes += 1;
+            stats.max_ref_count = std::cmp::max(stats.max_ref_count, ref_count);
+            stats.max_depth = std::cmp::max(stats.max_depth, depth);
+
+            if ref_count > 1 {
+                stats.shared_nodes += 1;
+            }
+        } else {
+            stack.push((bit, true));
+
+            for child in children.into_iter().rev() {
+                stack.push((child, false));
+            }
+        }
+    }
+
+    stats
+}
+
+// (gates reachable from outputs, ids allocated so far by their IdGenerator). generate_bristol only
+// visits what's reachable, so wires built but not used by any output (and other ids, like those of
+// inputs) account for the difference.
+pub fn count_reachable(outputs: &[CircuitOutput]) -> (usize, usize) {
+    let mut visited = HashSet::<*const BoolWire>::new();
+    let mut reachable_gates = 0;
+
+    let mut stack: Vec<&Rc<BoolWire>> = outputs
+        .iter()
+        .flat_map(|output| output.value.bits.iter())
+        .collect();
+
+    while let Some(bit) = stack.pop() {
+        if !visited.insert(Rc::as_ptr(bit)) {
+            continue;
+        }
+
+        match &bit.data {
+            BoolData::Const(_) | BoolData::Input(_, _) => {}
+            BoolData::And(_, a, b) | BoolData::Xor(_, a, b) => {
+                reachable_gates += 1;
+                stack.extend([a, b]);
+            }
+            BoolData::Inv(_, a) | BoolData::Copy(_, a) => {
+                reachable_gates += 1;
+                stack.push(a);
+            }
+        }
+    }
+
+    let allocated_ids = outputs
+        .first()
+        .map_or(0, |output| output.value.id_gen.borrow().peek());
+
+    (reachable_gates, allocated_ids)
+}
+
+// Graphviz DOT rendering of a circuit, eg for `dot -Tsvg`. Inputs and outputs are grouped in
+// clusters and labelled name[bit], and each gate is a node labelled with its op.
+pub fn to_dot(circuit: &BristolCircuit) -> String {
+    let mut dot = String::new();
+    let mut sources = HashMap::<usize, String>::new();
+
+    writeln!(dot, "digraph circuit {{").unwrap();
+    writeln!(dot, "  rankdir=TB;").unwrap();
+
+    writeln!(dot, "  subgraph cluster_inputs {{").unwrap();
+    writeln!(dot, "    label=\"inputs\";").unwrap();
+
+    for input in &circuit.info.inputs {
+        for bit in 0..input.width {
+            let wire = input.address + bit;
+            let node = format!("w{}", wire);
+
+            writeln!(
+                dot,
+                "    {} [label=\"{}[{}]\", shape=box];",
+                node, input.name, bit
+            )
+            .unwrap();
+
+            sources.insert(wire, node);
+        }
+    }
+
+    for constant in &circuit.info.constants {
+        let node = format!("w{}", constant.address);
+
+        writeln!(
+            dot,
+            "    {} [label=\"{}\", shape=box];",
+            node, constant.value
+        )
+        .unwrap();
+        sources.insert(constant.address, node);
+    }
+
+    writeln!(dot, "  }}").unwrap();
+
+    for (i, gate) in circuit.gates.iter().enumerate() {
+        let color = match gate.op.as_str() {
+            "AND" => "blue",
+            "XOR" => "green",
+            "INV" => "red",
+            _ => "gray",
+        };
+
+        let node = format!("g{}", i);
+
+        writeln!(dot, "  {} [label=\"{}\", color={}];", node, gate.op, color).unwrap();
+
+        for output in &gate.outputs {
+            sources.insert(*output, node.clone());
+        }
+    }
+
+    writeln!(dot, "  subgraph cluster_outputs {{").unwrap();
+    writeln!(dot, "    label=\"outputs\";").unwrap();
+
+    for output in &circuit.info.outputs {
+        for bit in 0..output.width {
+            writeln!(
+                dot,
+                "    out{} [label=\"{}[{}]\", shape=box];",
+                output.address + bit,
+                output.name,
+                bit
+            )
+            .unwrap();
+        }
+    }
+
+    writeln!(dot, "  }}").unwrap();
+
+    // Wires that aren't an input, constant or gate output (only in malformed circuits) still get a
+    // node, so the problem is visible in the rendering
+    let mut undriven = BTreeSet::<usize>::new();
+
+    let mut source = |wire: usize| match sources.get(&wire) {
+        Some(node) => node.clone(),
+        None => {
+            undriven.insert(wire);
+            format!("w{}", wire)
+        }
+    };
+
+    for (i, gate) in circuit.gates.iter().enumerate() {
+        for input in &gate.inputs {
+            writeln!(dot, "  {} -> g{};", source(*input), i).unwrap();
+        }
+    }
+
+    for output in &circuit.info.outputs {
+        for wire in output.address..output.address + output.width {
+            writeln!(dot, "  {} -> out{};", source(wire), wire).unwrap();
+        }
+    }
+
+    for wire in undriven {
+        writeln!(
+            dot,
+            "  w{} [label=\"undriven w{}\", shape=box, style=dashed];",
+            wire, wire
+        )
+        .unwrap();
+    }
+
+    writeln!(dot, "}}").unwrap();
+
+    dot
+}
@@ -0,0 +1,17 @@
+use std::{collections::VecDeque, rc::Rc};
+
+use crate::{
+    circuit_input::CircuitInput,
+    circuit_output::CircuitOutput,
+    generate_bristol::{collect_inputs, GenerateError},
+};
+
+// The inputs that the outputs depend on, ordered by id_start (which is creation order)
+pub fn find_inputs(outputs: &[CircuitOutput]) -> Result<Vec<Rc<CircuitInput>>, GenerateError> {
+    let bits = outputs
+        .iter()
+        .flat_map(|output| output.value.bits.iter().map(|bit| bit.as_ref()))
+        .collect::<VecDeque<_>>();
+
+    Ok(collect_inputs(bits)?.into_values().collect())
+}
@@ -1,67 +1,596 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    ops::{BitAnd, BitXor, Not},
+};
 
-use bristol_circuit::BristolCircuit;
+use bristol_circuit::{BristolCircuit, IOInfo};
 
-pub fn eval(circuit: &BristolCircuit, inputs: &HashMap<String, usize>) -> HashMap<String, usize> {
-    let mut wires: Vec<Option<bool>> = vec![None; circuit.wire_count];
+use crate::io_lookup::BristolCircuitExt;
+
+pub fn eval(
+    circuit: &BristolCircuit,
+    inputs: &HashMap<String, usize>,
+) -> Result<HashMap<String, usize>, EvalError> {
+    eval_u64(circuit, &widen(inputs))?
+        .into_iter()
+        .map(|(name, value)| match usize::try_from(value) {
+            Ok(value) => Ok((name, value)),
+            Err(_) => Err(EvalError::OutputTooLarge(name)),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    DuplicateInput(String),
+    InputMissing(String),
+    UnexpectedInput(String),
+    InputTooLarge {
+        name: String,
+        value: usize,
+        width: usize,
+    },
+    InputWrongWidth {
+        name: String,
+        width: usize,
+        bits: usize,
+    },
+    InvalidConstant(usize),
+    UnknownOp(String),
+    UninitialisedWire(usize),
+    // A gate or an input/output refers to a wire that isn't below wire_count
+    WireOutOfRange(usize),
+    // The gate at this index has fewer inputs or outputs than its op needs
+    MalformedGate(usize),
+    // This input has a different number of values from the first in eval_batch
+    BatchLengthMismatch(String),
+    OutputTooLarge(String),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::DuplicateInput(name) => write!(f, "input {} provided more than once", name),
+            EvalError::InputMissing(name) => write!(f, "missing input {}", name),
+            EvalError::UnexpectedInput(name) => write!(f, "unexpected input {}", name),
+            EvalError::InputTooLarge { name, value, width } => {
+                write!(
+                    f,
+                    "input {} = {} doesn't fit in {} bits",
+                    name, value, width
+                )
+            }
+            EvalError::InputWrongWidth { name, width, bits } => {
+                write!(f, "input {} has {} bits, expected {}", name, bits, width)
+            }
+            EvalError::InvalidConstant(wire) => write!(f, "constant wire {} is not a bool", wire),
+            EvalError::UnknownOp(op) => write!(f, "unknown gate operation: {}", op),
+            EvalError::UninitialisedWire(wire) => write!(f, "wire {} read before assignment", wire),
+            EvalError::WireOutOfRange(wire) => write!(f, "wire {} is out of range", wire),
+            EvalError::MalformedGate(i) => write!(f, "gate {} is missing inputs or outputs", i),
+            EvalError::BatchLengthMismatch(name) => {
+                write!(f, "input {} has a different number of values", name)
+            }
+            EvalError::OutputTooLarge(name) => write!(f, "output {} too large", name),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+// Like eval, but the inputs are a list of (name, value) pairs so that duplicates aren't silently
+// overwritten (as they would be when building a HashMap). Each input declared in circuit.info must
+// be provided exactly once, and nothing else may be provided.
+pub fn eval_checked(
+    circuit: &BristolCircuit,
+    inputs: &[(String, usize)],
+) -> Result<HashMap<String, usize>, EvalError> {
+    let declared = circuit
+        .info
+        .inputs
+        .iter()
+        .map(|input| input.name.as_str())
+        .collect::<HashSet<_>>();
+
+    let mut input_map = HashMap::<String, usize>::new();
+
+    for (name, value) in inputs {
+        if !declared.contains(name.as_str()) {
+            return Err(EvalError::UnexpectedInput(name.clone()));
+        }
+
+        if input_map.insert(name.clone(), *value).is_some() {
+            return Err(EvalError::DuplicateInput(name.clone()));
+        }
+    }
+
+    for input in &circuit.info.inputs {
+        if !input_map.contains_key(&input.name) {
+            return Err(EvalError::InputMissing(input.name.clone()));
+        }
+    }
+
+    eval(circuit, &input_map)
+}
+
+pub fn eval_u64(
+    circuit: &BristolCircuit,
+    inputs: &HashMap<String, u64>,
+) -> Result<HashMap<String, u64>, EvalError> {
+    eval_from_bits(circuit, &input_bits(circuit, inputs)?)?
+        .into_iter()
+        .map(|(name, bits)| {
+            let value = bits_to_u64(&name, &bits)?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+// Like eval, but each output is left as its bits (least significant first, matching the wire
+// layout), so outputs wider than usize are not lost
+pub fn eval_bits(
+    circuit: &BristolCircuit,
+    inputs: &HashMap<String, usize>,
+) -> Result<HashMap<String, Vec<bool>>, EvalError> {
+    eval_from_bits(circuit, &input_bits(circuit, &widen(inputs))?)
+}
+
+// The outputs, and the value of each wire
+type TracedOutputs = (HashMap<String, usize>, Vec<Option<bool>>);
+
+// Like eval, but also returns the value of every wire (indexed by wire id), to see where a circuit
+// goes wrong. Wires that nothing assigned (only possible in a malformed circuit) are None.
+pub fn eval_traced(
+    circuit: &BristolCircuit,
+    inputs: &HashMap<String, usize>,
+) -> Result<TracedOutputs, EvalError> {
+    let mut wires = Vec::new();
+    eval_inner(circuit, &input_bits(circuit, &widen(inputs))?, &mut wires)?;
+
+    let outputs = output_bits(circuit, &wires)?
+        .into_iter()
+        .map(
+            |(name, bits)| match usize::try_from(bits_to_u64(&name, &bits)?) {
+                Ok(value) => Ok((name, value)),
+                Err(_) => Err(EvalError::OutputTooLarge(name)),
+            },
+        )
+        .collect::<Result<_, _>>()?;
+
+    Ok((outputs, wires))
+}
+
+// Evaluates with each input given as its bits (least significant first). Every input must have
+// exactly the width declared in circuit.info.
+pub fn eval_from_bits(
+    circuit: &BristolCircuit,
+    inputs: &HashMap<String, Vec<bool>>,
+) -> Result<HashMap<String, Vec<bool>>, EvalError> {
+    let mut wires = Vec::new();
+    eval_inner(circuit, inputs, &mut wires)?;
+
+    output_bits(circuit, &wires)
+}
+
+// Fills wires (resized to circuit.wire_count) with the value of each wire
+fn eval_inner(
+    circuit: &BristolCircuit,
+    inputs: &HashMap<String, Vec<bool>>,
+    wires: &mut Vec<Option<bool>>,
+) -> Result<(), EvalError> {
+    wires.clear();
+    wires.resize(circuit.wire_count, None);
 
     for input in &circuit.info.inputs {
-        let value = inputs.get(&input.name).expect("missing input value");
+        let bits = inputs
+            .get(&input.name)
+            .ok_or_else(|| EvalError::InputMissing(input.name.clone()))?;
 
-        if input.width < (usize::BITS as usize) {
-            assert!(*value >> input.width == 0, "input value too large");
+        if bits.len() != input.width {
+            return Err(EvalError::InputWrongWidth {
+                name: input.name.clone(),
+                width: input.width,
+                bits: bits.len(),
+            });
         }
 
-        for j in 0..input.width {
-            wires[input.address + j] = Some((value >> j) & 1 == 1);
+        check_io_range(circuit, input)?;
+
+        for (bit, wire) in bits.iter().zip(BristolCircuit::wire_value_range(input)) {
+            wires[wire] = Some(*bit);
         }
     }
 
-    for gate in &circuit.gates {
-        match gate.op.as_str() {
-            "AND" => {
-                let a = gate.inputs[0];
-                let b = gate.inputs[1];
-                let c = gate.outputs[0];
+    eval_gates(circuit, wires, false, true)
+}
+
+// Sets the constants and runs the gates over whatever the wires hold. This is shared by eval
+// (bool wires) and eval_batch (u64 wires, one evaluation per bit), so false and true are passed in.
+fn eval_gates<T>(
+    circuit: &BristolCircuit,
+    wires: &mut [Option<T>],
+    false_: T,
+    true_: T,
+) -> Result<(), EvalError>
+where
+    T: Copy + BitAnd<Output = T> + BitXor<Output = T> + Not<Output = T>,
+{
+    for const_info in &circuit.info.constants {
+        let value = const_info
+            .value
+            .as_bool()
+            .ok_or(EvalError::InvalidConstant(const_info.address))?;
+
+        write_wire(
+            wires,
+            const_info.address,
+            if value { true_ } else { false_ },
+        )?;
+    }
+
+    for (i, gate) in circuit.gates.iter().enumerate() {
+        let input = |j: usize| match gate.inputs.get(j) {
+            Some(wire) => read_wire(wires, *wire),
+            None => Err(EvalError::MalformedGate(i)),
+        };
+
+        let value = match gate.op.as_str() {
+            "AND" => input(0)? & input(1)?,
+            "XOR" => input(0)? ^ input(1)?,
+            "INV" => !input(0)?,
+            "COPY" => input(0)?,
+            _ => return Err(EvalError::UnknownOp(gate.op.clone())),
+        };
+
+        let output = *gate.outputs.first().ok_or(EvalError::MalformedGate(i))?;
+        write_wire(wires, output, value)?;
+    }
+
+    Ok(())
+}
+
+fn read_wire<T: Copy>(wires: &[Option<T>], wire: usize) -> Result<T, EvalError> {
+    match wires.get(wire) {
+        Some(Some(value)) => Ok(*value),
+        Some(None) => Err(EvalError::UninitialisedWire(wire)),
+        None => Err(EvalError::WireOutOfRange(wire)),
+    }
+}
+
+fn write_wire<T>(wires: &mut [Option<T>], wire: usize, value: T) -> Result<(), EvalError> {
+    *wires.get_mut(wire).ok_or(EvalError::WireOutOfRange(wire))? = Some(value);
+
+    Ok(())
+}
+
+fn output_bits(
+    circuit: &BristolCircuit,
+    wires: &[Option<bool>],
+) -> Result<HashMap<String, Vec<bool>>, EvalError> {
+    circuit
+        .info
+        .outputs
+        .iter()
+        .map(|output| {
+            check_io_range(circuit, output)?;
 
-                wires[c] = Some(wires[a].unwrap() && wires[b].unwrap());
+            let bits = BristolCircuit::wire_value_range(output)
+                .map(|wire| read_wire(wires, wire))
+                .collect::<Result<_, _>>()?;
+
+            Ok((output.name.clone(), bits))
+        })
+        .collect()
+}
+
+fn widen(inputs: &HashMap<String, usize>) -> HashMap<String, u64> {
+    inputs
+        .iter()
+        .map(|(name, value)| (name.clone(), *value as u64))
+        .collect()
+}
+
+fn bits_to_u64(name: &str, bits: &[bool]) -> Result<u64, EvalError> {
+    let mut value = 0;
+
+    for (j, bit) in bits.iter().enumerate() {
+        if *bit {
+            if j >= u64::BITS as usize {
+                return Err(EvalError::OutputTooLarge(name.to_string()));
             }
-            "XOR" => {
-                let a = gate.inputs[0];
-                let b = gate.inputs[1];
-                let c = gate.outputs[0];
 
-                wires[c] = Some(wires[a].unwrap() ^ wires[b].unwrap());
+            value |= 1 << j;
+        }
+    }
+
+    Ok(value)
+}
+
+// Splits each input value into the declared number of bits (wider inputs get zeros at the top)
+fn input_bits(
+    circuit: &BristolCircuit,
+    inputs: &HashMap<String, u64>,
+) -> Result<HashMap<String, Vec<bool>>, EvalError> {
+    circuit
+        .info
+        .inputs
+        .iter()
+        .map(|input| {
+            let value = *inputs
+                .get(&input.name)
+                .ok_or_else(|| EvalError::InputMissing(input.name.clone()))?;
+
+            check_input_fits(&input.name, value, input.width)?;
+            check_io_range(circuit, input)?;
+
+            let bits = (0..input.width)
+                .map(|j| j < u64::BITS as usize && (value >> j) & 1 == 1)
+                .collect();
+
+            Ok((input.name.clone(), bits))
+        })
+        .collect()
+}
+
+fn check_input_fits(name: &str, value: u64, width: usize) -> Result<(), EvalError> {
+    if width < (u64::BITS as usize) && value >> width != 0 {
+        return Err(EvalError::InputTooLarge {
+            name: name.to_string(),
+            // Only eval_u64 inputs can exceed usize (on 32-bit targets), and those saturate
+            value: usize::try_from(value).unwrap_or(usize::MAX),
+            width,
+        });
+    }
+
+    Ok(())
+}
+
+// Evaluates once per input set, bit-sliced: each wire holds a u64 where bit i is its value in the
+// i-th evaluation, so each gate is a single bitwise op for up to 64 evaluations at once. The circuit
+// is prepared once (see PreparedCircuit) and can be reused across batches. inputs[name][k] is the
+// value of the input in the k-th evaluation, so every input needs the same number of values, and
+// the outputs are returned the same way. A circuit without inputs has nothing to batch over, so it
+// gets no evaluations; use eval for that.
+pub fn eval_batch(
+    circuit: &PreparedCircuit,
+    inputs: &HashMap<String, Vec<usize>>,
+) -> Result<HashMap<String, Vec<usize>>, EvalError> {
+    let columns = circuit
+        .inputs
+        .iter()
+        .map(|(name, _, _)| {
+            inputs
+                .get(name)
+                .map(Vec::as_slice)
+                .ok_or_else(|| EvalError::InputMissing(name.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let count = columns.first().map_or(0, |column| column.len());
+
+    for ((name, _, _), column) in circuit.inputs.iter().zip(&columns) {
+        if column.len() != count {
+            return Err(EvalError::BatchLengthMismatch(name.clone()));
+        }
+    }
+
+    let outputs = circuit.eval_columns(count, &columns)?;
+
+    Ok(circuit
+        .outputs
+        .iter()
+        .map(|(name, _, _)| name.clone())
+        .zip(outputs)
+        .collect())
+}
+
+// A circuit checked and flattened for repeated bit-sliced evaluation. Addresses, ops and constants
+// are resolved once up front, so evaluating is just the gate loop over a flat Vec<u64> plus packing
+// the inputs into lanes and unpacking the outputs.
+pub struct PreparedCircuit {
+    wire_count: usize,
+    inputs: Vec<(String, usize, usize)>,
+    outputs: Vec<(String, usize, usize)>,
+    constants: Vec<(usize, bool)>,
+    gates: Vec<PreparedGate>,
+}
+
+enum PreparedGate {
+    And(usize, usize, usize),
+    Xor(usize, usize, usize),
+    Inv(usize, usize),
+    Copy(usize, usize),
+}
+
+impl PreparedCircuit {
+    pub fn new(circuit: &BristolCircuit) -> Result<Self, EvalError> {
+        // Tracks which wires have been assigned, so reads before assignment are caught here rather
+        // than silently reading zeros when evaluating
+        let mut assigned = vec![false; circuit.wire_count];
+
+        let mut inputs = Vec::with_capacity(circuit.info.inputs.len());
+
+        for input in &circuit.info.inputs {
+            check_io_range(circuit, input)?;
+
+            for wire in BristolCircuit::wire_value_range(input) {
+                assign_wire(&mut assigned, wire)?;
             }
-            "INV" => {
-                let a = gate.inputs[0];
-                let c = gate.outputs[0];
 
-                wires[c] = Some(!wires[a].unwrap());
+            inputs.push((input.name.clone(), input.address, input.width));
+        }
+
+        let mut constants = Vec::with_capacity(circuit.info.constants.len());
+
+        for const_info in &circuit.info.constants {
+            let value = const_info
+                .value
+                .as_bool()
+                .ok_or(EvalError::InvalidConstant(const_info.address))?;
+
+            constants.push((assign_wire(&mut assigned, const_info.address)?, value));
+        }
+
+        let mut gates = Vec::with_capacity(circuit.gates.len());
+
+        for (i, gate) in circuit.gates.iter().enumerate() {
+            let input = |j: usize| match gate.inputs.get(j) {
+                Some(wire) => assigned_wire(&assigned, *wire),
+                None => Err(EvalError::MalformedGate(i)),
+            };
+
+            let output = *gate.outputs.first().ok_or(EvalError::MalformedGate(i))?;
+
+            gates.push(match gate.op.as_str() {
+                "AND" => PreparedGate::And(input(0)?, input(1)?, output),
+                "XOR" => PreparedGate::Xor(input(0)?, input(1)?, output),
+                "INV" => PreparedGate::Inv(input(0)?, output),
+                "COPY" => PreparedGate::Copy(input(0)?, output),
+                _ => return Err(EvalError::UnknownOp(gate.op.clone())),
+            });
+
+            assign_wire(&mut assigned, output)?;
+        }
+
+        let mut outputs = Vec::with_capacity(circuit.info.outputs.len());
+
+        for output in &circuit.info.outputs {
+            check_io_range(circuit, output)?;
+
+            for wire in BristolCircuit::wire_value_range(output) {
+                assigned_wire(&assigned, wire)?;
             }
-            "COPY" => {
-                let a = gate.inputs[0];
-                let c = gate.outputs[0];
 
-                wires[c] = Some(wires[a].unwrap());
+            outputs.push((output.name.clone(), output.address, output.width));
+        }
+
+        Ok(PreparedCircuit {
+            wire_count: circuit.wire_count,
+            inputs,
+            outputs,
+            constants,
+            gates,
+        })
+    }
+
+    // Evaluates count times, where inputs[i][k] is the value of the i-th input (in the order of
+    // circuit.info.inputs) in the k-th evaluation, and every column has count values. The result
+    // has a column per output, laid out the same way.
+    fn eval_columns(
+        &self,
+        count: usize,
+        inputs: &[&[usize]],
+    ) -> Result<Vec<Vec<usize>>, EvalError> {
+        let mut results = vec![Vec::with_capacity(count); self.outputs.len()];
+        let mut wires = vec![0u64; self.wire_count];
+
+        for ((name, _, width), column) in self.inputs.iter().zip(inputs) {
+            // Checking the OR of the values is enough, then find which one to report
+            let all_bits = column.iter().fold(0, |a, b| a | b);
+
+            if check_input_fits(name, all_bits as u64, *width).is_err() {
+                for value in column.iter() {
+                    check_input_fits(name, *value as u64, *width)?;
+                }
             }
-            _ => {
-                panic!("unknown gate operation: {}", gate.op);
+        }
+
+        for start in (0..count).step_by(u64::BITS as usize) {
+            let lanes = start..count.min(start + u64::BITS as usize);
+
+            for ((_, address, width), column) in self.inputs.iter().zip(inputs) {
+                let words = &mut wires[*address..*address + *width];
+                words.fill(0);
+
+                // Only visits the set bits, which all fit in the width since the inputs were checked
+                for (lane, value) in column[lanes.clone()].iter().enumerate() {
+                    let mut bits = *value;
+
+                    while bits != 0 {
+                        words[bits.trailing_zeros() as usize] |= 1 << lane;
+                        bits &= bits - 1;
+                    }
+                }
+            }
+
+            for (wire, value) in &self.constants {
+                wires[*wire] = if *value { u64::MAX } else { 0 };
+            }
+
+            for gate in &self.gates {
+                match *gate {
+                    PreparedGate::And(a, b, c) => wires[c] = wires[a] & wires[b],
+                    PreparedGate::Xor(a, b, c) => wires[c] = wires[a] ^ wires[b],
+                    PreparedGate::Inv(a, c) => wires[c] = !wires[a],
+                    PreparedGate::Copy(a, c) => wires[c] = wires[a],
+                }
+            }
+
+            for ((name, address, width), column) in self.outputs.iter().zip(&mut results) {
+                // The lanes past the last evaluation hold whatever the gates made of zeros
+                let used = u64::MAX >> (u64::BITS as usize - lanes.len());
+
+                column.resize(lanes.end, 0);
+
+                for (j, word) in wires[*address..*address + *width].iter().enumerate() {
+                    let mut bits = word & used;
+
+                    if bits != 0 && j >= usize::BITS as usize {
+                        return Err(EvalError::OutputTooLarge(name.clone()));
+                    }
+
+                    while bits != 0 {
+                        column[start + bits.trailing_zeros() as usize] |= 1 << j;
+                        bits &= bits - 1;
+                    }
+                }
             }
         }
+
+        Ok(results)
     }
+}
 
-    let mut outputs = HashMap::<String, usize>::new();
+fn check_io_range(circuit: &BristolCircuit, io: &IOInfo) -> Result<(), EvalError> {
+    match io.address.checked_add(io.width) {
+        Some(end) if end <= circuit.wire_count => Ok(()),
+        _ => Err(EvalError::WireOutOfRange(io.address)),
+    }
+}
 
-    for output in &circuit.info.outputs {
-        let mut value = 0;
+fn assign_wire(assigned: &mut [bool], wire: usize) -> Result<usize, EvalError> {
+    *assigned
+        .get_mut(wire)
+        .ok_or(EvalError::WireOutOfRange(wire))? = true;
 
-        for j in 0..output.width {
-            value |= (wires[output.address + j].unwrap() as usize) << j;
-        }
+    Ok(wire)
+}
 
-        outputs.insert(output.name.clone(), value);
+fn assigned_wire(assigned: &[bool], wire: usize) -> Result<usize, EvalError> {
+    match assigned.get(wire) {
+        Some(true) => Ok(wire),
+        Some(false) => Err(EvalError::UninitialisedWire(wire)),
+        None => Err(EvalError::WireOutOfRange(wire)),
     }
+}
+
+// Interprets each output as a fixed-point value with fractional_bits[name] bits after the point
+// (outputs without an entry are plain integers)
+pub fn eval_fixed_point(
+    circuit: &BristolCircuit,
+    inputs: &HashMap<String, usize>,
+    fractional_bits: &HashMap<String, usize>,
+) -> Result<HashMap<String, f64>, EvalError> {
+    let outputs = eval(circuit, inputs)?
+        .into_iter()
+        .map(|(name, value)| {
+            let bits = fractional_bits.get(&name).copied().unwrap_or(0);
+            let scaled = value as f64 / 2f64.powi(bits as i32);
+
+            (name, scaled)
+        })
+        .collect();
 
-    outputs
+    Ok(outputs)
 }
@@ -0,0 +1,60 @@
+use std::fmt;
+
+use bristol_circuit::{BristolCircuit, Gate};
+
+// Errors from exporting a circuit to another format (BLIF, Verilog). The circuit may come from
+// anywhere, so anything the format can't express is reported rather than assumed away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportError {
+    UnknownOp(String),
+    // The constant at this wire isn't a bool
+    InvalidConstant(usize),
+    // The gate at this index has the wrong number of inputs or outputs for its op
+    MalformedGate(usize),
+    // The named input or output extends past wire_count
+    IOOutOfRange(String),
+    // The named input or output has no bits, which a Verilog port can't express
+    EmptyPort(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::UnknownOp(op) => write!(f, "unknown gate operation: {}", op),
+            ExportError::InvalidConstant(wire) => write!(f, "constant wire {} is not a bool", wire),
+            ExportError::MalformedGate(i) => {
+                write!(f, "gate {} has the wrong number of inputs or outputs", i)
+            }
+            ExportError::IOOutOfRange(name) => write!(f, "wires of {} are out of range", name),
+            ExportError::EmptyPort(name) => write!(f, "{} has no bits", name),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+pub(crate) fn check_io_ranges(circuit: &BristolCircuit) -> Result<(), ExportError> {
+    for io in circuit.info.inputs.iter().chain(&circuit.info.outputs) {
+        if !matches!(io.address.checked_add(io.width), Some(end) if end <= circuit.wire_count) {
+            return Err(ExportError::IOOutOfRange(io.name.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+// Checks the op is one of the four Bristol ops and the gate has the inputs it needs and a single
+// output
+pub(crate) fn check_gate(i: usize, gate: &Gate) -> Result<(), ExportError> {
+    let input_count = match gate.op.as_str() {
+        "AND" | "XOR" => 2,
+        "INV" | "COPY" => 1,
+        _ => return Err(ExportError::UnknownOp(gate.op.clone())),
+    };
+
+    if gate.inputs.len() != input_count || gate.outputs.len() != 1 {
+        return Err(ExportError::MalformedGate(i));
+    }
+
+    Ok(())
+}
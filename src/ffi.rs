@@ -0,0 +1,77 @@
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic,
+};
+
+use bristol_circuit::{BristolCircuit, CircuitInfo};
+use serde_json::json;
+
+use crate::boolify;
+
+// Circuits are exchanged as JSON of the form {"info": <circuit info>, "bristol": "<bristol text>"},
+// matching the info and circuit files used by the CLI. See include/boolify.h.
+
+/// Boolifies the arithmetic circuit, returning 0 on success and -1 on error.
+///
+/// # Safety
+///
+/// `arith_circuit_json` must be a valid NUL-terminated string, and `out_bristol` and `out_len` must
+/// be valid for writes. On success, `*out_bristol` must be released with `free_bristol_string`.
+#[no_mangle]
+pub unsafe extern "C" fn boolify_circuit(
+    arith_circuit_json: *const c_char,
+    bit_width: usize,
+    out_bristol: *mut *mut c_char,
+    out_len: *mut usize,
+) -> i32 {
+    if arith_circuit_json.is_null() || out_bristol.is_null() || out_len.is_null() {
+        return -1;
+    }
+
+    let Ok(input) = CStr::from_ptr(arith_circuit_json).to_str() else {
+        return -1;
+    };
+
+    // boolify reports invalid circuits by panicking, which must not unwind into C
+    let Ok(Some(output)) = panic::catch_unwind(|| boolify_json(input, bit_width)) else {
+        return -1;
+    };
+
+    let len = output.len();
+
+    let Ok(output) = CString::new(output) else {
+        return -1;
+    };
+
+    *out_bristol = output.into_raw();
+    *out_len = len;
+
+    0
+}
+
+/// # Safety
+///
+/// `ptr` must be null or a string returned by `boolify_circuit` that hasn't already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn free_bristol_string(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(CString::from_raw(ptr));
+    }
+}
+
+fn boolify_json(input: &str, bit_width: usize) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(input).ok()?;
+    let info: CircuitInfo = serde_json::from_value(value["info"].clone()).ok()?;
+    let bristol = value["bristol"].as_str()?;
+
+    let arith_circuit =
+        BristolCircuit::read_info_and_bristol(&info, &mut bristol.as_bytes()).ok()?;
+    let bool_circuit = boolify(&arith_circuit, bit_width).ok()?;
+
+    let output = json!({
+        "info": bool_circuit.info,
+        "bristol": bool_circuit.get_bristol_string().ok()?,
+    });
+
+    serde_json::to_string(&output).ok()
+}
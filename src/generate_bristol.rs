@@ -1,53 +1,181 @@
 use std::{
+    cell::RefCell,
     collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt,
     rc::Rc,
     usize,
 };
 
-use bristol_circuit::{BristolCircuit, CircuitInfo, Gate, IOInfo};
+use bristol_circuit::{BristolCircuit, CircuitInfo, ConstantInfo, Gate, IOInfo};
 use serde_json::json;
 
 use crate::{
     bool_wire::{BoolData, BoolWire},
+    canonical_ordering::canonicalize_gates,
     circuit_input::CircuitInput,
     circuit_output::CircuitOutput,
+    circuit_stats::CircuitStats,
+    id_generator::IdGenerator,
+    simplify_gates::simplify_gates,
+    topological_ordering::topologically_order_gates,
 };
 
-pub fn generate_bristol(outputs: &Vec<CircuitOutput>) -> BristolCircuit {
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConstantStrategy {
+    // false is XOR(first_input, first_input) and true is INV(false)
+    #[default]
+    XorFirst,
+
+    // Wires 0 and 1 are declared as the constants false and true (via info.constants), and
+    // constant outputs are copied from them
+    ReservedWires,
+
+    // Each constant output bit is declared as a constant wire (via info.constants) at its own
+    // output address, so no gates are needed for it
+    Inline,
+}
+
+#[derive(Clone, Debug)]
+pub struct GenerateBristolOptions {
+    pub constant_strategy: ConstantStrategy,
+
+    // Run the algebraic simplification pass (see simplify_gates) on the generated gates
+    pub simplify: bool,
+
+    // Number the wires by the structure of the circuit rather than generation order, so that
+    // equivalent constructions produce identical output (see canonicalize_gates)
+    pub canonical_ordering: bool,
+
+    // Emit gates breadth-first so that every gate's inputs have strictly smaller wire ids than its
+    // outputs (see topologically_order_gates)
+    pub topological_order: bool,
+
+    // Share a single wire between structurally identical gates (same op and inputs)
+    pub deduplicate: bool,
+}
+
+impl Default for GenerateBristolOptions {
+    fn default() -> Self {
+        GenerateBristolOptions {
+            constant_strategy: ConstantStrategy::default(),
+            simplify: true,
+            canonical_ordering: false,
+            topological_order: false,
+            deduplicate: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenerateError {
+    // The XorFirst strategy derives constants from the first input, so it needs one
+    NoInputs,
+    NoOutputs,
+    ConstInMiddleOfCircuit,
+    // Includes outputs with no bits, since they have no wire to place the output at
+    OutputMissingId,
+    InputMissingId,
+    // Two different inputs start at the same wire id, which happens when the outputs mix wires
+    // from different IdGenerators
+    InputIdConflict(String, String),
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerateError::NoInputs => write!(f, "no inputs"),
+            GenerateError::NoOutputs => write!(f, "no outputs"),
+            GenerateError::ConstInMiddleOfCircuit => {
+                write!(f, "const should not be in the middle of the circuit")
+            }
+            GenerateError::OutputMissingId => write!(f, "output should have an id"),
+            GenerateError::InputMissingId => write!(f, "input should have an id"),
+            GenerateError::InputIdConflict(a, b) => {
+                write!(f, "inputs {} and {} share an id range", a, b)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GenerateError {}
+
+pub fn generate_bristol(outputs: &[CircuitOutput]) -> Result<BristolCircuit, GenerateError> {
+    generate_bristol_with_options(outputs, &GenerateBristolOptions::default())
+}
+
+pub fn generate_bristol_with_stats(
+    outputs: &[CircuitOutput],
+) -> Result<(BristolCircuit, CircuitStats), GenerateError> {
+    let circuit = generate_bristol(outputs)?;
+    let stats = CircuitStats::from_circuit(&circuit).expect("generated circuits are well formed");
+
+    Ok((circuit, stats))
+}
+
+pub fn generate_bristol_with_options(
+    outputs: &[CircuitOutput],
+    options: &GenerateBristolOptions,
+) -> Result<BristolCircuit, GenerateError> {
     let output_bits = outputs
         .iter()
         .flat_map(|output| output.value.bits.iter().map(|bit| bit.as_ref()))
         .collect::<VecDeque<_>>();
 
-    let inputs = collect_inputs(output_bits);
+    let inputs = collect_inputs(output_bits)?;
+
+    let id_gen = &outputs
+        .first()
+        .ok_or(GenerateError::NoOutputs)?
+        .value
+        .id_gen;
 
     let mut wire_id_mapper = WireIdMapper::new();
+    let mut constant_wires = Vec::<(usize, bool)>::new();
+
+    let reserved_wires = match options.constant_strategy {
+        ConstantStrategy::ReservedWires => {
+            let reserved_false = new_constant_wire(false, id_gen);
+            let reserved_true = new_constant_wire(true, id_gen);
+
+            for (wire, value) in [(&reserved_false, false), (&reserved_true, true)] {
+                let id = wire.id().expect("Constant wire should have an id");
+                wire_id_mapper.get(id);
+                constant_wires.push((id, value));
+            }
+
+            Some((reserved_false, reserved_true))
+        }
+        _ => None,
+    };
 
+    // Input wires follow the input's id order, so a BigEndian input has its MSB at its address
     for input in inputs.values() {
-        for i in 0..input.size {
-            wire_id_mapper.get(input.id_start + i);
+        for id in input.id_start..input.id_start + input.size {
+            wire_id_mapper.get(id);
         }
     }
 
-    let first_input = inputs.first_key_value().expect("error: no inputs").1;
-    let id_gen = &outputs.first().expect("error: no outputs").value.id_gen;
-
-    let first_wire = Rc::new(BoolWire {
-        id_gen: id_gen.clone(),
-        data: BoolData::Input(first_input.id_start, first_input.clone()),
-    });
-
     // These exist for the slightly unusual scenario where the outputs include constants -
     // we replace with these to get the required values without having to deal with any explicit
     // constants in boolean circuits, which don't usually require them
-    let special_false = BoolWire::xor(&first_wire, &first_wire);
-    let special_true = BoolWire::inv(&special_false);
+    let (special_false, special_true) = match reserved_wires {
+        Some(reserved_wires) => reserved_wires,
+        None => {
+            let first_input = inputs.first_key_value().ok_or(GenerateError::NoInputs)?.1;
+
+            let first_wire = Rc::new(BoolWire {
+                id_gen: id_gen.clone(),
+                data: BoolData::Input(first_input.id_start, first_input.clone()),
+            });
 
-    // Special true/false often gets copied. By ensuring false is an inversion, we can produce each
-    // copy with a single inversion instead of two.
-    let special_false = BoolWire::inv_with_new_id(&special_true);
+            let special_false = BoolWire::xor(&first_wire, &first_wire);
+            let special_true = BoolWire::inv(&special_false);
 
-    let mut outputs = outputs.clone();
+            (special_false, special_true)
+        }
+    };
+
+    let mut outputs = outputs.to_vec();
     for output in outputs.iter_mut() {
         for bit in output.value.bits.iter_mut() {
             let const_value: Option<bool> = match &bit.data {
@@ -57,13 +185,20 @@ pub fn generate_bristol(outputs: &Vec<CircuitOutput>) -> BristolCircuit {
             };
 
             if let Some(const_value) = const_value {
-                *bit = match const_value {
-                    true => special_true.clone(),
-                    false => special_false.clone(),
+                *bit = match options.constant_strategy {
+                    ConstantStrategy::Inline => {
+                        let wire = new_constant_wire(const_value, id_gen);
+                        constant_wires.push((wire.id().unwrap(), const_value));
+                        wire
+                    }
+                    _ => match const_value {
+                        true => special_true.clone(),
+                        false => special_false.clone(),
+                    },
                 };
             }
 
-            let mut id = bit.id().unwrap();
+            let mut id = bit.id().ok_or(GenerateError::OutputMissingId)?;
 
             if wire_id_mapper.get_existing(id).is_some() {
                 // This output wire overlaps with input!
@@ -79,10 +214,17 @@ pub fn generate_bristol(outputs: &Vec<CircuitOutput>) -> BristolCircuit {
 
     let mut gates = Vec::<Gate>::new();
     let mut generated_ids = HashSet::<usize>::new();
+    let mut structural_ids = HashMap::<(String, Vec<usize>), usize>::new();
 
     for output in &outputs {
         for bit in &output.value.bits {
-            generate_gates(&mut gates, &mut wire_id_mapper, &mut generated_ids, bit);
+            generate_gates(
+                &mut gates,
+                &mut wire_id_mapper,
+                &mut generated_ids,
+                options.deduplicate.then_some(&mut structural_ids),
+                bit,
+            )?;
         }
     }
 
@@ -93,7 +235,7 @@ pub fn generate_bristol(outputs: &Vec<CircuitOutput>) -> BristolCircuit {
     for input in inputs.values() {
         let id = wire_id_mapper
             .get_existing(input.id_start)
-            .expect("Input should have an id");
+            .ok_or(GenerateError::InputMissingId)?;
 
         info.inputs.push(IOInfo {
             name: input.name.clone(),
@@ -107,12 +249,23 @@ pub fn generate_bristol(outputs: &Vec<CircuitOutput>) -> BristolCircuit {
         });
     }
 
-    for output in &outputs {
-        let first = output.value.bits.first().expect("Output should have bits");
+    for (id, value) in constant_wires {
+        info.constants.push(ConstantInfo {
+            value: json!(value),
+            address: wire_id_mapper
+                .get_existing(id)
+                .expect("Constant should have an id"),
+        });
+    }
 
-        let id = wire_id_mapper
-            .get_existing(first.id().expect("Output should have an id"))
-            .expect("Output should have an id");
+    for output in &outputs {
+        let id = output
+            .value
+            .bits
+            .first()
+            .and_then(|first| first.id())
+            .and_then(|first_id| wire_id_mapper.get_existing(first_id))
+            .ok_or(GenerateError::OutputMissingId)?;
 
         info.outputs.push(IOInfo {
             name: output.name.clone(),
@@ -126,18 +279,71 @@ pub fn generate_bristol(outputs: &Vec<CircuitOutput>) -> BristolCircuit {
         });
     }
 
-    BristolCircuit {
-        wire_count: wire_id_mapper.map.len(),
+    let mut wire_count = wire_id_mapper.next_id;
+
+    if options.simplify {
+        (gates, wire_count) = simplify_gates(gates, &mut info, wire_count);
+    }
+
+    if options.canonical_ordering {
+        (gates, wire_count) = canonicalize_gates(gates, &mut info, wire_count);
+    }
+
+    if options.topological_order {
+        (gates, wire_count) = topologically_order_gates(gates, &mut info, wire_count);
+    }
+
+    Ok(BristolCircuit {
+        wire_count,
         info,
         gates,
-    }
+    })
+}
+
+// Constant wires are leaves in the DAG like inputs, but their values are declared in
+// info.constants rather than provided by the caller
+fn new_constant_wire(value: bool, id_gen: &Rc<RefCell<IdGenerator>>) -> Rc<BoolWire> {
+    let id = id_gen.borrow_mut().gen();
+
+    Rc::new(BoolWire {
+        id_gen: id_gen.clone(),
+        data: BoolData::Input(
+            id,
+            Rc::new(CircuitInput {
+                name: value.to_string(),
+                id_start: id,
+                size: 1,
+            }),
+        ),
+    })
 }
 
-fn collect_inputs(mut bits: VecDeque<&BoolWire>) -> BTreeMap<usize, Rc<CircuitInput>> {
+pub(crate) fn collect_inputs(
+    mut bits: VecDeque<&BoolWire>,
+) -> Result<BTreeMap<usize, Rc<CircuitInput>>, GenerateError> {
     let mut inputs = BTreeMap::<usize, Rc<CircuitInput>>::new();
     let mut visited = HashSet::<usize>::new();
 
     while let Some(bool) = bits.pop_front() {
+        // Inputs are checked before the visited set, since a wire from another IdGenerator can
+        // reuse an id that has already been visited
+        if let BoolData::Input(_, input) = &bool.data {
+            match inputs.get(&input.id_start) {
+                Some(prev) if !Rc::ptr_eq(prev, input) => {
+                    return Err(GenerateError::InputIdConflict(
+                        prev.name.clone(),
+                        input.name.clone(),
+                    ));
+                }
+                Some(_) => (),
+                None => {
+                    inputs.insert(input.id_start, input.clone());
+                }
+            }
+
+            continue;
+        }
+
         let Some(id) = bool.id() else {
             continue;
         };
@@ -147,41 +353,36 @@ fn collect_inputs(mut bits: VecDeque<&BoolWire>) -> BTreeMap<usize, Rc<CircuitIn
         }
 
         match &bool.data {
-            BoolData::Input(_, input) => {
-                let prev = inputs.insert(input.id_start, input.clone());
-
-                if let Some(prev) = prev {
-                    assert!(std::ptr::eq(&*prev, &**input));
-                }
-            }
             BoolData::And(_, a, b) | BoolData::Xor(_, a, b) => {
                 bits.push_back(&a);
                 bits.push_back(&b);
             }
-            BoolData::Const(_) => (),
-            BoolData::Inv(_, a) => {
+            BoolData::Const(_) | BoolData::Input(_, _) => (),
+            BoolData::Inv(_, a) | BoolData::Copy(_, a) => {
                 bits.push_back(&a);
             }
         }
     }
 
-    inputs
+    Ok(inputs)
 }
 
+// BTreeMaps rather than HashMaps so that nothing about the wire numbering can depend on hash
+// iteration order, keeping the output identical between runs
 struct WireIdMapper {
-    map: HashMap<usize, usize>,
+    map: BTreeMap<usize, usize>,
     next_id: usize,
 
-    temp_output_map: HashMap<usize, usize>,
+    temp_output_map: BTreeMap<usize, usize>,
     next_output_id: usize,
 }
 
 impl WireIdMapper {
     fn new() -> WireIdMapper {
         WireIdMapper {
-            map: HashMap::new(),
+            map: BTreeMap::new(),
             next_id: 0,
-            temp_output_map: HashMap::new(),
+            temp_output_map: BTreeMap::new(),
             next_output_id: usize::MAX,
         }
     }
@@ -207,6 +408,16 @@ impl WireIdMapper {
         }
     }
 
+    fn is_temp_output(&self, old_id: usize) -> bool {
+        self.temp_output_map.contains_key(&old_id)
+    }
+
+    // Maps old_id to a wire that already exists
+    fn alias(&mut self, old_id: usize, new_id: usize) {
+        assert!(self.get_existing(old_id).is_none());
+        self.map.insert(old_id, new_id);
+    }
+
     fn get_temp_output(&mut self, old_id: usize) -> usize {
         if let Some(new_id) = self.get_existing(old_id) {
             new_id
@@ -219,13 +430,13 @@ impl WireIdMapper {
     }
 
     fn finalize_outputs(&mut self, gates: &mut Vec<Gate>) {
-        let mut update_map = HashMap::<usize, usize>::new();
+        let mut update_map = BTreeMap::<usize, usize>::new();
 
         let temp_output_map_rev = self
             .temp_output_map
             .iter()
             .map(|(a, b)| (*b, *a))
-            .collect::<HashMap<usize, usize>>();
+            .collect::<BTreeMap<usize, usize>>();
 
         for i in 0..self.temp_output_map.len() {
             let temp_id = usize::MAX - i;
@@ -259,8 +470,9 @@ fn generate_gates(
     gates: &mut Vec<Gate>,
     wire_id_mapper: &mut WireIdMapper,
     generated_ids: &mut HashSet<usize>,
+    mut structural_ids: Option<&mut HashMap<(String, Vec<usize>), usize>>,
     start: &Rc<BoolWire>,
-) {
+) -> Result<(), GenerateError> {
     // The stack holds tuples of (node, visited_flag).
     // visited_flag == false: children not yet processed.
     // visited_flag == true: ready to process the node.
@@ -277,41 +489,61 @@ fn generate_gates(
 
         if visited {
             // Process the node after its children have been processed.
-            match &bit.data {
-                BoolData::Input(_, _) => { /* nothing to do for inputs */ }
-                BoolData::And(_, a, b) | BoolData::Xor(_, a, b) => {
-                    let a_id = wire_id_mapper.get(a.id().expect("Input should have an id"));
-                    let b_id = wire_id_mapper.get(b.id().expect("Input should have an id"));
-                    let out_id = wire_id_mapper.get(bit_id);
-                    let op = match &bit.data {
-                        BoolData::And(_, _, _) => "AND".to_string(),
-                        BoolData::Xor(_, _, _) => "XOR".to_string(),
-                        _ => unreachable!(),
-                    };
-                    gates.push(Gate {
-                        inputs: vec![a_id, b_id],
-                        outputs: vec![out_id],
-                        op,
-                    });
-                }
-                BoolData::Inv(_, a) => {
-                    let a_id = wire_id_mapper.get(a.id().expect("Input should have an id"));
-                    let out_id = wire_id_mapper.get(bit_id);
-                    let op = match &bit.data {
-                        BoolData::Inv(_, _) => "INV".to_string(),
-                        _ => unreachable!(),
-                    };
-                    gates.push(Gate {
-                        inputs: vec![a_id],
-                        outputs: vec![out_id],
-                        op,
-                    });
+            let (op, children) = match &bit.data {
+                BoolData::Input(_, _) => {
+                    generated_ids.insert(bit_id);
+                    continue;
                 }
-                BoolData::Const(_) => {
-                    panic!("Const should not be in the middle of the circuit")
+                BoolData::And(_, a, b) => ("AND", vec![a, b]),
+                BoolData::Xor(_, a, b) => ("XOR", vec![a, b]),
+                BoolData::Inv(_, a) => ("INV", vec![a]),
+                BoolData::Copy(_, a) => ("COPY", vec![a]),
+                BoolData::Const(_) => return Err(GenerateError::ConstInMiddleOfCircuit),
+            };
+
+            let input_ids = children
+                .iter()
+                .map(|child| {
+                    // Only consts lack an id (the constructors fold them away, but BoolData can be
+                    // built directly), and a gate input has to have one
+                    let child_id = child.id().ok_or(GenerateError::InputMissingId)?;
+                    Ok(wire_id_mapper.get(child_id))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // Gates with the same op and inputs compute the same value, so they can share a wire
+            // (this is keyed on the generated input ids, so it applies recursively). Copies and
+            // outputs need their own wires though.
+            let mut key_inputs = input_ids.clone();
+
+            if op == "AND" || op == "XOR" {
+                key_inputs.sort();
+            }
+
+            let key = (op.to_string(), key_inputs);
+
+            if let Some(structural_ids) = &structural_ids {
+                if op != "COPY" && !wire_id_mapper.is_temp_output(bit_id) {
+                    if let Some(existing_id) = structural_ids.get(&key) {
+                        wire_id_mapper.alias(bit_id, *existing_id);
+                        generated_ids.insert(bit_id);
+                        continue;
+                    }
                 }
             }
 
+            let out_id = wire_id_mapper.get(bit_id);
+
+            if let Some(structural_ids) = &mut structural_ids {
+                structural_ids.entry(key).or_insert(out_id);
+            }
+
+            gates.push(Gate {
+                inputs: input_ids,
+                outputs: vec![out_id],
+                op: op.to_string(),
+            });
+
             generated_ids.insert(bit_id);
         } else {
             // First time seeing this node:
@@ -328,15 +560,15 @@ fn generate_gates(
                         stack.push((a.clone(), false));
                     }
                 }
-                BoolData::Inv(_, a) => {
+                BoolData::Inv(_, a) | BoolData::Copy(_, a) => {
                     if a.id().is_some() {
                         stack.push((a.clone(), false));
                     }
                 }
-                BoolData::Const(_) => {
-                    panic!("Const should not be in the middle of the circuit")
-                }
+                BoolData::Const(_) => return Err(GenerateError::ConstInMiddleOfCircuit),
             }
         }
     }
+
+    Ok(())
 }
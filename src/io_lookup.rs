@@ -0,0 +1,62 @@
+use std::{cell::OnceCell, collections::HashMap, ops::Range};
+
+use bristol_circuit::{BristolCircuit, IOInfo};
+
+use crate::topological_ordering::{topological_sort, TopologicalError};
+
+pub trait BristolCircuitExt {
+    fn wire_value_range(io: &IOInfo) -> Range<usize>;
+    fn io_lookup(&self) -> IOLookup<'_>;
+
+    // Gate indices in an order where every gate comes after the gates producing its inputs
+    fn topological_sort(&self) -> Result<Vec<usize>, TopologicalError>;
+}
+
+impl BristolCircuitExt for BristolCircuit {
+    fn wire_value_range(io: &IOInfo) -> Range<usize> {
+        io.address..io.address + io.width
+    }
+
+    fn io_lookup(&self) -> IOLookup<'_> {
+        IOLookup::new(self)
+    }
+
+    fn topological_sort(&self) -> Result<Vec<usize>, TopologicalError> {
+        topological_sort(self)
+    }
+}
+
+// Finds inputs/outputs by name. The maps are only built on first use and then reused.
+pub struct IOLookup<'a> {
+    circuit: &'a BristolCircuit,
+    inputs: OnceCell<HashMap<&'a str, &'a IOInfo>>,
+    outputs: OnceCell<HashMap<&'a str, &'a IOInfo>>,
+}
+
+impl<'a> IOLookup<'a> {
+    pub fn new(circuit: &'a BristolCircuit) -> Self {
+        IOLookup {
+            circuit,
+            inputs: OnceCell::new(),
+            outputs: OnceCell::new(),
+        }
+    }
+
+    pub fn get_input_info(&self, name: &str) -> Option<&'a IOInfo> {
+        self.inputs
+            .get_or_init(|| index_by_name(&self.circuit.info.inputs))
+            .get(name)
+            .copied()
+    }
+
+    pub fn get_output_info(&self, name: &str) -> Option<&'a IOInfo> {
+        self.outputs
+            .get_or_init(|| index_by_name(&self.circuit.info.outputs))
+            .get(name)
+            .copied()
+    }
+}
+
+fn index_by_name(ios: &[IOInfo]) -> HashMap<&str, &IOInfo> {
+    ios.iter().map(|io| (io.name.as_str(), io)).collect()
+}
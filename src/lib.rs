@@ -1,18 +1,60 @@
+mod adder_strategy;
+mod array;
+mod blif;
 mod bool_wire;
 mod boolify;
+mod canonical_ordering;
+mod circuit_info_builder;
 mod circuit_input;
 mod circuit_output;
+mod circuit_stats;
+mod cost_model;
 mod eval;
+mod export_error;
 mod generate_bristol;
 mod id_generator;
+mod io_lookup;
+mod simplify_gates;
+mod small_value_wire;
+mod topological_ordering;
 mod value_wire;
+mod verilog;
 
+pub mod circuit_analysis;
+pub mod circuits;
+pub mod debug;
+pub mod dependency_analysis;
+pub mod ffi;
+pub mod testing;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use adder_strategy::AdderStrategy;
+pub use blif::to_blif;
 pub use bool_wire::{BoolData, BoolWire};
-pub use circuit_input::CircuitInput;
+pub use circuit_info_builder::CircuitInfoBuilder;
+pub use circuit_input::{BitOrder, CircuitInput};
 pub use circuit_output::CircuitOutput;
+pub use circuit_stats::CircuitStats;
+pub use cost_model::{circuit_cost, CostModel};
+pub use export_error::ExportError;
 pub use id_generator::IdGenerator;
+pub use io_lookup::{BristolCircuitExt, IOLookup};
+pub use small_value_wire::SmallValueWire;
+pub use topological_ordering::TopologicalError;
 pub use value_wire::ValueWire;
+pub use verilog::to_verilog_netlist;
 
-pub use boolify::boolify;
-pub use eval::eval;
-pub use generate_bristol::generate_bristol;
+pub use boolify::{
+    boolify, boolify_typed, boolify_with_options, wire_widths_from_info_json, BoolifyError,
+    BoolifyOptions, WireWidthsError,
+};
+pub use eval::{
+    eval, eval_batch, eval_bits, eval_checked, eval_fixed_point, eval_from_bits, eval_traced,
+    eval_u64, EvalError, PreparedCircuit,
+};
+pub use generate_bristol::{
+    generate_bristol, generate_bristol_with_options, generate_bristol_with_stats, ConstantStrategy,
+    GenerateBristolOptions, GenerateError,
+};
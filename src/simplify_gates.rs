@@ -0,0 +1,237 @@
+use std::collections::{HashMap, HashSet};
+
+use bristol_circuit::{BristolCircuit, CircuitInfo, Gate};
+
+use crate::io_lookup::BristolCircuitExt;
+
+enum Simplified {
+    Alias(usize),
+    Const(bool),
+    Keep,
+}
+
+// Applies local identities (INV(INV(a)) = a, AND(a, a) = a, XOR(a, a) = 0, AND(a, INV(a)) = 0,
+// XOR(a, INV(a)) = 1, ...) until nothing changes, then drops dead gates and renumbers the wires.
+// A constant is only replaced by a wire that already holds it, so the first gate producing each
+// constant is kept.
+pub fn simplify_gates(
+    mut gates: Vec<Gate>,
+    info: &mut CircuitInfo,
+    wire_count: usize,
+) -> (Vec<Gate>, usize) {
+    let output_wires = info
+        .outputs
+        .iter()
+        .flat_map(BristolCircuit::wire_value_range)
+        .collect::<Vec<_>>();
+
+    let mut is_output = vec![false; wire_count];
+    for wire in &output_wires {
+        is_output[*wire] = true;
+    }
+
+    loop {
+        let before = gates.len();
+        gates = simplify_pass(gates, info, &is_output);
+
+        if gates.len() == before {
+            break;
+        }
+    }
+
+    let gates = remove_dead_gates(gates, &output_wires);
+    let gates = retarget_output_copies(gates, &is_output);
+    renumber_wires(gates, info, wire_count)
+}
+
+fn simplify_pass(gates: Vec<Gate>, info: &CircuitInfo, is_output: &[bool]) -> Vec<Gate> {
+    let mut alias = (0..is_output.len()).collect::<Vec<_>>();
+    let mut values = vec![None; is_output.len()];
+    let mut holders = [None, None];
+    let mut inv_input = HashMap::<usize, usize>::new();
+
+    for constant in &info.constants {
+        if let Some(value) = constant.value.as_bool() {
+            values[constant.address] = Some(value);
+            holders[value as usize].get_or_insert(constant.address);
+        }
+    }
+
+    let mut result = Vec::with_capacity(gates.len());
+
+    for mut gate in gates {
+        for input in &mut gate.inputs {
+            *input = alias[*input];
+        }
+
+        let out = gate.outputs[0];
+
+        let is_complement =
+            |a: usize, b: usize| inv_input.get(&a) == Some(&b) || inv_input.get(&b) == Some(&a);
+
+        let simplified = match (gate.op.as_str(), gate.inputs.as_slice()) {
+            ("INV", &[a]) => match (values[a], inv_input.get(&a)) {
+                (Some(value), _) => Simplified::Const(!value),
+                (None, Some(&b)) => Simplified::Alias(b),
+                (None, None) => Simplified::Keep,
+            },
+            ("COPY", &[a]) => Simplified::Alias(a),
+            ("AND", &[a, b]) => match (values[a], values[b]) {
+                (Some(false), _) | (_, Some(false)) => Simplified::Const(false),
+                (Some(true), _) => Simplified::Alias(b),
+                (_, Some(true)) => Simplified::Alias(a),
+                _ if a == b => Simplified::Alias(a),
+                _ if is_complement(a, b) => Simplified::Const(false),
+                _ => Simplified::Keep,
+            },
+            ("XOR", &[a, b]) => match (values[a], values[b]) {
+                (Some(x), Some(y)) => Simplified::Const(x ^ y),
+                (Some(false), _) => Simplified::Alias(b),
+                (_, Some(false)) => Simplified::Alias(a),
+                _ if a == b => Simplified::Const(false),
+                _ if is_complement(a, b) => Simplified::Const(true),
+                _ => Simplified::Keep,
+            },
+            _ => Simplified::Keep,
+        };
+
+        let replacement = match simplified {
+            Simplified::Alias(wire) => Some(wire),
+            Simplified::Const(value) => holders[value as usize],
+            Simplified::Keep => None,
+        };
+
+        match replacement {
+            Some(wire) if !is_output[out] => {
+                alias[out] = wire;
+                continue;
+            }
+            Some(wire) if gate.op != "COPY" || gate.inputs[0] != wire => {
+                // Outputs have fixed wires, so copy the replacement into place
+                gate = Gate {
+                    inputs: vec![wire],
+                    outputs: vec![out],
+                    op: "COPY".to_string(),
+                };
+            }
+            _ => (),
+        }
+
+        if let Simplified::Const(value) = simplified {
+            values[out] = Some(value);
+            holders[value as usize].get_or_insert(out);
+        } else if gate.op == "INV" {
+            inv_input.insert(out, gate.inputs[0]);
+        }
+
+        result.push(gate);
+    }
+
+    result
+}
+
+// COPY(x) into an output is unnecessary if x is an internal gate output with no other uses, since
+// that gate can write to the output wire directly
+fn retarget_output_copies(mut gates: Vec<Gate>, is_output: &[bool]) -> Vec<Gate> {
+    let mut use_counts = vec![0; is_output.len()];
+    let mut producers = HashMap::<usize, usize>::new();
+
+    for (i, gate) in gates.iter().enumerate() {
+        for input in &gate.inputs {
+            use_counts[*input] += 1;
+        }
+
+        producers.insert(gate.outputs[0], i);
+    }
+
+    let mut removed = vec![false; gates.len()];
+
+    for i in 0..gates.len() {
+        let (source, out) = (gates[i].inputs[0], gates[i].outputs[0]);
+
+        if gates[i].op != "COPY" || is_output[source] || use_counts[source] != 1 {
+            continue;
+        }
+
+        if let Some(&producer) = producers.get(&source) {
+            gates[producer].outputs[0] = out;
+            removed[i] = true;
+        }
+    }
+
+    gates
+        .into_iter()
+        .zip(removed)
+        .filter_map(|(gate, removed)| (!removed).then_some(gate))
+        .collect()
+}
+
+fn remove_dead_gates(gates: Vec<Gate>, output_wires: &[usize]) -> Vec<Gate> {
+    let mut needed = output_wires.iter().copied().collect::<HashSet<_>>();
+    let mut keep = vec![false; gates.len()];
+
+    for (i, gate) in gates.iter().enumerate().rev() {
+        if gate.outputs.iter().any(|out| needed.contains(out)) {
+            keep[i] = true;
+            needed.extend(gate.inputs.iter().copied());
+        }
+    }
+
+    gates
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(gate, keep)| keep.then_some(gate))
+        .collect()
+}
+
+// Closes the gaps left by removed gates, preserving the order of the remaining wires (so the
+// outputs stay at the end)
+fn renumber_wires(
+    mut gates: Vec<Gate>,
+    info: &mut CircuitInfo,
+    wire_count: usize,
+) -> (Vec<Gate>, usize) {
+    let mut used = vec![false; wire_count];
+
+    for io in info.inputs.iter().chain(&info.outputs) {
+        for wire in BristolCircuit::wire_value_range(io) {
+            used[wire] = true;
+        }
+    }
+
+    for constant in &info.constants {
+        used[constant.address] = true;
+    }
+
+    for gate in &gates {
+        for wire in gate.inputs.iter().chain(&gate.outputs) {
+            used[*wire] = true;
+        }
+    }
+
+    let mut new_ids = vec![usize::MAX; wire_count];
+    let mut next_id = 0;
+
+    for (old_id, used) in used.iter().enumerate() {
+        if *used {
+            new_ids[old_id] = next_id;
+            next_id += 1;
+        }
+    }
+
+    for gate in &mut gates {
+        for wire in gate.inputs.iter_mut().chain(gate.outputs.iter_mut()) {
+            *wire = new_ids[*wire];
+        }
+    }
+
+    for io in info.inputs.iter_mut().chain(info.outputs.iter_mut()) {
+        io.address = new_ids[io.address];
+    }
+
+    for constant in &mut info.constants {
+        constant.address = new_ids[constant.address];
+    }
+
+    (gates, next_id)
+}
@@ -0,0 +1,121 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    bool_wire::{BoolData, BoolWire},
+    circuit_input::CircuitInput,
+    id_generator::IdGenerator,
+    value_wire::{ripple_carry_bits, ValueWire},
+};
+
+// A fixed width value with its bits stored inline, for narrow values where the Vec in ValueWire
+// would be the only heap allocation besides the gates themselves. Operations wrap at N bits, like
+// ValueWire's do at the width of their widest operand, and build the same gates, so a circuit can
+// switch between the two (via from_value/to_value) without changing its output.
+#[derive(Clone)]
+pub struct SmallValueWire<const N: usize> {
+    pub id_gen: Rc<RefCell<IdGenerator>>,
+    pub bits: [Rc<BoolWire>; N],
+}
+
+impl<const N: usize> SmallValueWire<N> {
+    // Always LittleEndian (see BitOrder). For another order, use ValueWire::new_input_with_order
+    // and from_value.
+    pub fn new_input(name: &str, id_gen: &Rc<RefCell<IdGenerator>>) -> Self {
+        let circuit_input = Rc::new(CircuitInput {
+            name: name.to_string(),
+            id_start: id_gen.borrow_mut().peek(),
+            size: N,
+        });
+
+        SmallValueWire {
+            id_gen: id_gen.clone(),
+            bits: std::array::from_fn(|_| {
+                Rc::new(BoolWire {
+                    id_gen: id_gen.clone(),
+                    data: BoolData::Input(id_gen.borrow_mut().gen(), circuit_input.clone()),
+                })
+            }),
+        }
+    }
+
+    pub fn new_const(value: usize, id_gen: &Rc<RefCell<IdGenerator>>) -> Self {
+        SmallValueWire {
+            id_gen: id_gen.clone(),
+            bits: std::array::from_fn(|i| {
+                Rc::new(BoolWire {
+                    id_gen: id_gen.clone(),
+                    data: BoolData::Const(i < usize::BITS as usize && (value >> i) & 1 == 1),
+                })
+            }),
+        }
+    }
+
+    // Zero extends or truncates to N bits (silently, since the width is part of the type)
+    pub fn from_value(value: &ValueWire) -> Self {
+        SmallValueWire {
+            id_gen: value.id_gen.clone(),
+            bits: std::array::from_fn(|i| value.at(i)),
+        }
+    }
+
+    pub fn to_value(&self) -> ValueWire {
+        ValueWire {
+            id_gen: self.id_gen.clone(),
+            bits: self.bits.to_vec(),
+        }
+    }
+
+    pub fn at(&self, index: usize) -> Rc<BoolWire> {
+        match self.bits.get(index) {
+            Some(bit) => bit.clone(),
+            None => Rc::new(BoolWire {
+                id_gen: self.id_gen.clone(),
+                data: BoolData::Const(false),
+            }),
+        }
+    }
+
+    pub fn add(a: &Self, b: &Self) -> Self {
+        let mut carry = Rc::new(BoolWire {
+            id_gen: a.id_gen.clone(),
+            data: BoolData::Const(false),
+        });
+
+        let mut sums = ripple_carry_bits(&a.bits, &b.bits, &mut carry);
+
+        SmallValueWire {
+            id_gen: a.id_gen.clone(),
+            bits: std::array::from_fn(|_| sums.next().expect("a sum for each bit")),
+        }
+    }
+
+    pub fn zip_with(
+        a: &Self,
+        b: &Self,
+        f: fn(&Rc<BoolWire>, &Rc<BoolWire>) -> Rc<BoolWire>,
+    ) -> Self {
+        SmallValueWire {
+            id_gen: a.id_gen.clone(),
+            bits: std::array::from_fn(|i| f(&a.bits[i], &b.bits[i])),
+        }
+    }
+
+    pub fn bit_and(a: &Self, b: &Self) -> Self {
+        SmallValueWire::zip_with(a, b, BoolWire::and)
+    }
+
+    pub fn bit_or(a: &Self, b: &Self) -> Self {
+        SmallValueWire::zip_with(a, b, BoolWire::or)
+    }
+
+    pub fn bit_xor(a: &Self, b: &Self) -> Self {
+        SmallValueWire::zip_with(a, b, BoolWire::xor)
+    }
+
+    pub fn bit_not(a: &Self) -> Self {
+        SmallValueWire {
+            id_gen: a.id_gen.clone(),
+            bits: std::array::from_fn(|i| BoolWire::inv(&a.bits[i])),
+        }
+    }
+}
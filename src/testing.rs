@@ -0,0 +1,88 @@
+use bristol_circuit::{BristolCircuit, CircuitInfo, Gate, IOInfo};
+use serde_json::json;
+
+// Generates a random valid boolean circuit. Each input and output is a single bit, gates only
+// reference wires computed before them, and the outputs are the last output_count gates.
+pub fn random_circuit(
+    gate_count: usize,
+    input_count: usize,
+    output_count: usize,
+    seed: u64,
+) -> BristolCircuit {
+    assert!(input_count > 0, "random circuit needs at least one input");
+    assert!(
+        output_count <= gate_count,
+        "random circuit needs a gate for each output"
+    );
+
+    let mut rng = SplitMix64::new(seed);
+    let mut gates = Vec::<Gate>::with_capacity(gate_count);
+
+    for i in 0..gate_count {
+        let available = input_count + i;
+
+        let (op, input_len) = match rng.below(3) {
+            0 => ("AND", 2),
+            1 => ("XOR", 2),
+            _ => ("INV", 1),
+        };
+
+        gates.push(Gate {
+            inputs: (0..input_len).map(|_| rng.below(available)).collect(),
+            outputs: vec![available],
+            op: op.to_string(),
+        });
+    }
+
+    let wire_count = input_count + gate_count;
+    let mut info = CircuitInfo::default();
+
+    for i in 0..input_count {
+        info.inputs.push(IOInfo {
+            name: format!("input{}", i),
+            type_: json!("bool"),
+            address: i,
+            width: 1,
+        });
+    }
+
+    for i in 0..output_count {
+        info.outputs.push(IOInfo {
+            name: format!("output{}", i),
+            type_: json!("bool"),
+            address: wire_count - output_count + i,
+            width: 1,
+        });
+    }
+
+    BristolCircuit {
+        wire_count,
+        info,
+        gates,
+    }
+}
+
+// Small deterministic PRNG, so that random circuits are reproducible without extra dependencies
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % (n as u64)) as usize
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    fmt,
+};
+
+use bristol_circuit::{BristolCircuit, CircuitInfo, Gate};
+
+use crate::io_lookup::BristolCircuitExt;
+
+// Reorders gates breadth-first (level by level, keeping the existing order within a level) and
+// renumbers wires in that order, so that every gate's inputs have strictly smaller ids than its
+// outputs. Outputs are numbered at the end, so any output that is also read by another gate is
+// produced on an internal wire first and then copied to the output.
+pub fn topologically_order_gates(
+    mut gates: Vec<Gate>,
+    info: &mut CircuitInfo,
+    mut wire_count: usize,
+) -> (Vec<Gate>, usize) {
+    let output_wires = info
+        .outputs
+        .iter()
+        .flat_map(BristolCircuit::wire_value_range)
+        .collect::<Vec<_>>();
+
+    let output_set = output_wires.iter().copied().collect::<HashSet<_>>();
+
+    let mut read_outputs = gates
+        .iter()
+        .flat_map(|gate| gate.inputs.iter().copied())
+        .filter(|input| output_set.contains(input))
+        .collect::<Vec<_>>();
+
+    read_outputs.sort();
+    read_outputs.dedup();
+
+    let mut internal_wires = HashMap::<usize, usize>::new();
+
+    for wire in read_outputs {
+        internal_wires.insert(wire, wire_count);
+        wire_count += 1;
+    }
+
+    let mut copies = Vec::<Gate>::new();
+
+    for gate in &mut gates {
+        for input in &mut gate.inputs {
+            if let Some(internal) = internal_wires.get(input) {
+                *input = *internal;
+            }
+        }
+
+        for output in &mut gate.outputs {
+            if let Some(internal) = internal_wires.get(output) {
+                copies.push(Gate {
+                    inputs: vec![*internal],
+                    outputs: vec![*output],
+                    op: "COPY".to_string(),
+                });
+
+                *output = *internal;
+            }
+        }
+    }
+
+    gates.extend(copies);
+
+    let (gates_by_level, mut renumbering) = gates_by_level(gates, &output_wires, wire_count);
+
+    let mut result = Vec::<Gate>::new();
+
+    for mut gate in gates_by_level.into_iter().flatten() {
+        for input in &mut gate.inputs {
+            *input = renumbering.new_ids[*input];
+        }
+
+        for output in &mut gate.outputs {
+            *output = renumbering.gate_output(*output);
+        }
+
+        result.push(gate);
+    }
+
+    renumbering.apply_to_info(info);
+
+    (result, renumbering.next_id + output_wires.len())
+}
+
+// New wire ids while gates are being reordered. Leaf wires (inputs and constants) are numbered
+// first, in their existing order, and outputs last, keeping their positions. Internal wires are
+// numbered in between, as the gates producing them are emitted.
+pub(crate) struct Renumbering {
+    pub new_ids: Vec<usize>,
+    pub next_id: usize,
+}
+
+impl Renumbering {
+    pub fn gate_output(&mut self, wire: usize) -> usize {
+        if self.new_ids[wire] == usize::MAX {
+            self.new_ids[wire] = self.next_id;
+            self.next_id += 1;
+        }
+
+        self.new_ids[wire]
+    }
+
+    pub fn apply_to_info(&self, info: &mut CircuitInfo) {
+        for io in info.inputs.iter_mut().chain(info.outputs.iter_mut()) {
+            io.address = self.new_ids[io.address];
+        }
+
+        for constant in &mut info.constants {
+            constant.address = self.new_ids[constant.address];
+        }
+    }
+}
+
+// Buckets gates by level (one more than the highest level of their inputs, with leaf wires at
+// level 0), keeping their order within a level, and numbers the leaf and output wires
+pub(crate) fn gates_by_level(
+    gates: Vec<Gate>,
+    output_wires: &[usize],
+    wire_count: usize,
+) -> (Vec<Vec<Gate>>, Renumbering) {
+    let output_set = output_wires.iter().copied().collect::<HashSet<_>>();
+
+    let mut levels = vec![0; wire_count];
+    let mut produced = vec![false; wire_count];
+
+    for gate in &gates {
+        let level = gate.inputs.iter().map(|i| levels[*i]).max().unwrap_or(0) + 1;
+
+        for output in &gate.outputs {
+            levels[*output] = level;
+            produced[*output] = true;
+        }
+    }
+
+    let mut new_ids = vec![usize::MAX; wire_count];
+    let mut next_id = 0;
+
+    for wire in 0..wire_count {
+        if !produced[wire] && !output_set.contains(&wire) {
+            new_ids[wire] = next_id;
+            next_id += 1;
+        }
+    }
+
+    let internal_count = (0..wire_count)
+        .filter(|wire| produced[*wire] && !output_set.contains(wire))
+        .count();
+
+    for (i, wire) in output_wires.iter().enumerate() {
+        new_ids[*wire] = next_id + internal_count + i;
+    }
+
+    let max_level = levels.iter().copied().max().unwrap_or(0);
+    let mut gates_by_level = vec![Vec::<Gate>::new(); max_level + 1];
+
+    for gate in gates {
+        gates_by_level[levels[gate.outputs[0]]].push(gate);
+    }
+
+    (gates_by_level, Renumbering { new_ids, next_id })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopologicalError {
+    CycleDetected,
+    // A gate reads or writes a wire that isn't below wire_count
+    WireOutOfRange(usize),
+    // The named input or output extends past wire_count
+    IOOutOfRange(String),
+}
+
+impl fmt::Display for TopologicalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopologicalError::CycleDetected => write!(f, "circuit contains a cycle"),
+            TopologicalError::WireOutOfRange(wire) => {
+                write!(f, "gate uses wire {} which is out of range", wire)
+            }
+            TopologicalError::IOOutOfRange(name) => {
+                write!(f, "wires of {} are out of range", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TopologicalError {}
+
+// Kahn's algorithm: a gate is ready once all the gates producing its inputs have been emitted.
+// Wires that no gate produces (inputs and constants) are available from the start. The lowest
+// ready index is emitted first, so an already ordered circuit is left as is.
+pub(crate) fn topological_sort(circuit: &BristolCircuit) -> Result<Vec<usize>, TopologicalError> {
+    // The circuit may come from an external source, so check every wire is in range before using
+    // them as indices
+    for io in circuit.info.inputs.iter().chain(&circuit.info.outputs) {
+        if !matches!(io.address.checked_add(io.width), Some(end) if end <= circuit.wire_count) {
+            return Err(TopologicalError::IOOutOfRange(io.name.clone()));
+        }
+    }
+
+    for gate in &circuit.gates {
+        if let Some(wire) = gate
+            .inputs
+            .iter()
+            .chain(&gate.outputs)
+            .find(|wire| **wire >= circuit.wire_count)
+        {
+            return Err(TopologicalError::WireOutOfRange(*wire));
+        }
+    }
+
+    let mut produced = vec![false; circuit.wire_count];
+
+    for gate in &circuit.gates {
+        for output in &gate.outputs {
+            produced[*output] = true;
+        }
+    }
+
+    let mut consumers = vec![Vec::<usize>::new(); circuit.wire_count];
+    let mut missing_inputs = vec![0; circuit.gates.len()];
+
+    for (i, gate) in circuit.gates.iter().enumerate() {
+        for input in &gate.inputs {
+            if produced[*input] {
+                consumers[*input].push(i);
+                missing_inputs[i] += 1;
+            }
+        }
+    }
+
+    let mut ready = (0..circuit.gates.len())
+        .filter(|i| missing_inputs[*i] == 0)
+        .map(Reverse)
+        .collect::<BinaryHeap<_>>();
+
+    let mut order = Vec::with_capacity(circuit.gates.len());
+
+    while let Some(Reverse(i)) = ready.pop() {
+        order.push(i);
+
+        for output in &circuit.gates[i].outputs {
+            for consumer in &consumers[*output] {
+                missing_inputs[*consumer] -= 1;
+
+                if missing_inputs[*consumer] == 0 {
+                    ready.push(Reverse(*consumer));
+                }
+            }
+        }
+    }
+
+    if order.len() < circuit.gates.len() {
+        return Err(TopologicalError::CycleDetected);
+    }
+
+    Ok(order)
+}
@@ -1,8 +1,14 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    rc::Rc,
+};
 
 use crate::{
-    bool_wire::{BoolData, BoolWire},
-    circuit_input::CircuitInput,
+    adder_strategy::AdderStrategy,
+    bool_wire::{reduce_tree, BoolData, BoolWire},
+    circuit_input::{BitOrder, CircuitInput},
+    circuits::adder_tree,
     id_generator::IdGenerator,
 };
 
@@ -14,6 +20,17 @@ pub struct ValueWire {
 
 impl ValueWire {
     pub fn new_input(name: &str, size: usize, id_gen: &Rc<RefCell<IdGenerator>>) -> Self {
+        ValueWire::new_input_with_order(name, size, BitOrder::LittleEndian, id_gen)
+    }
+
+    // Like new_input, with the bits' ids (and so their wires) in the given order. See BitOrder for
+    // what that means for eval.
+    pub fn new_input_with_order(
+        name: &str,
+        size: usize,
+        bit_order: BitOrder,
+        id_gen: &Rc<RefCell<IdGenerator>>,
+    ) -> Self {
         let circuit_input = Rc::new(CircuitInput {
             name: name.to_string(),
             id_start: id_gen.borrow_mut().peek(),
@@ -29,6 +46,10 @@ impl ValueWire {
             }));
         }
 
+        if bit_order == BitOrder::BigEndian {
+            bits.reverse();
+        }
+
         ValueWire {
             id_gen: id_gen.clone(),
             bits,
@@ -106,26 +127,153 @@ impl ValueWire {
     }
 
     pub fn add(a: &ValueWire, b: &ValueWire) -> ValueWire {
-        let size = std::cmp::max(a.bits.len(), b.bits.len());
-        let mut bits = Vec::with_capacity(size);
+        let no_carry = Rc::new(BoolWire {
+            id_gen: a.id_gen.clone(),
+            data: BoolData::Const(false),
+        });
 
-        let a_bit = a.at(0);
-        let b_bit = b.at(0);
+        ValueWire::add_with_carry(a, b, &no_carry)
+    }
 
-        bits.push(BoolWire::xor(&a_bit, &b_bit));
-        let mut carry = BoolWire::and(&a_bit, &b_bit);
+    // a + b + carry_in, for chaining word-sized adders (the carry out is discarded like in add)
+    pub fn add_with_carry(a: &ValueWire, b: &ValueWire, carry_in: &Rc<BoolWire>) -> ValueWire {
+        ValueWire::ripple_carry(a, b, carry_in).0
+    }
 
-        for i in 1..size {
-            let a_bit = a.at(i);
-            let b_bit = b.at(i);
+    // (a + b truncated to the wider width, carry out)
+    pub fn add_carry(a: &ValueWire, b: &ValueWire) -> (ValueWire, Rc<BoolWire>) {
+        let no_carry = Rc::new(BoolWire {
+            id_gen: a.id_gen.clone(),
+            data: BoolData::Const(false),
+        });
+
+        ValueWire::ripple_carry(a, b, &no_carry)
+    }
+
+    fn ripple_carry(
+        a: &ValueWire,
+        b: &ValueWire,
+        carry_in: &Rc<BoolWire>,
+    ) -> (ValueWire, Rc<BoolWire>) {
+        let mut carry = carry_in.clone();
+        let bits = ripple_carry_bits(&a.bits, &b.bits, &mut carry).collect();
+
+        let sum = ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits,
+        };
+
+        (sum, carry)
+    }
+
+    pub fn add_with(a: &ValueWire, b: &ValueWire, strategy: AdderStrategy) -> ValueWire {
+        match strategy {
+            AdderStrategy::RippleCarry => ValueWire::add(a, b),
+            AdderStrategy::CarryLookahead => lookahead_add(a, b, 4),
+            AdderStrategy::KoggeStone | AdderStrategy::Sklansky | AdderStrategy::BrentKung => {
+                prefix_add(a, b, strategy)
+            }
+        }
+    }
+
+    // Same as add_with(a, b, AdderStrategy::KoggeStone), the parallel prefix adder with the lowest
+    // depth (log2(width) levels of generate/propagate combining)
+    pub fn add_cla(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        ValueWire::add_with(a, b, AdderStrategy::KoggeStone)
+    }
+
+    pub fn carry_skip_adder(a: &ValueWire, b: &ValueWire, block_size: usize) -> ValueWire {
+        assert!(block_size > 0, "block_size must be positive");
+
+        let gp = generate_propagate(a, b);
+        let mut bits = Vec::with_capacity(gp.len());
+
+        let zero = Rc::new(BoolWire {
+            id_gen: a.id_gen.clone(),
+            data: BoolData::Const(false),
+        });
+
+        let mut carry = zero.clone();
+
+        for block in gp.chunks(block_size) {
+            // The sums ripple from the actual carry in
+            bits.extend(ripple_block(block, &carry).0);
+
+            // The carry out of the block's own ripple (from a carry in of zero) doesn't wait for
+            // the carry in, and is the carry out unless every bit propagates
+            let ripple_carry = ripple_block(block, &zero).1;
+
+            let propagate = block
+                .iter()
+                .skip(1)
+                .fold(block[0].1.clone(), |acc, (_, p)| BoolWire::and(&acc, p));
+
+            // The skip: mux(propagate, carry, ripple_carry) = ripple_carry ^ (propagate &
+            // (carry ^ ripple_carry))
+            carry = BoolWire::xor(
+                &ripple_carry,
+                &BoolWire::and(&propagate, &BoolWire::xor(&carry, &ripple_carry)),
+            );
+        }
+
+        ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits,
+        }
+    }
+
+    pub fn add_carry_select(a: &ValueWire, b: &ValueWire, block_size: usize) -> ValueWire {
+        assert!(block_size > 0, "block_size must be positive");
+
+        let gp = generate_propagate(a, b);
+        let mut bits = Vec::with_capacity(gp.len());
+
+        let mut carry = Rc::new(BoolWire {
+            id_gen: a.id_gen.clone(),
+            data: BoolData::Const(false),
+        });
+
+        let zero = carry.clone();
+
+        // Each block after the second is one bit longer than the one before, since the carry
+        // reaching it has been through one more select, so the longer ripple is ready at about the
+        // same time (the square root carry select)
+        let mut blocks = Vec::new();
+        let mut rest = &gp[..];
+        let mut size = block_size;
+
+        while !rest.is_empty() {
+            let (block, tail) = rest.split_at(size.min(rest.len()));
+            blocks.push(block);
+            rest = tail;
+
+            if blocks.len() > 1 {
+                size += 1;
+            }
+        }
+
+        for (i, block) in blocks.into_iter().enumerate() {
+            if i == 0 {
+                // The carry in of the first block is known to be zero
+                let (sums, carry_out) = ripple_block(block, &carry);
+                bits.extend(sums);
+                carry = carry_out;
+                continue;
+            }
+
+            let (sums0, carry0) = ripple_block(block, &zero);
+            let (sums1, carry1) = ripple_block(block, &BoolWire::inv(&zero));
 
-            let sum = BoolWire::xor(&a_bit, &b_bit);
+            // mux(carry, x1, x0) = x0 ^ (carry & (x1 ^ x0))
+            let select = |x0: &Rc<BoolWire>, x1: &Rc<BoolWire>| {
+                BoolWire::xor(x0, &BoolWire::and(&carry, &BoolWire::xor(x1, x0)))
+            };
 
-            let new_carry =
-                BoolWire::xor(&BoolWire::and(&a_bit, &b_bit), &BoolWire::and(&carry, &sum));
+            for (sum0, sum1) in sums0.iter().zip(sums1.iter()) {
+                bits.push(select(sum0, sum1));
+            }
 
-            bits.push(BoolWire::xor(&sum, &carry));
-            carry = new_carry;
+            carry = select(&carry0, &carry1);
         }
 
         ValueWire {
@@ -150,7 +298,23 @@ impl ValueWire {
         )
     }
 
+    #[track_caller]
     pub fn resize(&self, size: usize) -> ValueWire {
+        #[cfg(debug_assertions)]
+        if size < self.bits.len() {
+            eprintln!(
+                "warning: resize truncates {} bits to {} at {} (use truncate_silent if intended)",
+                self.bits.len(),
+                size,
+                std::panic::Location::caller(),
+            );
+        }
+
+        self.truncate_silent(size)
+    }
+
+    // Same as resize, but without the debug build warning when bits are dropped
+    pub fn truncate_silent(&self, size: usize) -> ValueWire {
         if size == self.bits.len() {
             return self.clone();
         }
@@ -161,6 +325,27 @@ impl ValueWire {
         }
     }
 
+    // Like resize, but for two's complement values, so the added bits are copies of the sign bit
+    pub fn sign_extend(a: &ValueWire, new_size: usize) -> ValueWire {
+        assert!(
+            new_size >= a.bits.len(),
+            "error: sign_extend would truncate {} bits to {}",
+            a.bits.len(),
+            new_size
+        );
+
+        let Some(sign) = a.bits.last() else {
+            return a.resize(new_size);
+        };
+
+        ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits: (0..new_size)
+                .map(|i| a.bits.get(i).unwrap_or(sign).clone())
+                .collect(),
+        }
+    }
+
     pub fn sub(a: &ValueWire, b: &ValueWire) -> ValueWire {
         let neg_b = if b.bits.len() < a.bits.len() {
             ValueWire::negate(&b.resize(a.bits.len()))
@@ -171,6 +356,168 @@ impl ValueWire {
         ValueWire::add(a, &neg_b)
     }
 
+    // a + constant (wrapping at the width of a). Adding 2^k leaves the bits below k alone and only
+    // needs a half adder chain above it, rather than a full adder per bit.
+    pub fn add_const(a: &ValueWire, constant: usize) -> ValueWire {
+        let size = a.bits.len();
+
+        if size < usize::BITS as usize && constant >> size != 0 {
+            return ValueWire::add_const(a, constant & ((1 << size) - 1));
+        }
+
+        if constant == 0 {
+            return a.clone();
+        }
+
+        if !constant.is_power_of_two() {
+            return ValueWire::add(a, &ValueWire::new_const(constant, &a.id_gen).resize(size));
+        }
+
+        let mut bits = a.bits.clone();
+        let mut carry = ValueWire::new_const(1, &a.id_gen).at(0);
+
+        for bit in bits.iter_mut().skip(constant.trailing_zeros() as usize) {
+            let sum = BoolWire::xor(bit, &carry);
+            carry = BoolWire::and(bit, &carry);
+            *bit = sum;
+        }
+
+        ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits,
+        }
+    }
+
+    // a - constant (wrapping at the width of a). Subtracting 2^k only needs a borrow chain from bit
+    // k, and when the two's complement of the constant is a power of two it's cheaper to add that.
+    pub fn sub_const(a: &ValueWire, constant: usize) -> ValueWire {
+        let size = a.bits.len();
+
+        let mask = match size < usize::BITS as usize {
+            true => (1 << size) - 1,
+            false => usize::MAX,
+        };
+
+        let constant = constant & mask;
+        let complement = constant.wrapping_neg() & mask;
+
+        if constant == 0 {
+            return a.clone();
+        }
+
+        if complement.is_power_of_two() {
+            return ValueWire::add_const(a, complement);
+        }
+
+        if !constant.is_power_of_two() {
+            return ValueWire::sub(a, &ValueWire::new_const(constant, &a.id_gen));
+        }
+
+        let mut bits = a.bits.clone();
+        let mut borrow = ValueWire::new_const(1, &a.id_gen).at(0);
+
+        for bit in bits.iter_mut().skip(constant.trailing_zeros() as usize) {
+            let diff = BoolWire::xor(bit, &borrow);
+            borrow = BoolWire::and(&BoolWire::inv(bit), &borrow);
+            *bit = diff;
+        }
+
+        ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits,
+        }
+    }
+
+    // (a - b, borrow), where the borrow is set when a < b (unsigned). Computed as a + !b + 1, so the
+    // borrow is the inverse of the carry out.
+    pub fn sub_with_overflow(a: &ValueWire, b: &ValueWire) -> (ValueWire, Rc<BoolWire>) {
+        let size = std::cmp::max(a.bits.len(), b.bits.len());
+
+        let carry_in = Rc::new(BoolWire {
+            id_gen: a.id_gen.clone(),
+            data: BoolData::Const(true),
+        });
+
+        let (diff, carry) = ValueWire::ripple_carry(
+            &a.resize(size),
+            &ValueWire::bit_not(&b.resize(size)),
+            &carry_in,
+        );
+
+        (diff, BoolWire::inv(&carry))
+    }
+
+    // (a - b truncated to the wider width, borrow out), the subtraction counterpart of add_carry
+    pub fn sub_borrow(a: &ValueWire, b: &ValueWire) -> (ValueWire, Rc<BoolWire>) {
+        ValueWire::sub_with_overflow(a, b)
+    }
+
+    // Set when a + b overflows as two's complement values, ie when the carries into and out of the
+    // top bit differ. The carry in is recovered from the sum bit, so this only adds XOR gates to
+    // add_carry.
+    pub fn overflow_add(a: &ValueWire, b: &ValueWire) -> Rc<BoolWire> {
+        let size = std::cmp::max(a.bits.len(), b.bits.len());
+
+        if size == 0 {
+            return Rc::new(BoolWire {
+                id_gen: a.id_gen.clone(),
+                data: BoolData::Const(false),
+            });
+        }
+
+        let (sum, carry_out) = ValueWire::add_carry(a, b);
+        let top = size - 1;
+
+        let carry_in = BoolWire::xor(&sum.at(top), &BoolWire::xor(&a.at(top), &b.at(top)));
+
+        BoolWire::xor(&carry_in, &carry_out)
+    }
+
+    // a + b, or all ones if it overflows the wider width
+    pub fn saturating_add(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        let (sum, carry) = ValueWire::add_carry(a, b);
+
+        ValueWire {
+            id_gen: sum.id_gen.clone(),
+            bits: sum
+                .bits
+                .iter()
+                .map(|bit| BoolWire::or(bit, &carry))
+                .collect(),
+        }
+    }
+
+    // a - b, or 0 if b > a
+    pub fn saturating_sub(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        let (diff, borrow) = ValueWire::sub_with_overflow(a, b);
+
+        ValueWire::conditional_zero(&diff, &BoolWire::inv(&borrow))
+    }
+
+    // |a - b| with a and b as two's complement values. The difference is taken one bit wider so it
+    // can't overflow, and its magnitude always fits the original (unsigned) width.
+    pub fn signed_abs_diff(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        let size = std::cmp::max(a.bits.len(), b.bits.len());
+
+        let diff = ValueWire::sub(
+            &ValueWire::sign_extend(a, size + 1),
+            &ValueWire::sign_extend(b, size + 1),
+        );
+        let sign = diff.at(size);
+
+        // abs(d) = (d ^ sign) + sign
+        let flipped = ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits: diff
+                .bits
+                .iter()
+                .map(|bit| BoolWire::xor(bit, &sign))
+                .collect(),
+        };
+
+        ValueWire::add(&flipped, &BoolWire::as_value(&sign)).truncate_silent(size)
+    }
+
     pub fn shift_up_const(&self, amount: usize) -> ValueWire {
         if amount >= self.bits.len() {
             return ValueWire::new_const(0, &self.id_gen);
@@ -195,6 +542,80 @@ impl ValueWire {
         }
     }
 
+    // Rotation within the current width just reorders the bits, so it needs no gates
+    pub fn rotate_right(&self, amount: usize) -> ValueWire {
+        let mut bits = self.bits.clone();
+
+        if !bits.is_empty() {
+            bits.rotate_left(amount % self.bits.len());
+        }
+
+        ValueWire {
+            id_gen: self.id_gen.clone(),
+            bits,
+        }
+    }
+
+    pub fn rotate_left(&self, amount: usize) -> ValueWire {
+        let mut bits = self.bits.clone();
+
+        if !bits.is_empty() {
+            bits.rotate_right(amount % self.bits.len());
+        }
+
+        ValueWire {
+            id_gen: self.id_gen.clone(),
+            bits,
+        }
+    }
+
+    // Like shift_down_const, but the vacated high bits are copies of the sign bit
+    pub fn arith_shift_down_const(&self, amount: usize) -> ValueWire {
+        let Some(sign) = self.bits.last() else {
+            return self.clone();
+        };
+
+        let amount = std::cmp::min(amount, self.bits.len());
+
+        ValueWire {
+            id_gen: self.id_gen.clone(),
+            bits: self.bits[amount..]
+                .iter()
+                .cloned()
+                .chain(std::iter::repeat_n(sign.clone(), amount))
+                .collect(),
+        }
+    }
+
+    // Barrel shifter: amount bit i conditionally rotates by 2^i (mod the width), so it takes one
+    // mux layer (width AND gates) per amount bit
+    pub fn rotate_left_var(a: &ValueWire, amount: &ValueWire) -> ValueWire {
+        let mut result = a.clone();
+
+        if a.bits.is_empty() {
+            return result;
+        }
+
+        let mut step = 1 % a.bits.len();
+
+        for bit in &amount.bits {
+            if step != 0 {
+                result = ValueWire::select(bit, &result.rotate_left(step), &result);
+            }
+
+            step = (2 * step) % a.bits.len();
+        }
+
+        result
+    }
+
+    pub fn rotate_right_var(a: &ValueWire, amount: &ValueWire) -> ValueWire {
+        ValueWire::bit_reverse(&ValueWire::rotate_left_var(
+            &ValueWire::bit_reverse(a),
+            amount,
+        ))
+    }
+
     pub fn shift_down_const(&self, amount: usize) -> ValueWire {
         if amount >= self.bits.len() {
             return ValueWire::new_const(0, &self.id_gen);
@@ -219,6 +640,40 @@ impl ValueWire {
         }
     }
 
+    pub fn bit_interleave(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        let size = std::cmp::max(a.bits.len(), b.bits.len());
+        let mut bits = Vec::with_capacity(2 * size);
+
+        for i in 0..size {
+            bits.push(a.at(i));
+            bits.push(b.at(i));
+        }
+
+        ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits,
+        }
+    }
+
+    pub fn zip(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        ValueWire::bit_interleave(a, b)
+    }
+
+    // (even bits, odd bits)
+    pub fn unzip(a: &ValueWire) -> (ValueWire, ValueWire) {
+        let even = ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits: a.bits.iter().step_by(2).cloned().collect(),
+        };
+
+        let odd = ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits: a.bits.iter().skip(1).step_by(2).cloned().collect(),
+        };
+
+        (even, odd)
+    }
+
     pub fn mul_bool(a: &Rc<BoolWire>, b: &ValueWire) -> ValueWire {
         let mut bits = Vec::with_capacity(b.bits.len());
 
@@ -232,64 +687,307 @@ impl ValueWire {
         }
     }
 
-    pub fn mul(a: &ValueWire, b: &ValueWire) -> ValueWire {
-        let (sm, lg) = if a.bits.len() <= b.bits.len() {
-            (a, b)
-        } else {
-            (b, a)
-        };
+    // a if condition is set, otherwise 0 (the same as mul_bool, named for masking)
+    pub fn conditional_zero(a: &ValueWire, condition: &Rc<BoolWire>) -> ValueWire {
+        match &condition.data {
+            BoolData::Const(false) => ValueWire::new_const(0, &a.id_gen).resize(a.bits.len()),
+            BoolData::Const(true) => a.clone(),
+            _ => ValueWire::mul_bool(condition, a),
+        }
+    }
 
-        let mut sum_terms = Vec::new();
+    // Sums values[i] for each bits[i] that is set, so a one-hot bits selects a single value
+    pub fn mul_bool_tree(bits: &[Rc<BoolWire>], values: &[ValueWire]) -> ValueWire {
+        assert_eq!(bits.len(), values.len());
 
-        for i in 0..sm.bits.len() {
-            if let BoolData::Const(false) = sm.bits[i].data {
-                continue;
-            }
+        let id_gen = &values.first().expect("error: no values").id_gen;
 
-            let term = ValueWire::mul_bool(&sm.bits[i], &lg.shift_up_const(i));
-            sum_terms.push(term);
-        }
+        let products = bits
+            .iter()
+            .zip(values)
+            .map(|(bit, value)| ValueWire::mul_bool(bit, value))
+            .collect::<Vec<_>>();
 
-        tree_sum(&sum_terms, &a.id_gen)
+        ValueWire::tree_sum(&products, id_gen)
     }
 
-    pub fn exp(a: &ValueWire, b: &ValueWire) -> ValueWire {
-        match b.as_usize() {
-            Some(n) => {
-                if n == 0 {
-                    // Base case: any number to the power of 0 is 1.
-                    return ValueWire::new_const(1, &a.id_gen);
-                } else if n == 1 {
-                    return a.clone();
-                } else if n % 2 == 0 {
-                    // If n is even, compute (a * a)^(n / 2).
-                    let half = ValueWire::mul(a, a);
-
-                    return ValueWire::exp(&half, &ValueWire::new_const(n / 2, &a.id_gen));
-                } else {
-                    // If n is odd: compute a * (a * a)^((n - 1) / 2).
-                    let half = ValueWire::mul(a, a);
-                    let reduced =
-                        ValueWire::exp(&half, &ValueWire::new_const((n - 1) / 2, &a.id_gen));
+    // Sums with a balanced tree of adds. Like add, the result wraps at the widest input's width.
+    pub fn tree_sum(values: &[ValueWire], id_gen: &Rc<RefCell<IdGenerator>>) -> ValueWire {
+        if values.is_empty() {
+            ValueWire::new_const(0, id_gen)
+        } else if values.len() == 1 {
+            values[0].clone()
+        } else {
+            let mid = values.len() / 2;
+            let left = ValueWire::tree_sum(&values[..mid], id_gen);
+            let right = ValueWire::tree_sum(&values[mid..], id_gen);
 
-                    return ValueWire::mul(a, &reduced);
-                }
-            }
-            None => panic!("Wire 'b' is not a constant"),
+            ValueWire::add(&left, &right)
         }
     }
 
-    fn split_at(&self, split_point: usize) -> (ValueWire, ValueWire) {
-        if self.bits.len() <= split_point {
-            return (self.clone(), ValueWire::new_const(0, &self.id_gen));
-        }
+    // Number of set bits, as a ceil(log2(width + 1)) bit value. Each bit is zero-extended to the
+    // full count width before the tree_sum, but the constant zeros fold away so the adders near
+    // the leaves stay small.
+    pub fn popcount(&self) -> ValueWire {
+        let width = (usize::BITS - self.bits.len().leading_zeros()) as usize;
 
-        let left = ValueWire {
-            id_gen: self.id_gen.clone(),
-            bits: self.bits[..split_point].to_vec(),
-        };
+        let values = self
+            .bits
+            .iter()
+            .map(|bit| BoolWire::as_value(bit).resize(width))
+            .collect::<Vec<_>>();
 
-        let right = ValueWire {
+        ValueWire::tree_sum(&values, &self.id_gen).truncate_silent(width)
+    }
+
+    // Number of zero bits above the highest set bit (the full width if a is zero), as a
+    // ceil(log2(width + 1)) bit value
+    pub fn clz(&self) -> ValueWire {
+        let width = (usize::BITS - self.bits.len().leading_zeros()) as usize;
+
+        if self.bits.is_empty() {
+            return ValueWire::new_const(0, &self.id_gen);
+        }
+
+        count_leading_zeros(&self.bits).1.resize(width)
+    }
+
+    // Number of zero bits below the lowest set bit (the full width if a is zero)
+    pub fn ctz(&self) -> ValueWire {
+        ValueWire::bit_reverse(self).clz()
+    }
+
+    // Counts the set bits with a carry-save adder tree, wide enough that the count can't wrap
+    pub fn count_ones_bool(
+        bits: impl Iterator<Item = Rc<BoolWire>>,
+        id_gen: &Rc<RefCell<IdGenerator>>,
+    ) -> ValueWire {
+        let bits = bits.collect::<Vec<_>>();
+
+        if bits.is_empty() {
+            return ValueWire::new_const(0, id_gen);
+        }
+
+        let width = (usize::BITS - bits.len().leading_zeros()) as usize;
+
+        let values = bits
+            .iter()
+            .map(|bit| BoolWire::as_value(bit).resize(width))
+            .collect::<Vec<_>>();
+
+        adder_tree::sum(&values)
+    }
+
+    // BoolWire::mux on each bit, ie f ^ (c & (t ^ f))
+    pub fn mux(condition: &Rc<BoolWire>, if_true: &ValueWire, if_false: &ValueWire) -> ValueWire {
+        let size = std::cmp::max(if_true.bits.len(), if_false.bits.len());
+
+        ValueWire {
+            id_gen: if_false.id_gen.clone(),
+            bits: (0..size)
+                .map(|i| BoolWire::mux(condition, &if_true.at(i), &if_false.at(i)))
+                .collect(),
+        }
+    }
+
+    // if cond { a } else { b }, with the shorter operand zero-extended. Equivalent to
+    // (cond & a) | (!cond & b) per bit, but built as a mux so it needs one AND per bit rather than
+    // three.
+    pub fn select(cond: &Rc<BoolWire>, a: &ValueWire, b: &ValueWire) -> ValueWire {
+        ValueWire::mux(cond, a, b)
+    }
+
+    // Returns (b, a) if condition is set, otherwise (a, b). Both outputs share c & (a ^ b).
+    pub fn conditional_swap(
+        a: &ValueWire,
+        b: &ValueWire,
+        condition: &Rc<BoolWire>,
+    ) -> (ValueWire, ValueWire) {
+        let diff = ValueWire::mul_bool(condition, &ValueWire::bit_xor(a, b));
+
+        (ValueWire::bit_xor(a, &diff), ValueWire::bit_xor(b, &diff))
+    }
+
+    // matrix[i][j] = a[i] & b[j], which has weight 2^(i + j) in the product
+    pub fn partial_product_matrix(a: &ValueWire, b: &ValueWire) -> Vec<Vec<Rc<BoolWire>>> {
+        a.bits
+            .iter()
+            .map(|a_bit| {
+                b.bits
+                    .iter()
+                    .map(|b_bit| BoolWire::and(a_bit, b_bit))
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn mul(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        ValueWire::tree_sum(&partial_products(a, b), &a.id_gen)
+    }
+
+    // Same product as mul, but the partial products are reduced with a carry-save (Wallace) tree
+    // and a Kogge-Stone final adder, so the depth is logarithmic in the width rather than linear
+    pub fn mul_wallace(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        let partial_products = partial_products(a, b);
+
+        if partial_products.is_empty() {
+            let size = std::cmp::max(a.bits.len(), b.bits.len());
+            return ValueWire::new_const(0, &a.id_gen).resize(size);
+        }
+
+        adder_tree::sum_with(&partial_products, AdderStrategy::KoggeStone)
+    }
+
+    // a * k (wrapping at the width of a) using shifts, adds and subtracts only. k is recoded into
+    // non-adjacent form, so runs of set bits become one add and one subtract (eg a * 7 is
+    // (a << 3) - a), and a power of two needs no gates at all.
+    pub fn mul_const_optimized(a: &ValueWire, k: usize) -> ValueWire {
+        let size = a.bits.len();
+        let mut add_terms = Vec::new();
+        let mut sub_terms = Vec::new();
+
+        let mut rest = k as u128;
+        let mut shift = 0;
+
+        while rest != 0 && shift < size {
+            if rest & 1 == 1 {
+                // Digit is +1 if rest % 4 == 1, otherwise -1 (and the borrow moves up)
+                if rest & 3 == 1 {
+                    add_terms.push(a.shift_up_const(shift));
+                    rest -= 1;
+                } else {
+                    sub_terms.push(a.shift_up_const(shift));
+                    rest += 1;
+                }
+            }
+
+            rest >>= 1;
+            shift += 1;
+        }
+
+        let added = ValueWire::tree_sum(&add_terms, &a.id_gen).resize(size);
+
+        if sub_terms.is_empty() {
+            return added;
+        }
+
+        ValueWire::sub(
+            &added,
+            &ValueWire::tree_sum(&sub_terms, &a.id_gen).resize(size),
+        )
+    }
+
+    // The full a.len + b.len bit product (unlike mul, which wraps at the wider width). Each level
+    // splits both operands in half and uses three half-size products, (a0 + a1)(b0 + b1), a0b0 and
+    // a1b1, instead of four. Karatsuba doesn't help a wrapping product, since that never needs a1b1.
+    pub fn karatsuba_mul(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        let size = std::cmp::max(a.bits.len(), b.bits.len());
+
+        karatsuba_full(&a.resize(size), &b.resize(size))
+            .truncate_silent(a.bits.len() + b.bits.len())
+    }
+
+    // a * b + c, with c added to the partial products as one more row so there's a single
+    // reduction instead of a multiply followed by an add. For 8 bits this is 64 AND gates vs 85 for
+    // add(mul(a, b), c) (32 bits: 1024 vs 1489), with the same total (170, 32 bits: 2978). The
+    // totals can't differ while both are made of full and half adders, since the number of each is
+    // fixed by how many bits land in each column.
+    pub fn mul_add(a: &ValueWire, b: &ValueWire, c: &ValueWire) -> ValueWire {
+        let mut sum_terms = vec![c.clone()];
+        sum_terms.extend(partial_products(a, b));
+
+        column_sum(&sum_terms)
+    }
+
+    pub fn exp(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        match b.as_usize() {
+            Some(n) => {
+                // 0^n = 0 and 1^n = 1 for n > 0 (0^0 is 1 below)
+                if let Some(base @ (0 | 1)) = a.as_usize().filter(|_| n > 0) {
+                    return ValueWire::new_const(base, &a.id_gen).resize(a.bits.len());
+                }
+
+                if n == 0 {
+                    // Base case: any number to the power of 0 is 1.
+                    return ValueWire::new_const(1, &a.id_gen);
+                } else if n == 1 {
+                    return a.clone();
+                } else if n % 2 == 0 {
+                    // If n is even, compute (a * a)^(n / 2).
+                    let half = ValueWire::mul(a, a);
+
+                    return ValueWire::exp(&half, &ValueWire::new_const(n / 2, &a.id_gen));
+                } else {
+                    // If n is odd: compute a * (a * a)^((n - 1) / 2).
+                    let half = ValueWire::mul(a, a);
+                    let reduced =
+                        ValueWire::exp(&half, &ValueWire::new_const((n - 1) / 2, &a.id_gen));
+
+                    return ValueWire::mul(a, &reduced);
+                }
+            }
+            None => panic!("Wire 'b' is not a constant"),
+        }
+    }
+
+    // low in the least significant bits, followed by high (no gates needed)
+    pub fn concat(low: &ValueWire, high: &ValueWire) -> ValueWire {
+        ValueWire {
+            id_gen: low.id_gen.clone(),
+            bits: low.bits.iter().chain(&high.bits).cloned().collect(),
+        }
+    }
+
+    // Bits start..end of a (no gates needed)
+    pub fn slice(a: &ValueWire, start: usize, end: usize) -> ValueWire {
+        assert!(
+            start <= end && end <= a.bits.len(),
+            "error: slice {}..{} out of range for {} bits",
+            start,
+            end,
+            a.bits.len()
+        );
+
+        ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits: a.bits[start..end].to_vec(),
+        }
+    }
+
+    // Reverses the byte order, keeping the bits within each byte (no gates needed)
+    pub fn byte_swap(a: &ValueWire) -> ValueWire {
+        assert!(
+            a.bits.len().is_multiple_of(8),
+            "error: byte_swap needs a multiple of 8 bits, got {}",
+            a.bits.len()
+        );
+
+        ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits: a.bits.chunks(8).rev().flatten().cloned().collect(),
+        }
+    }
+
+    // Bit i of the result is bit (width - 1 - i) of a (no gates needed)
+    pub fn bit_reverse(a: &ValueWire) -> ValueWire {
+        ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits: a.bits.iter().rev().cloned().collect(),
+        }
+    }
+
+    fn split_at(&self, split_point: usize) -> (ValueWire, ValueWire) {
+        if self.bits.len() <= split_point {
+            return (self.clone(), ValueWire::new_const(0, &self.id_gen));
+        }
+
+        let left = ValueWire {
+            id_gen: self.id_gen.clone(),
+            bits: self.bits[..split_point].to_vec(),
+        };
+
+        let right = ValueWire {
             id_gen: self.id_gen.clone(),
             bits: self.bits[split_point..].to_vec(),
         };
@@ -333,6 +1031,33 @@ impl ValueWire {
         (eq, lt)
     }
 
+    // eq, lt
+    // Linear depth alternative to cmp. Each bit that differs overrides the result from the bits
+    // below it, so the final result is decided by the most significant differing bit.
+    pub fn cmp_ripple(a: &ValueWire, b: &ValueWire) -> (Rc<BoolWire>, Rc<BoolWire>) {
+        let size = std::cmp::max(a.bits.len(), b.bits.len());
+
+        let mut eq = Rc::new(BoolWire {
+            id_gen: a.id_gen.clone(),
+            data: BoolData::Const(true),
+        });
+
+        let mut lt = Rc::new(BoolWire {
+            id_gen: a.id_gen.clone(),
+            data: BoolData::Const(false),
+        });
+
+        for i in 0..size {
+            let b_bit = b.at(i);
+            let diff = BoolWire::xor(&a.at(i), &b_bit);
+
+            lt = BoolWire::xor(&lt, &BoolWire::and(&diff, &BoolWire::xor(&b_bit, &lt)));
+            eq = BoolWire::and(&eq, &BoolWire::inv(&diff));
+        }
+
+        (eq, lt)
+    }
+
     pub fn less_than(a: &ValueWire, b: &ValueWire) -> Rc<BoolWire> {
         let (_eq, lt) = ValueWire::cmp(a, b);
 
@@ -351,10 +1076,41 @@ impl ValueWire {
         BoolWire::inv(&ValueWire::less_than(a, b))
     }
 
+    pub fn min(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        ValueWire::select(&ValueWire::less_than(a, b), a, b)
+    }
+
+    pub fn max(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        ValueWire::select(&ValueWire::greater_than(a, b), a, b)
+    }
+
+    // Reduces pairwise like tree_sum, so the depth is log2(n) comparisons rather than n
+    pub fn min_array(values: &[ValueWire]) -> ValueWire {
+        assert!(!values.is_empty(), "error: no values");
+        tree_reduce(values, ValueWire::min)
+    }
+
+    pub fn max_array(values: &[ValueWire]) -> ValueWire {
+        assert!(!values.is_empty(), "error: no values");
+        tree_reduce(values, ValueWire::max)
+    }
+
     pub fn equal(a: &ValueWire, b: &ValueWire) -> Rc<BoolWire> {
+        if let Some(constant) = b.as_usize() {
+            return ValueWire::equal_const(a, constant);
+        }
+
+        if let Some(constant) = a.as_usize() {
+            return ValueWire::equal_const(b, constant);
+        }
+
         let size = std::cmp::max(a.bits.len(), b.bits.len());
 
-        if size == 0 {
+        // Comparing a wire against itself
+        let same_bits = a.bits.len() == b.bits.len()
+            && a.bits.iter().zip(&b.bits).all(|(x, y)| Rc::ptr_eq(x, y));
+
+        if size == 0 || same_bits {
             return Rc::new(BoolWire {
                 id_gen: a.id_gen.clone(),
                 data: BoolData::Const(true),
@@ -374,25 +1130,54 @@ impl ValueWire {
         BoolWire::and(&eq0, &eq1)
     }
 
+    pub fn equal_const(a: &ValueWire, constant: usize) -> Rc<BoolWire> {
+        let const_size = (usize::BITS - constant.leading_zeros()) as usize;
+        let size = std::cmp::max(a.bits.len(), const_size);
+
+        // Each bit matches if it is equal to the constant bit, which is either the bit itself or
+        // its inversion
+        let matches = (0..size)
+            .map(|i| match (constant >> i) & 1 == 1 {
+                true => a.at(i),
+                false => BoolWire::inv(&a.at(i)),
+            })
+            .collect::<Vec<_>>();
+
+        reduce_tree(&matches, &a.id_gen, true, BoolWire::and)
+    }
+
+    // All ones if a == b, otherwise all zeros
+    pub fn eq_mask(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        let width = std::cmp::max(a.bits.len(), b.bits.len());
+
+        ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits: vec![ValueWire::equal(a, b); width],
+        }
+    }
+
     pub fn not_equal(a: &ValueWire, b: &ValueWire) -> Rc<BoolWire> {
         BoolWire::inv(&ValueWire::equal(a, b))
     }
 
     pub fn to_bool(&self) -> Rc<BoolWire> {
-        if self.bits.len() == 0 {
-            return Rc::new(BoolWire {
-                id_gen: self.id_gen.clone(),
-                data: BoolData::Const(false),
-            });
-        }
+        reduce_tree(&self.bits, &self.id_gen, false, BoolWire::or)
+    }
 
-        if self.bits.len() == 1 {
-            return self.bits[0].clone();
-        }
+    // The deepest of the bits (see BoolWire::depth)
+    pub fn depth(&self) -> usize {
+        let mut depths = HashMap::new();
 
-        let (left, right) = self.split_at(self.bits.len() / 2);
+        self.bits
+            .iter()
+            .map(|bit| BoolWire::depth_memo(bit, &mut depths))
+            .max()
+            .unwrap_or(0)
+    }
 
-        BoolWire::or(&left.to_bool(), &right.to_bool())
+    // Same as to_bool (a balanced OR tree), named to distinguish it from other reductions
+    pub fn to_bool_tree(a: &ValueWire) -> Rc<BoolWire> {
+        a.to_bool()
     }
 
     pub fn bool_and(a: &ValueWire, b: &ValueWire) -> Rc<BoolWire> {
@@ -411,37 +1196,54 @@ impl ValueWire {
         BoolWire::xor(&a.to_bool(), &b.to_bool())
     }
 
-    pub fn bit_and(a: &ValueWire, b: &ValueWire) -> ValueWire {
-        let size = std::cmp::max(a.bits.len(), b.bits.len());
+    // Exactly one bit set, ie a != 0 && (a & (a - 1)) == 0 (so 0 is not a power of two)
+    pub fn is_power_of_two(a: &ValueWire) -> Rc<BoolWire> {
+        let a_minus_1 = ValueWire::sub(a, &ValueWire::new_const(1, &a.id_gen));
+        let masked = ValueWire::bit_and(a, &a_minus_1);
 
-        ValueWire {
-            id_gen: a.id_gen.clone(),
-            bits: (0..size)
-                .map(|i| BoolWire::and(&a.at(i), &b.at(i)))
-                .collect(),
-        }
+        BoolWire::and(&a.to_bool(), &ValueWire::bool_not(&masked))
     }
 
-    pub fn bit_or(a: &ValueWire, b: &ValueWire) -> ValueWire {
+    pub fn zip_with(
+        a: &ValueWire,
+        b: &ValueWire,
+        f: fn(&Rc<BoolWire>, &Rc<BoolWire>) -> Rc<BoolWire>,
+    ) -> ValueWire {
         let size = std::cmp::max(a.bits.len(), b.bits.len());
 
         ValueWire {
             id_gen: a.id_gen.clone(),
-            bits: (0..size)
-                .map(|i| BoolWire::or(&a.at(i), &b.at(i)))
-                .collect(),
+            bits: (0..size).map(|i| f(&a.at(i), &b.at(i))).collect(),
         }
     }
 
+    pub fn bit_and(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        ValueWire::zip_with(a, b, BoolWire::and)
+    }
+
+    pub fn bit_or(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        ValueWire::zip_with(a, b, BoolWire::or)
+    }
+
     pub fn bit_xor(a: &ValueWire, b: &ValueWire) -> ValueWire {
-        let size = std::cmp::max(a.bits.len(), b.bits.len());
+        ValueWire::zip_with(a, b, BoolWire::xor)
+    }
 
-        ValueWire {
-            id_gen: a.id_gen.clone(),
-            bits: (0..size)
-                .map(|i| BoolWire::xor(&a.at(i), &b.at(i)))
-                .collect(),
-        }
+    // Only valid when no bit is set in both a and b, in which case OR is equivalent to XOR. The
+    // debug check only catches constant bits that are both true; overlap between wires is not
+    // detected, so for those disjointness is up to the caller.
+    pub fn bit_or_disjoint(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        ValueWire::zip_with(a, b, |a_bit, b_bit| {
+            debug_assert!(
+                !matches!(
+                    (&a_bit.data, &b_bit.data),
+                    (BoolData::Const(true), BoolData::Const(true))
+                ),
+                "bit_or_disjoint operands overlap"
+            );
+
+            BoolWire::xor(a_bit, b_bit)
+        })
     }
 
     pub fn bit_shl(a: &ValueWire, b: &ValueWire) -> ValueWire {
@@ -458,6 +1260,135 @@ impl ValueWire {
         }
     }
 
+    // Arithmetic (sign extending) right shift. A constant b is just rewiring, otherwise each bit of
+    // b conditionally shifts by 2^i, and any set bit with 2^i >= width fills everything with the
+    // sign bit.
+    pub fn arith_shr(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        if let Some(n) = b.as_usize() {
+            return a.arith_shift_down_const(n);
+        }
+
+        let mut result = a.clone();
+
+        for (i, bit) in b.bits.iter().enumerate() {
+            let amount = if i < usize::BITS as usize {
+                1 << i
+            } else {
+                usize::MAX
+            };
+
+            result = ValueWire::select(bit, &result.arith_shift_down_const(amount), &result);
+        }
+
+        result
+    }
+
+    // a with bit index replaced by condition (no gates needed)
+    pub fn conditional_set_bit(a: &ValueWire, index: usize, condition: &Rc<BoolWire>) -> ValueWire {
+        assert!(index < a.bits.len(), "error: bit index out of range");
+
+        let mut bits = a.bits.clone();
+        bits[index] = condition.clone();
+
+        ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits,
+        }
+    }
+
+    // a with bit index cleared if condition is set
+    pub fn conditional_clear_bit(
+        a: &ValueWire,
+        index: usize,
+        condition: &Rc<BoolWire>,
+    ) -> ValueWire {
+        let cleared = BoolWire::and(&a.at(index), &BoolWire::inv(condition));
+
+        ValueWire::conditional_set_bit(a, index, &cleared)
+    }
+
+    // Packs the bits of a selected by mask into the low bits of the result (like x86 PEXT)
+    pub fn select_bits(a: &ValueWire, mask: &ValueWire) -> ValueWire {
+        let const_mask = mask
+            .bits
+            .iter()
+            .map(|bit| match bit.data {
+                BoolData::Const(value) => Some(value),
+                _ => None,
+            })
+            .collect::<Option<Vec<bool>>>();
+
+        if let Some(const_mask) = const_mask {
+            return ValueWire {
+                id_gen: a.id_gen.clone(),
+                bits: (0..const_mask.len())
+                    .filter(|i| const_mask[*i])
+                    .map(|i| a.at(i))
+                    .collect(),
+            };
+        }
+
+        let size = a.bits.len();
+        let count_size = (usize::BITS - size.leading_zeros()) as usize;
+
+        // counts[i] is the number of selected bits below i, which is where a[i] ends up
+        let mut counts = vec![ValueWire::new_const(0, &a.id_gen).resize(count_size)];
+
+        for i in 0..size {
+            let next = ValueWire::add(&counts[i], &BoolWire::as_value(&mask.at(i)));
+            counts.push(next.resize(count_size));
+        }
+
+        let bits = (0..size)
+            .map(|j| {
+                let target = ValueWire::new_const(j, &a.id_gen);
+
+                // At most one selected bit lands on each position, so XOR is sufficient to combine
+                (0..size).fold(
+                    Rc::new(BoolWire {
+                        id_gen: a.id_gen.clone(),
+                        data: BoolData::Const(false),
+                    }),
+                    |acc, i| {
+                        let selected = BoolWire::and(&a.at(i), &mask.at(i));
+                        let lands = ValueWire::equal(&counts[i], &target);
+
+                        BoolWire::xor(&acc, &BoolWire::and(&selected, &lands))
+                    },
+                )
+            })
+            .collect();
+
+        ValueWire {
+            id_gen: a.id_gen.clone(),
+            bits,
+        }
+    }
+
+    // Spreads the low bits of value into the positions set in mask (like x86 PDEP)
+    pub fn deposit_bits(value: &ValueWire, mask: usize) -> ValueWire {
+        let size = (usize::BITS - mask.leading_zeros()) as usize;
+        let mut bits = Vec::with_capacity(size);
+        let mut j = 0;
+
+        for i in 0..size {
+            if (mask >> i) & 1 == 1 {
+                bits.push(value.at(j));
+                j += 1;
+            } else {
+                bits.push(Rc::new(BoolWire {
+                    id_gen: value.id_gen.clone(),
+                    data: BoolData::Const(false),
+                }));
+            }
+        }
+
+        ValueWire {
+            id_gen: value.id_gen.clone(),
+            bits,
+        }
+    }
+
     pub fn quotient_remainder(a: &ValueWire, b: &ValueWire) -> (ValueWire, ValueWire) {
         let size = std::cmp::max(a.bits.len(), b.bits.len());
         let a = a.resize(size);
@@ -498,25 +1429,498 @@ impl ValueWire {
         (quotient, rem)
     }
 
+    // Non-restoring division: the partial remainder is allowed to go negative, and each step adds
+    // or subtracts the divisor depending on its sign, so there is no comparison per step. Only the
+    // final remainder needs a correction.
+    // For 16 bits this is 1714 gates (571 AND) vs 2299 (1124 AND) for quotient_remainder, but the
+    // depth is 618 vs 253 since every step waits on the previous step's full carry chain.
+    pub fn divmod_nr(a: &ValueWire, b: &ValueWire) -> (ValueWire, ValueWire) {
+        let size = std::cmp::max(a.bits.len(), b.bits.len());
+
+        // 2R + a_i is in [-2b, 2b), which needs two more bits than b
+        let width = size + 2;
+        let divisor = b.resize(width);
+
+        let mut rem = ValueWire::new_const(0, &a.id_gen).resize(width);
+        let mut quotient = ValueWire::new_const(0, &a.id_gen).resize(size);
+
+        for i in (0..size).rev() {
+            let non_negative = BoolWire::inv(&rem.at(width - 1));
+
+            let mut shifted_bits = vec![a.at(i)];
+            shifted_bits.extend_from_slice(&rem.bits[..width - 1]);
+
+            let shifted = ValueWire {
+                id_gen: a.id_gen.clone(),
+                bits: shifted_bits,
+            };
+
+            // Subtract (add the complement plus one) when non-negative, otherwise add
+            let operand = ValueWire {
+                id_gen: a.id_gen.clone(),
+                bits: divisor
+                    .bits
+                    .iter()
+                    .map(|bit| BoolWire::xor(bit, &non_negative))
+                    .collect(),
+            };
+
+            let (sums, _) = ripple_block(&generate_propagate(&shifted, &operand), &non_negative);
+
+            rem = ValueWire {
+                id_gen: a.id_gen.clone(),
+                bits: sums,
+            };
+
+            quotient.bits[i] = BoolWire::inv(&rem.at(width - 1));
+        }
+
+        let correction = ValueWire::mul_bool(&rem.at(width - 1), &divisor);
+        let rem = ValueWire::add(&rem, &correction).truncate_silent(size);
+
+        (quotient, rem)
+    }
+
     pub fn div(a: &ValueWire, b: &ValueWire) -> ValueWire {
-        ValueWire::quotient_remainder(a, b).0
+        match b.as_usize() {
+            Some(divisor) if divisor.is_power_of_two() => {
+                let size = std::cmp::max(a.bits.len(), b.bits.len());
+
+                a.resize(size)
+                    .shift_down_const(divisor.trailing_zeros() as usize)
+                    .resize(size)
+            }
+            Some(divisor) if divisor != 0 => match ValueWire::div_reciprocal(a, divisor) {
+                Some(quotient) => quotient.resize(std::cmp::max(a.bits.len(), b.bits.len())),
+                None => ValueWire::quotient_remainder(a, b).0,
+            },
+            _ => ValueWire::quotient_remainder(a, b).0,
+        }
+    }
+
+    // For n-bit a and l = ceil(log2(d)), a / d == (a * ceil(2^(n + l) / d)) >> (n + l) exactly, so
+    // a constant division becomes a constant multiplication. None if 2^(n + l) doesn't fit in a
+    // usize (which is only 32 bits on wasm32).
+    fn div_reciprocal(a: &ValueWire, divisor: usize) -> Option<ValueWire> {
+        let n = a.bits.len();
+        let l = (usize::BITS - (divisor - 1).leading_zeros()) as usize;
+
+        let shift = u32::try_from(n + l).ok()?;
+        let multiplier = 1usize.checked_shl(shift)?.div_ceil(divisor);
+
+        let product = ValueWire::mul(
+            &a.resize(2 * n + l + 1),
+            &ValueWire::new_const(multiplier, &a.id_gen),
+        );
+
+        Some(product.shift_down_const(n + l).truncate_silent(n))
+    }
+
+    // (a / b, a % b) from a single divider. Calling div and mod_ separately builds the divider
+    // twice, which only gets merged if generate_bristol deduplicates. Since the remainder is
+    // needed anyway, each step takes its comparison from the borrow out of the subtraction that
+    // updates the remainder, rather than a separate comparator. That's fewer gates than
+    // quotient_remainder, but each step waits for the subtraction's full carry chain.
+    pub fn div_rem(a: &ValueWire, b: &ValueWire) -> (ValueWire, ValueWire) {
+        match b.as_usize() {
+            Some(divisor) if divisor.is_power_of_two() => {
+                (ValueWire::div(a, b), ValueWire::mod_(a, b))
+            }
+            _ => {
+                let size = std::cmp::max(a.bits.len(), b.bits.len());
+                let b = b.resize(size);
+
+                let mut quotient = ValueWire::new_const(0, &a.id_gen).resize(size);
+                let mut rem = a.resize(size);
+
+                // b << i only fits when the top i bits of b are zero
+                let mut valid = Rc::new(BoolWire {
+                    id_gen: a.id_gen.clone(),
+                    data: BoolData::Const(true),
+                });
+
+                let mut shifts_valid = vec![valid.clone()];
+
+                for i in 1..size {
+                    valid = BoolWire::and(&valid, &BoolWire::inv(&b.at(size - i)));
+                    shifts_valid.push(valid.clone());
+                }
+
+                for i in (0..size).rev() {
+                    let shift_b = b.shift_up_const(i).resize(size);
+                    let (diff, borrow) = ValueWire::sub_with_overflow(&rem, &shift_b);
+
+                    let apply = BoolWire::and(&shifts_valid[i], &BoolWire::inv(&borrow));
+
+                    quotient.bits[i] = apply.clone();
+                    rem = ValueWire::select(&apply, &diff, &rem);
+                }
+
+                (quotient, rem)
+            }
+        }
     }
 
     pub fn mod_(a: &ValueWire, b: &ValueWire) -> ValueWire {
+        if let Some(modulus) = b.as_usize().filter(|m| m.is_power_of_two()) {
+            let size = std::cmp::max(a.bits.len(), b.bits.len());
+            let k = modulus.trailing_zeros() as usize;
+
+            return a.truncate_silent(std::cmp::min(k, size)).resize(size);
+        }
+
         ValueWire::quotient_remainder(a, b).1
     }
 }
 
-fn tree_sum(values: &[ValueWire], id_gen: &Rc<RefCell<IdGenerator>>) -> ValueWire {
-    if values.len() == 0 {
-        ValueWire::new_const(0, id_gen)
-    } else if values.len() == 1 {
+// At or below this width the schoolbook product has fewer AND gates than another level of
+// Karatsuba (see benches/karatsuba.rs)
+const KARATSUBA_THRESHOLD: usize = 12;
+
+// a * b as a 2n bit value, for n bit a and b
+fn karatsuba_full(a: &ValueWire, b: &ValueWire) -> ValueWire {
+    let n = a.bits.len();
+
+    if n <= KARATSUBA_THRESHOLD {
+        return ValueWire::mul(&a.resize(2 * n), &b.resize(2 * n));
+    }
+
+    let h = n / 2;
+    let (a0, a1) = a.split_at(h);
+    let (b0, b1) = b.split_at(h);
+
+    // a1 and b1 have n - h >= h bits, so the sums need one more
+    let m = n - h + 1;
+    let a_sum = ValueWire::add(&a0.resize(m), &a1.resize(m));
+    let b_sum = ValueWire::add(&b0.resize(m), &b1.resize(m));
+
+    let low = karatsuba_full(&a0.resize(n - h), &b0.resize(n - h));
+    let high = karatsuba_full(&a1, &b1);
+    let sum = karatsuba_full(&a_sum, &b_sum);
+
+    // a0b1 + a1b0 = sum - low - high = sum + !low + !high + 2 (in the width of sum, since it can't
+    // be negative), with a carry-save adder so there's only one carry chain
+    let middle = adder_tree::sum_with(
+        &[
+            sum,
+            ValueWire::bit_not(&low.resize(2 * m)),
+            ValueWire::bit_not(&high.resize(2 * m)),
+            ValueWire::new_const(2, &a.id_gen),
+        ],
+        AdderStrategy::RippleCarry,
+    );
+
+    // low has 2h bits, so low + (high << 2h) is just a concatenation
+    let outer = ValueWire::concat(&low.truncate_silent(2 * h), &high).resize(2 * n);
+
+    ValueWire::add(&outer, &middle.resize(2 * n).shift_up_const(h))
+}
+
+// The rows of a * b: the wider value shifted up by i and masked by bit i of the narrower one,
+// skipping the rows for constant zero bits. Each row has the width of the wider value, so summing
+// them wraps like mul.
+fn partial_products(a: &ValueWire, b: &ValueWire) -> Vec<ValueWire> {
+    let (sm, lg) = if a.bits.len() <= b.bits.len() {
+        (a, b)
+    } else {
+        (b, a)
+    };
+
+    sm.bits
+        .iter()
+        .enumerate()
+        .filter(|(_, bit)| !matches!(bit.data, BoolData::Const(false)))
+        .map(|(i, bit)| ValueWire::mul_bool(bit, &lg.shift_up_const(i)))
+        .collect()
+}
+
+// Sums values (wrapping at the widest one) by reducing each bit position in turn, least
+// significant first. Full adders take three bits of a column to one, carrying into the next
+// column, and only the last two bits of a column need a half adder. Unlike rows of carry-save
+// adders, this never spends a half adder on a column that would fit in the final addition anyway.
+fn column_sum(values: &[ValueWire]) -> ValueWire {
+    let id_gen = &values.first().expect("error: no values").id_gen;
+    let size = values.iter().map(|v| v.bits.len()).max().unwrap();
+
+    let mut columns = (0..size)
+        .map(|i| {
+            values
+                .iter()
+                .map(|v| v.at(i))
+                .filter(|bit| !matches!(bit.data, BoolData::Const(false)))
+                .collect::<VecDeque<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut bits = Vec::with_capacity(size);
+
+    for i in 0..size {
+        let mut column = std::mem::take(&mut columns[i]);
+
+        // The oldest bits are taken first and sums go to the back, so the column is reduced as a
+        // tree rather than a chain
+        while column.len() >= 2 {
+            let (sum, carry) = match column.len() {
+                2 => {
+                    let (x, y) = (column.pop_front().unwrap(), column.pop_front().unwrap());
+                    (BoolWire::xor(&x, &y), BoolWire::and(&x, &y))
+                }
+                _ => {
+                    let (x, y, z) = (
+                        column.pop_front().unwrap(),
+                        column.pop_front().unwrap(),
+                        column.pop_front().unwrap(),
+                    );
+
+                    // majority(x, y, z) with a single AND
+                    let x_xor_z = BoolWire::xor(&x, &z);
+                    let y_xor_z = BoolWire::xor(&y, &z);
+
+                    (
+                        BoolWire::xor(&x_xor_z, &y),
+                        BoolWire::xor(&BoolWire::and(&x_xor_z, &y_xor_z), &z),
+                    )
+                }
+            };
+
+            column.push_back(sum);
+
+            if let Some(next) = columns.get_mut(i + 1) {
+                next.push_back(carry);
+            }
+        }
+
+        bits.push(column.pop_front().unwrap_or_else(|| {
+            Rc::new(BoolWire {
+                id_gen: id_gen.clone(),
+                data: BoolData::Const(false),
+            })
+        }));
+    }
+
+    ValueWire {
+        id_gen: id_gen.clone(),
+        bits,
+    }
+}
+
+fn tree_reduce(values: &[ValueWire], f: fn(&ValueWire, &ValueWire) -> ValueWire) -> ValueWire {
+    if values.len() == 1 {
         values[0].clone()
     } else {
         let mid = values.len() / 2;
-        let left = tree_sum(&values[..mid], id_gen);
-        let right = tree_sum(&values[mid..], id_gen);
+        let left = tree_reduce(&values[..mid], f);
+        let right = tree_reduce(&values[mid..], f);
+
+        f(&left, &right)
+    }
+}
+
+// (bits are all zero, number of leading zeros), for LSB first bits. Splits in half and uses the
+// upper half's count unless the upper half is all zero, in which case it's the lower half's count
+// plus the upper half's width. The all zero flags come out of the recursion, so there's no
+// separate equality check per level, and the depth is O(log(width)) selects.
+fn count_leading_zeros(bits: &[Rc<BoolWire>]) -> (Rc<BoolWire>, ValueWire) {
+    if bits.len() == 1 {
+        let zero = BoolWire::inv(&bits[0]);
+        return (zero.clone(), BoolWire::as_value(&zero));
+    }
+
+    let width = (usize::BITS - bits.len().leading_zeros()) as usize;
+    let mid = bits.len() / 2;
+
+    let (lower_zero, lower_count) = count_leading_zeros(&bits[..mid]);
+    let (upper_zero, upper_count) = count_leading_zeros(&bits[mid..]);
+
+    let count = ValueWire::select(
+        &upper_zero,
+        &ValueWire::add_const(&lower_count.resize(width), bits.len() - mid),
+        &upper_count.resize(width),
+    );
+
+    (BoolWire::and(&upper_zero, &lower_zero), count)
+}
+
+// (generate, propagate) for each bit
+fn generate_propagate(a: &ValueWire, b: &ValueWire) -> Vec<(Rc<BoolWire>, Rc<BoolWire>)> {
+    let size = std::cmp::max(a.bits.len(), b.bits.len());
+
+    (0..size)
+        .map(|i| {
+            let a_bit = a.at(i);
+            let b_bit = b.at(i);
+
+            (BoolWire::and(&a_bit, &b_bit), BoolWire::xor(&a_bit, &b_bit))
+        })
+        .collect()
+}
+
+// Combines the (generate, propagate) of a higher group with the adjacent lower group. The
+// generate terms are mutually exclusive, so XOR can be used instead of OR.
+fn combine_gp(
+    hi: &(Rc<BoolWire>, Rc<BoolWire>),
+    lo: &(Rc<BoolWire>, Rc<BoolWire>),
+) -> (Rc<BoolWire>, Rc<BoolWire>) {
+    (
+        BoolWire::xor(&hi.0, &BoolWire::and(&hi.1, &lo.0)),
+        BoolWire::and(&hi.1, &lo.1),
+    )
+}
+
+// The full adder chain behind ripple_carry, over bit slices so that SmallValueWire can share it
+// without going through a Vec. Yields a sum bit for each bit of the wider input (missing bits are
+// zeros), and leaves the carry out in carry, which starts as the carry in.
+pub(crate) fn ripple_carry_bits<'a>(
+    a: &'a [Rc<BoolWire>],
+    b: &'a [Rc<BoolWire>],
+    carry: &'a mut Rc<BoolWire>,
+) -> impl Iterator<Item = Rc<BoolWire>> + 'a {
+    let size = std::cmp::max(a.len(), b.len());
+
+    (0..size).map(move |i| {
+        let zero = || {
+            Rc::new(BoolWire {
+                id_gen: carry.id_gen.clone(),
+                data: BoolData::Const(false),
+            })
+        };
+
+        let a_bit = a.get(i).cloned().unwrap_or_else(zero);
+        let b_bit = b.get(i).cloned().unwrap_or_else(zero);
+
+        let sum = BoolWire::xor(&a_bit, &b_bit);
+
+        let new_carry = BoolWire::xor(&BoolWire::and(&a_bit, &b_bit), &BoolWire::and(carry, &sum));
+
+        let bit = BoolWire::xor(&sum, carry);
+        *carry = new_carry;
+
+        bit
+    })
+}
+
+// (sums, carry out)
+fn ripple_block(
+    block: &[(Rc<BoolWire>, Rc<BoolWire>)],
+    carry: &Rc<BoolWire>,
+) -> (Vec<Rc<BoolWire>>, Rc<BoolWire>) {
+    let mut sums = Vec::with_capacity(block.len());
+    let mut carry = carry.clone();
+
+    for (g, p) in block {
+        sums.push(BoolWire::xor(p, &carry));
+        carry = BoolWire::xor(g, &BoolWire::and(p, &carry));
+    }
+
+    (sums, carry)
+}
+
+fn prefix_add(a: &ValueWire, b: &ValueWire, network: AdderStrategy) -> ValueWire {
+    let gp = generate_propagate(a, b);
+    let size = gp.len();
+
+    // After the network, prefix[i] is the (generate, propagate) of bits 0..=i
+    let mut prefix = gp.clone();
+
+    match network {
+        AdderStrategy::KoggeStone => {
+            let mut dist = 1;
+
+            while dist < size {
+                let prev = prefix.clone();
+
+                for i in dist..size {
+                    prefix[i] = combine_gp(&prev[i], &prev[i - dist]);
+                }
+
+                dist *= 2;
+            }
+        }
+        AdderStrategy::Sklansky => {
+            let mut dist = 1;
+
+            while dist < size {
+                for i in 0..size {
+                    if i & dist != 0 {
+                        let j = (i & !(dist - 1)) - 1;
+                        prefix[i] = combine_gp(&prefix[i], &prefix[j]);
+                    }
+                }
+
+                dist *= 2;
+            }
+        }
+        AdderStrategy::BrentKung => {
+            let mut dist = 1;
+
+            while dist < size {
+                for i in ((2 * dist - 1)..size).step_by(2 * dist) {
+                    prefix[i] = combine_gp(&prefix[i], &prefix[i - dist]);
+                }
+
+                dist *= 2;
+            }
+
+            while dist > 1 {
+                dist /= 2;
+
+                for i in ((3 * dist - 1)..size).step_by(2 * dist) {
+                    prefix[i] = combine_gp(&prefix[i], &prefix[i - dist]);
+                }
+            }
+        }
+        _ => panic!("Not a prefix network: {:?}", network),
+    }
+
+    let bits = (0..size)
+        .map(|i| match i {
+            0 => gp[0].1.clone(),
+            _ => BoolWire::xor(&gp[i].1, &prefix[i - 1].0),
+        })
+        .collect();
+
+    ValueWire {
+        id_gen: a.id_gen.clone(),
+        bits,
+    }
+}
+
+fn lookahead_add(a: &ValueWire, b: &ValueWire, block_size: usize) -> ValueWire {
+    let gp = generate_propagate(a, b);
+    let size = gp.len();
+    let mut bits = Vec::with_capacity(size);
+
+    let mut carry = Rc::new(BoolWire {
+        id_gen: a.id_gen.clone(),
+        data: BoolData::Const(false),
+    });
+
+    for block_start in (0..size).step_by(block_size) {
+        let block_end = std::cmp::min(block_start + block_size, size);
+
+        // (generate, propagate) of bits block_start..i
+        let mut group: Option<(Rc<BoolWire>, Rc<BoolWire>)> = None;
+
+        for bit_gp in &gp[block_start..block_end] {
+            let bit_carry = match &group {
+                None => carry.clone(),
+                Some((g, p)) => BoolWire::xor(g, &BoolWire::and(p, &carry)),
+            };
+
+            bits.push(BoolWire::xor(&bit_gp.1, &bit_carry));
+
+            group = Some(match group {
+                None => bit_gp.clone(),
+                Some(lo) => combine_gp(bit_gp, &lo),
+            });
+        }
+
+        let (g, p) = group.expect("Block should not be empty");
+        carry = BoolWire::xor(&g, &BoolWire::and(&p, &carry));
+    }
 
-        ValueWire::add(&left, &right)
+    ValueWire {
+        id_gen: a.id_gen.clone(),
+        bits,
     }
 }
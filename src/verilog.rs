@@ -0,0 +1,141 @@
+use std::{collections::HashMap, fmt::Write};
+
+use bristol_circuit::{BristolCircuit, IOInfo};
+
+use crate::export_error::{check_gate, check_io_ranges, ExportError};
+
+// Structural Verilog for Yosys and other hardware synthesis flows. AND and XOR gates instantiate
+// the and_gate and xor_gate modules defined after the circuit's module, and INV and COPY gates are
+// assigns. Port bits are used directly, so only the internal wires are declared.
+pub fn to_verilog_netlist(
+    circuit: &BristolCircuit,
+    module_name: &str,
+) -> Result<String, ExportError> {
+    check_io_ranges(circuit)?;
+
+    // A port needs at least one bit
+    if let Some(io) = circuit
+        .info
+        .inputs
+        .iter()
+        .chain(&circuit.info.outputs)
+        .find(|io| io.width == 0)
+    {
+        return Err(ExportError::EmptyPort(io.name.clone()));
+    }
+
+    let mut verilog = String::new();
+    let mut names = HashMap::<usize, String>::new();
+
+    for input in &circuit.info.inputs {
+        for bit in 0..input.width {
+            names.insert(input.address + bit, port_bit(input, bit));
+        }
+    }
+
+    let output_names = circuit
+        .info
+        .outputs
+        .iter()
+        .flat_map(|output| {
+            (0..output.width).map(move |bit| (output.address + bit, port_bit(output, bit)))
+        })
+        .collect::<Vec<_>>();
+
+    // Outputs that are also inputs (or other outputs) keep the first name and get an assign below
+    for (wire, name) in &output_names {
+        names.entry(*wire).or_insert_with(|| name.clone());
+    }
+
+    let ports = circuit
+        .info
+        .inputs
+        .iter()
+        .map(|input| ("input", input))
+        .chain(circuit.info.outputs.iter().map(|output| ("output", output)))
+        .map(|(direction, io)| match io.width {
+            1 => format!("  {} {}", direction, io.name),
+            width => format!("  {} [{}:0] {}", direction, width - 1, io.name),
+        })
+        .collect::<Vec<_>>();
+
+    writeln!(verilog, "module {} (", module_name).unwrap();
+    writeln!(verilog, "{}", ports.join(",\n")).unwrap();
+    writeln!(verilog, ");").unwrap();
+
+    for wire in 0..circuit.wire_count {
+        if !names.contains_key(&wire) {
+            writeln!(verilog, "  wire w{};", wire).unwrap();
+        }
+    }
+
+    let name = |wire: &usize| {
+        names
+            .get(wire)
+            .cloned()
+            .unwrap_or_else(|| format!("w{}", wire))
+    };
+
+    for constant in &circuit.info.constants {
+        let value = constant
+            .value
+            .as_bool()
+            .ok_or(ExportError::InvalidConstant(constant.address))?;
+
+        writeln!(
+            verilog,
+            "  assign {} = 1'b{};",
+            name(&constant.address),
+            value as u8
+        )
+        .unwrap();
+    }
+
+    for (i, gate) in circuit.gates.iter().enumerate() {
+        check_gate(i, gate)?;
+
+        let inputs = gate.inputs.iter().map(name).collect::<Vec<_>>();
+        let output = name(&gate.outputs[0]);
+
+        match gate.op.as_str() {
+            "AND" | "XOR" => writeln!(
+                verilog,
+                "  {}_gate g{} (.a({}), .b({}), .y({}));",
+                gate.op.to_lowercase(),
+                i,
+                inputs[0],
+                inputs[1],
+                output
+            )
+            .unwrap(),
+            "INV" => writeln!(verilog, "  assign {} = ~{};", output, inputs[0]).unwrap(),
+            // COPY, since check_gate allows nothing else
+            _ => writeln!(verilog, "  assign {} = {};", output, inputs[0]).unwrap(),
+        }
+    }
+
+    for (wire, output_name) in &output_names {
+        if names[wire] != *output_name {
+            writeln!(verilog, "  assign {} = {};", output_name, names[wire]).unwrap();
+        }
+    }
+
+    writeln!(verilog, "endmodule").unwrap();
+
+    for (gate, op) in [("and_gate", "&"), ("xor_gate", "^")] {
+        writeln!(verilog).unwrap();
+        writeln!(verilog, "module {} (input a, input b, output y);", gate).unwrap();
+        writeln!(verilog, "  assign y = a {} b;", op).unwrap();
+        writeln!(verilog, "endmodule").unwrap();
+    }
+
+    Ok(verilog)
+}
+
+// Single bit ports are scalars, which can't be indexed
+fn port_bit(io: &IOInfo, bit: usize) -> String {
+    match io.width {
+        1 => io.name.clone(),
+        _ => format!("{}[{}]", io.name, bit),
+    }
+}
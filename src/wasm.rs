@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use bristol_circuit::{BristolCircuit, CircuitInfo};
+use wasm_bindgen::prelude::*;
+
+use crate::{generate_bristol, CircuitOutput, IdGenerator, ValueWire};
+
+// Circuits cross the JS boundary as the info JSON plus the bristol text, like the files used by
+// the CLI
+#[wasm_bindgen]
+pub struct Circuit {
+    info: String,
+    bristol: String,
+}
+
+#[wasm_bindgen]
+impl Circuit {
+    #[wasm_bindgen(constructor)]
+    pub fn new(info: String, bristol: String) -> Circuit {
+        Circuit { info, bristol }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn info(&self) -> String {
+        self.info.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn bristol(&self) -> String {
+        self.bristol.clone()
+    }
+}
+
+impl Circuit {
+    fn from_bristol_circuit(circuit: &BristolCircuit) -> Result<Circuit, JsValue> {
+        Ok(Circuit {
+            info: serde_json::to_string(&circuit.info).map_err(to_js_error)?,
+            bristol: circuit.get_bristol_string().map_err(to_js_error)?,
+        })
+    }
+
+    fn to_bristol_circuit(&self) -> Result<BristolCircuit, JsValue> {
+        let info: CircuitInfo = serde_json::from_str(&self.info).map_err(to_js_error)?;
+
+        BristolCircuit::read_info_and_bristol(&info, &mut self.bristol.as_bytes())
+            .map_err(to_js_error)
+    }
+}
+
+#[wasm_bindgen]
+pub fn boolify(arith_circuit: &Circuit, bit_width: usize) -> Result<Circuit, JsValue> {
+    let arith_circuit = arith_circuit.to_bristol_circuit()?;
+
+    let bool_circuit = crate::boolify(&arith_circuit, bit_width).map_err(to_js_error)?;
+
+    Circuit::from_bristol_circuit(&bool_circuit)
+}
+
+// Takes and returns JSON objects mapping names to values, eg {"a": 3, "b": 5}
+#[wasm_bindgen(js_name = evalCircuit)]
+pub fn eval(circuit: &Circuit, inputs: &str) -> Result<String, JsValue> {
+    let circuit = circuit.to_bristol_circuit()?;
+    let inputs: HashMap<String, usize> = serde_json::from_str(inputs).map_err(to_js_error)?;
+
+    let outputs = crate::eval(&circuit, &inputs).map_err(to_js_error)?;
+
+    serde_json::to_string(&outputs).map_err(to_js_error)
+}
+
+// Generates a boolean circuit for c = a <op> b with bit_width-bit inputs
+#[wasm_bindgen(js_name = generateBristolBinaryOp)]
+pub fn generate_bristol_binary_op(op: &str, bit_width: usize) -> Result<Circuit, JsValue> {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", bit_width, &id_gen);
+    let b = ValueWire::new_input("b", bit_width, &id_gen);
+
+    let c = match op {
+        "add" => ValueWire::add(&a, &b),
+        "sub" => ValueWire::sub(&a, &b),
+        "mul" => ValueWire::mul(&a, &b),
+        "and" => ValueWire::bit_and(&a, &b),
+        "or" => ValueWire::bit_or(&a, &b),
+        "xor" => ValueWire::bit_xor(&a, &b),
+        _ => return Err(JsValue::from_str(&format!("Unsupported op: {}", op))),
+    };
+
+    let circuit = generate_bristol(&vec![CircuitOutput::new("c", c)]).map_err(to_js_error)?;
+
+    Circuit::from_bristol_circuit(&circuit)
+}
+
+fn to_js_error<E: std::fmt::Debug>(error: E) -> JsValue {
+    JsValue::from_str(&format!("{:?}", error))
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use boolify::{
+    circuits::blake3::{self, CHUNK_END, CHUNK_START, IV, ROOT},
+    eval, generate_bristol, CircuitOutput, IdGenerator, ValueWire,
+};
+use bristol_circuit::BristolCircuit;
+
+// Plain u32 version of the reference compression function
+fn reference_compress(
+    chaining_value: &[u32; 8],
+    block: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 16] {
+    const PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+    fn g(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, mx: u32, my: u32) {
+        state[a] = state[a].wrapping_add(state[b]).wrapping_add(mx);
+        state[d] = (state[d] ^ state[a]).rotate_right(16);
+        state[c] = state[c].wrapping_add(state[d]);
+        state[b] = (state[b] ^ state[c]).rotate_right(12);
+        state[a] = state[a].wrapping_add(state[b]).wrapping_add(my);
+        state[d] = (state[d] ^ state[a]).rotate_right(8);
+        state[c] = state[c].wrapping_add(state[d]);
+        state[b] = (state[b] ^ state[c]).rotate_right(7);
+    }
+
+    let mut state = [0u32; 16];
+    state[..8].copy_from_slice(chaining_value);
+
+    for i in 0..4 {
+        state[8 + i] = IV[i] as u32;
+    }
+
+    state[12] = counter as u32;
+    state[13] = (counter >> 32) as u32;
+    state[14] = block_len;
+    state[15] = flags;
+
+    let mut m = *block;
+
+    for _ in 0..7 {
+        g(&mut state, 0, 4, 8, 12, m[0], m[1]);
+        g(&mut state, 1, 5, 9, 13, m[2], m[3]);
+        g(&mut state, 2, 6, 10, 14, m[4], m[5]);
+        g(&mut state, 3, 7, 11, 15, m[6], m[7]);
+        g(&mut state, 0, 5, 10, 15, m[8], m[9]);
+        g(&mut state, 1, 6, 11, 12, m[10], m[11]);
+        g(&mut state, 2, 7, 8, 13, m[12], m[13]);
+        g(&mut state, 3, 4, 9, 14, m[14], m[15]);
+
+        m = PERMUTATION.map(|i| m[i]);
+    }
+
+    for i in 0..8 {
+        state[i] ^= state[i + 8];
+        state[i + 8] ^= chaining_value[i];
+    }
+
+    state
+}
+
+fn compress_circuit() -> BristolCircuit {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let chaining_value =
+        std::array::from_fn(|i| ValueWire::new_input(&format!("cv{}", i), 32, &id_gen));
+
+    let block = std::array::from_fn(|i| ValueWire::new_input(&format!("m{}", i), 32, &id_gen));
+
+    let counter = ValueWire::new_input("counter", 64, &id_gen);
+    let block_len = ValueWire::new_input("block_len", 32, &id_gen);
+    let flags = ValueWire::new_input("flags", 32, &id_gen);
+
+    let outputs = blake3::compress(&chaining_value, &block, &counter, &block_len, &flags)
+        .into_iter()
+        .enumerate()
+        .map(|(i, word)| CircuitOutput::new(&format!("out{}", i), word))
+        .collect::<Vec<_>>();
+
+    generate_bristol(&outputs).unwrap()
+}
+
+fn eval_compress(
+    circuit: &BristolCircuit,
+    chaining_value: &[u32; 8],
+    block: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 16] {
+    let mut inputs = HashMap::<String, usize>::new();
+
+    for (i, word) in chaining_value.iter().enumerate() {
+        inputs.insert(format!("cv{}", i), *word as usize);
+    }
+
+    for (i, word) in block.iter().enumerate() {
+        inputs.insert(format!("m{}", i), *word as usize);
+    }
+
+    inputs.insert("counter".to_string(), counter as usize);
+    inputs.insert("block_len".to_string(), block_len as usize);
+    inputs.insert("flags".to_string(), flags as usize);
+
+    let outputs = eval(circuit, &inputs).unwrap();
+
+    std::array::from_fn(|i| outputs[&format!("out{}", i)] as u32)
+}
+
+#[test]
+fn test_blake3_empty_input_hash() {
+    let circuit = compress_circuit();
+
+    // The hash of the empty input is a single compression of an empty block with the IV as the
+    // chaining value
+    let chaining_value = IV.map(|word| word as u32);
+    let flags = (CHUNK_START | CHUNK_END | ROOT) as u32;
+
+    let output = eval_compress(&circuit, &chaining_value, &[0; 16], 0, 0, flags);
+
+    let hash = output[..8]
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+
+    assert_eq!(
+        hash,
+        "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+    );
+}
+
+#[test]
+fn test_blake3_compress_matches_reference() {
+    let circuit = compress_circuit();
+
+    let chaining_value = std::array::from_fn(|i| 0x01234567u32.wrapping_mul(i as u32 + 1));
+    let block = std::array::from_fn(|i| 0x89abcdefu32.rotate_left(i as u32) ^ i as u32);
+    let counter = 0x0000_0001_8000_0002;
+
+    assert_eq!(
+        eval_compress(&circuit, &chaining_value, &block, counter, 64, 0),
+        reference_compress(&chaining_value, &block, counter, 64, 0)
+    );
+}
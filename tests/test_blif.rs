@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use boolify::{
+    eval, generate_bristol, generate_bristol_with_options, testing::SplitMix64, to_blif, BoolWire,
+    CircuitOutput, ConstantStrategy, ExportError, GenerateBristolOptions, IdGenerator, ValueWire,
+};
+use serde_json::json;
+
+struct Blif {
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+
+    // (inputs, output, rows of the on-set)
+    names: Vec<(Vec<String>, String, Vec<String>)>,
+}
+
+// Checks every line is one of the forms to_blif emits, and collects the model
+fn parse_blif(blif: &str) -> Blif {
+    let is_signal = |s: &str| {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_[]".contains(c))
+    };
+
+    let mut lines = blif.lines();
+    assert_eq!(lines.next(), Some(".model circuit"));
+
+    let mut inputs = None;
+    let mut outputs = None;
+    let mut names = Vec::<(Vec<String>, String, Vec<String>)>::new();
+    let mut ended = false;
+
+    for line in lines {
+        assert!(!ended, "content after .end: {}", line);
+
+        let words = line.split(' ').collect::<Vec<_>>();
+
+        match words[0] {
+            ".inputs" | ".outputs" => {
+                assert!(words[1..].iter().all(|w| is_signal(w)), "{}", line);
+
+                let signals = words[1..].iter().map(|w| w.to_string()).collect();
+
+                if words[0] == ".inputs" {
+                    inputs = Some(signals);
+                } else {
+                    outputs = Some(signals);
+                }
+            }
+            ".names" => {
+                assert!(words.len() >= 2, "{}", line);
+                assert!(words[1..].iter().all(|w| is_signal(w)), "{}", line);
+
+                let (output, gate_inputs) = words[1..].split_last().unwrap();
+
+                names.push((
+                    gate_inputs.iter().map(|w| w.to_string()).collect(),
+                    output.to_string(),
+                    vec![],
+                ));
+            }
+            ".end" => ended = true,
+            _ => {
+                let (gate_inputs, _, rows) = names.last_mut().expect("row before .names");
+
+                let pattern = match line.split_once(' ') {
+                    Some((pattern, "1")) => pattern,
+                    None if gate_inputs.is_empty() && line == "1" => "",
+                    _ => panic!("bad row: {}", line),
+                };
+
+                assert_eq!(pattern.len(), gate_inputs.len());
+                assert!(pattern.chars().all(|c| "01-".contains(c)), "{}", line);
+
+                rows.push(pattern.to_string());
+            }
+        }
+    }
+
+    assert!(ended);
+
+    Blif {
+        inputs: inputs.expect("missing .inputs"),
+        outputs: outputs.expect("missing .outputs"),
+        names,
+    }
+}
+
+// Evaluates the .names covers directly (a signal is 1 if any row matches its inputs)
+fn eval_blif(blif: &Blif, input_bits: &HashMap<String, bool>) -> HashMap<String, bool> {
+    let mut values = input_bits.clone();
+    let mut pending = blif.names.iter().collect::<Vec<_>>();
+
+    while !pending.is_empty() {
+        let before = pending.len();
+
+        pending.retain(|(gate_inputs, output, rows)| {
+            let Some(bits) = gate_inputs
+                .iter()
+                .map(|input| values.get(input).copied())
+                .collect::<Option<Vec<_>>>()
+            else {
+                return true;
+            };
+
+            let value = rows.iter().any(|row| {
+                row.chars().zip(&bits).all(|(c, bit)| match c {
+                    '1' => *bit,
+                    '0' => !*bit,
+                    _ => true,
+                })
+            });
+
+            values.insert(output.clone(), value);
+            false
+        });
+
+        assert!(pending.len() < before, "cycle or undriven signal");
+    }
+
+    blif.outputs
+        .iter()
+        .map(|output| (output.clone(), values[output]))
+        .collect()
+}
+
+fn eval_blif_values(
+    blif: &Blif,
+    widths: &[(&str, usize)],
+    inputs: &HashMap<String, usize>,
+    output_widths: &[(&str, usize)],
+) -> HashMap<String, usize> {
+    let mut input_bits = HashMap::new();
+
+    for (name, width) in widths {
+        for bit in 0..*width {
+            input_bits.insert(
+                format!("{}[{}]", name, bit),
+                (inputs[*name] >> bit) & 1 == 1,
+            );
+        }
+    }
+
+    assert_eq!(input_bits.len(), blif.inputs.len());
+
+    let output_bits = eval_blif(blif, &input_bits);
+
+    output_widths
+        .iter()
+        .map(|(name, width)| {
+            let value = (0..*width)
+                .map(|bit| (output_bits[&format!("{}[{}]", name, bit)] as usize) << bit)
+                .sum();
+
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+#[test]
+fn test_blif_2bit_add() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_input("b", 2, &id_gen);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", ValueWire::add(&a, &b))]).unwrap();
+
+    assert_eq!(
+        to_blif(&circuit).unwrap(),
+        vec![
+            ".model circuit",
+            ".inputs a[0] a[1] b[0] b[1]",
+            ".outputs c[0] c[1]",
+            ".names a[0] b[0] c[0]",
+            "01 1",
+            "10 1",
+            ".names a[1] b[1] w4",
+            "01 1",
+            "10 1",
+            ".names a[0] b[0] w5",
+            "11 1",
+            ".names w4 w5 c[1]",
+            "01 1",
+            "10 1",
+            ".end",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn test_blif_matches_eval() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_input("b", 8, &id_gen);
+
+    let outputs = vec![
+        CircuitOutput::new("sum", ValueWire::add(&a, &b)),
+        CircuitOutput::new("product", ValueWire::mul(&a, &b)),
+        CircuitOutput::new("less", BoolWire::as_value(&ValueWire::less_than(&a, &b))),
+        CircuitOutput::new("not_a", ValueWire::bit_not(&a)),
+    ];
+
+    let circuit = generate_bristol(&outputs).unwrap();
+    let blif = parse_blif(&to_blif(&circuit).unwrap());
+
+    let output_widths = [("sum", 8), ("product", 8), ("less", 1), ("not_a", 8)];
+
+    let mut rng = SplitMix64::new(1031);
+
+    for _ in 0..50 {
+        let inputs = HashMap::from([
+            ("a".to_string(), rng.below(256)),
+            ("b".to_string(), rng.below(256)),
+        ]);
+
+        assert_eq!(
+            eval_blif_values(&blif, &[("a", 8), ("b", 8)], &inputs, &output_widths),
+            eval(&circuit, &inputs).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_blif_constants() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+
+    let outputs = vec![
+        CircuitOutput::new("a", a.clone()),
+        CircuitOutput::new("c", ValueWire::new_const(0b1010, &id_gen)),
+    ];
+
+    let circuit = generate_bristol_with_options(
+        &outputs,
+        &GenerateBristolOptions {
+            constant_strategy: ConstantStrategy::Inline,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let blif = parse_blif(&to_blif(&circuit).unwrap());
+
+    let inputs = HashMap::from([("a".to_string(), 0b0110)]);
+
+    assert_eq!(
+        eval_blif_values(&blif, &[("a", 4)], &inputs, &[("a", 4), ("c", 4)]),
+        eval(&circuit, &inputs).unwrap()
+    );
+}
+
+#[test]
+fn test_blif_errors() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_input("b", 2, &id_gen);
+
+    let circuit = generate_bristol_with_options(
+        &[
+            CircuitOutput::new("c", ValueWire::add(&a, &b)),
+            CircuitOutput::new("d", ValueWire::new_const(0b10, &id_gen)),
+        ],
+        &GenerateBristolOptions {
+            constant_strategy: ConstantStrategy::Inline,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert!(to_blif(&circuit).is_ok());
+
+    let mut unknown_op = circuit.clone();
+    unknown_op.gates[0].op = "OR".to_string();
+    assert_eq!(
+        to_blif(&unknown_op),
+        Err(ExportError::UnknownOp("OR".to_string()))
+    );
+
+    let mut malformed = circuit.clone();
+    malformed.gates[0].outputs.clear();
+    assert_eq!(to_blif(&malformed), Err(ExportError::MalformedGate(0)));
+
+    let mut invalid_constant = circuit.clone();
+    invalid_constant.info.constants[0].value = json!("x");
+    assert_eq!(
+        to_blif(&invalid_constant),
+        Err(ExportError::InvalidConstant(
+            circuit.info.constants[0].address
+        ))
+    );
+
+    let mut out_of_range = circuit.clone();
+    out_of_range.info.inputs[0].address = usize::MAX;
+    assert_eq!(
+        to_blif(&out_of_range),
+        Err(ExportError::IOOutOfRange("a".to_string()))
+    );
+}
@@ -0,0 +1,187 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use boolify::{
+    boolify, boolify_typed, boolify_with_options, eval, BoolifyError, BoolifyOptions, GenerateError,
+};
+use bristol_circuit::{BristolCircuit, CircuitInfo, Gate, IOInfo};
+use serde_json::json;
+
+// ((a + b) + b) + b
+fn arith_circuit() -> BristolCircuit {
+    let io = |name: &str, address: usize| IOInfo {
+        name: name.to_string(),
+        type_: json!("number"),
+        address,
+        width: 1,
+    };
+
+    let add = |a: usize, b: usize, c: usize| Gate {
+        inputs: vec![a, b],
+        outputs: vec![c],
+        op: "AAdd".to_string(),
+    };
+
+    BristolCircuit {
+        wire_count: 5,
+        info: CircuitInfo {
+            inputs: vec![io("a", 0), io("b", 1)],
+            outputs: vec![io("c", 4)],
+            ..Default::default()
+        },
+        gates: vec![add(0, 1, 2), add(2, 1, 3), add(3, 1, 4)],
+    }
+}
+
+#[test]
+fn test_boolify_progress() {
+    let calls = Rc::new(RefCell::new(Vec::new()));
+    let calls_clone = calls.clone();
+
+    let mut options = BoolifyOptions::new(4);
+    options.progress_callback = Some(Box::new(move |done, total| {
+        calls_clone.borrow_mut().push((done, total));
+    }));
+
+    let circuit = boolify_with_options(&arith_circuit(), options).unwrap();
+
+    assert_eq!(*calls.borrow(), vec![(1, 3), (2, 3), (3, 3)]);
+    assert_eq!(
+        circuit.get_bristol_string().unwrap(),
+        boolify(&arith_circuit(), 4)
+            .unwrap()
+            .get_bristol_string()
+            .unwrap()
+    );
+
+    let inputs = HashMap::from([("a".to_string(), 2), ("b".to_string(), 3)]);
+    assert_eq!(eval(&circuit, &inputs).unwrap().get("c").unwrap(), &11);
+}
+
+#[test]
+fn test_boolify_cancel() {
+    let cancel_token = Arc::new(AtomicBool::new(false));
+    let cancel_clone = cancel_token.clone();
+    let calls = Rc::new(RefCell::new(0));
+    let calls_clone = calls.clone();
+
+    let mut options = BoolifyOptions::new(4);
+    options.cancel_token = Some(cancel_token);
+    options.progress_callback = Some(Box::new(move |_, _| {
+        *calls_clone.borrow_mut() += 1;
+        cancel_clone.store(true, Ordering::Relaxed);
+    }));
+
+    let result = boolify_with_options(&arith_circuit(), options);
+
+    assert!(matches!(result, Err(BoolifyError::Cancelled)));
+    assert_eq!(*calls.borrow(), 1);
+}
+
+#[test]
+fn test_boolify_typed() {
+    let io = |name: &str, address: usize| IOInfo {
+        name: name.to_string(),
+        type_: json!("number"),
+        address,
+        width: 1,
+    };
+
+    // c = a + b, with a 8-bit and b 32-bit
+    let arith_circuit = BristolCircuit {
+        wire_count: 3,
+        info: CircuitInfo {
+            inputs: vec![io("a", 0), io("b", 1)],
+            outputs: vec![io("c", 2)],
+            ..Default::default()
+        },
+        gates: vec![Gate {
+            inputs: vec![0, 1],
+            outputs: vec![2],
+            op: "AAdd".to_string(),
+        }],
+    };
+
+    let wire_widths = HashMap::from([(0, 8), (1, 32), (2, 32)]);
+    let circuit = boolify_typed(&arith_circuit, 16, &wire_widths).unwrap();
+
+    assert_eq!(circuit.info.inputs[0].width, 8);
+    assert_eq!(circuit.info.inputs[1].width, 32);
+    assert_eq!(circuit.info.outputs[0].width, 32);
+
+    for (a, b) in [(0, 0), (255, 1), (200, 4_000_000_000), (255, 0xffff_ffff)] {
+        let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+
+        assert_eq!(
+            eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+            &((a + b) & 0xffff_ffff)
+        );
+    }
+}
+
+#[test]
+fn test_boolify_mux() {
+    let io = |name: &str, type_: &str, address: usize| IOInfo {
+        name: name.to_string(),
+        type_: json!(type_),
+        address,
+        width: 1,
+    };
+
+    // d = cond ? a : b
+    let arith_circuit = BristolCircuit {
+        wire_count: 4,
+        info: CircuitInfo {
+            inputs: vec![
+                io("cond", "bool", 0),
+                io("a", "number", 1),
+                io("b", "number", 2),
+            ],
+            outputs: vec![io("d", "number", 3)],
+            ..Default::default()
+        },
+        gates: vec![Gate {
+            inputs: vec![0, 1, 2],
+            outputs: vec![3],
+            op: "AMux".to_string(),
+        }],
+    };
+
+    let circuit = boolify(&arith_circuit, 4).unwrap();
+
+    for cond in 0..2 {
+        for a in 0..16 {
+            for b in 0..16 {
+                let inputs = HashMap::from([
+                    ("cond".to_string(), cond),
+                    ("a".to_string(), a),
+                    ("b".to_string(), b),
+                ]);
+
+                let expected = if cond == 1 { a } else { b };
+                assert_eq!(
+                    eval(&circuit, &inputs).unwrap().get("d").unwrap(),
+                    &expected
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_boolify_generate_error() {
+    let mut circuit = arith_circuit();
+    circuit.info.outputs.clear();
+
+    assert_eq!(
+        boolify(&circuit, 4),
+        Err(BoolifyError::Generate(GenerateError::NoOutputs))
+    );
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use boolify::{circuits::chacha20, eval, generate_bristol, CircuitOutput, IdGenerator, ValueWire};
+
+// RFC 8439 2.1.1
+#[test]
+fn test_chacha20_quarter_round() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let [a, b, c, d] = ["a", "b", "c", "d"].map(|name| ValueWire::new_input(name, 32, &id_gen));
+    let (a, b, c, d) = chacha20::quarter_round(&a, &b, &c, &d);
+
+    let circuit = generate_bristol(&[
+        CircuitOutput::new("a", a),
+        CircuitOutput::new("b", b),
+        CircuitOutput::new("c", c),
+        CircuitOutput::new("d", d),
+    ])
+    .unwrap();
+
+    let inputs = HashMap::from([
+        ("a".to_string(), 0x11111111),
+        ("b".to_string(), 0x01020304),
+        ("c".to_string(), 0x9b8d6f43),
+        ("d".to_string(), 0x01234567),
+    ]);
+
+    let outputs = eval(&circuit, &inputs).unwrap();
+
+    assert_eq!(outputs["a"], 0xea2a92f4);
+    assert_eq!(outputs["b"], 0xcb1cf8ce);
+    assert_eq!(outputs["c"], 0x4581472e);
+    assert_eq!(outputs["d"], 0x5881c4bb);
+}
+
+// RFC 8439 2.3.2
+#[test]
+fn test_chacha20_block() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let key = std::array::from_fn(|i| ValueWire::new_input(&format!("k{}", i), 32, &id_gen));
+    let nonce = std::array::from_fn(|i| ValueWire::new_input(&format!("n{}", i), 32, &id_gen));
+    let counter = ValueWire::new_input("counter", 32, &id_gen);
+
+    let outputs = chacha20::block(&key, &nonce, &counter)
+        .into_iter()
+        .enumerate()
+        .map(|(i, word)| CircuitOutput::new(&format!("out{}", i), word))
+        .collect::<Vec<_>>();
+
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    // Key 00:01:02:...:1f, nonce 00:00:00:09:00:00:00:4a:00:00:00:00, as little endian words
+    let mut inputs = (0..8)
+        .map(|i| {
+            let bytes = [4 * i, 4 * i + 1, 4 * i + 2, 4 * i + 3];
+            (format!("k{}", i), u32::from_le_bytes(bytes) as usize)
+        })
+        .collect::<HashMap<_, _>>();
+
+    inputs.insert("n0".to_string(), 0x09000000);
+    inputs.insert("n1".to_string(), 0x4a000000);
+    inputs.insert("n2".to_string(), 0);
+    inputs.insert("counter".to_string(), 1);
+
+    let outputs = eval(&circuit, &inputs).unwrap();
+
+    let expected = [
+        0xe4e7f110, 0x15593bd1, 0x1fdd0f50, 0xc47120a3, 0xc7f4d1c7, 0x0368c033, 0x9aaa2204,
+        0x4e6cd4c3, 0x466482d2, 0x09aa9f07, 0x05d7c214, 0xa2028bd9, 0xd19c12b5, 0xb94e16de,
+        0xe883d0cb, 0x4e3c50a2,
+    ];
+
+    for (i, word) in expected.iter().enumerate() {
+        assert_eq!(outputs[&format!("out{}", i)], *word);
+    }
+}
@@ -0,0 +1,79 @@
+use boolify::{
+    circuit_analysis::{compute_depth, CircuitAnalysisError},
+    generate_bristol,
+    testing::random_circuit,
+    BoolWire, CircuitOutput, IdGenerator, ValueWire,
+};
+
+#[test]
+fn test_compute_depth_2bit_add() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_input("b", 2, &id_gen);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", ValueWire::add(&a, &b))]).unwrap();
+
+    // c[0] = a[0] ^ b[0] is depth 1, c[1] = (a[1] ^ b[1]) ^ (a[0] & b[0]) is depth 2
+    assert_eq!(compute_depth(&circuit).unwrap(), 2);
+}
+
+#[test]
+fn test_compute_depth_bounded_by_gate_count() {
+    // random_circuit's outputs are its last gates, so with one output only the gates it depends on
+    // contribute, and the depth can't exceed the number of gates
+    for seed in 0..50 {
+        let circuit = random_circuit(200, 8, 1, seed);
+        let depth = compute_depth(&circuit).unwrap();
+
+        assert!(depth >= 1);
+        assert!(depth <= circuit.gates.len());
+    }
+
+    // A chain where every gate reads the previous one has depth equal to its length
+    let id_gen = IdGenerator::new_rc_refcell();
+    let a = ValueWire::new_input("a", 10, &id_gen);
+
+    let chain = a.bits[1..]
+        .iter()
+        .fold(a.bits[0].clone(), |acc, bit| BoolWire::xor(&acc, bit));
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", BoolWire::as_value(&chain))]).unwrap();
+
+    assert_eq!(compute_depth(&circuit).unwrap(), 9);
+    assert_eq!(circuit.gates.len(), 9);
+}
+
+#[test]
+fn test_compute_depth_out_of_range() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_input("b", 2, &id_gen);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", ValueWire::add(&a, &b))]).unwrap();
+
+    let mut bad_input = circuit.clone();
+    bad_input.gates[0].inputs[0] = circuit.wire_count;
+
+    assert_eq!(
+        compute_depth(&bad_input),
+        Err(CircuitAnalysisError::WireOutOfRange(circuit.wire_count))
+    );
+
+    let mut bad_output = circuit.clone();
+    bad_output.gates[0].outputs[0] = usize::MAX;
+
+    assert_eq!(
+        compute_depth(&bad_output),
+        Err(CircuitAnalysisError::WireOutOfRange(usize::MAX))
+    );
+
+    let mut bad_io = circuit.clone();
+    bad_io.info.outputs[0].width = usize::MAX;
+
+    assert_eq!(
+        compute_depth(&bad_io),
+        Err(CircuitAnalysisError::IOOutOfRange("c".to_string()))
+    );
+}
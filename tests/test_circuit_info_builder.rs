@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use boolify::{eval, CircuitInfoBuilder};
+use bristol_circuit::{BristolCircuit, Gate};
+
+#[test]
+fn test_builder_wraps_bare_circuit() {
+    let info = CircuitInfoBuilder::new()
+        .add_input("a", "bool", 0, 1)
+        .add_input("b", "bool", 1, 1)
+        .add_output("c", "bool", 2, 1)
+        .build();
+
+    assert_eq!(info.inputs.len(), 2);
+    assert_eq!(info.outputs[0].name, "c");
+    assert_eq!(info.outputs[0].type_, "bool");
+    assert_eq!(info.outputs[0].address, 2);
+
+    let circuit = BristolCircuit {
+        wire_count: 3,
+        info,
+        gates: vec![Gate {
+            inputs: vec![0, 1],
+            outputs: vec![2],
+            op: "XOR".to_string(),
+        }],
+    };
+
+    for a in 0..2 {
+        for b in 0..2 {
+            let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+
+            assert_eq!(eval(&circuit, &inputs).unwrap().get("c").unwrap(), &(a ^ b));
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "overlaps")]
+fn test_builder_rejects_overlap() {
+    CircuitInfoBuilder::new()
+        .add_input("a", "number", 0, 4)
+        .add_output("c", "number", 3, 4)
+        .build();
+}
+
+#[test]
+#[should_panic(expected = "zero width")]
+fn test_builder_rejects_zero_width() {
+    CircuitInfoBuilder::new()
+        .add_input("a", "number", 0, 0)
+        .build();
+}
@@ -1,6 +1,16 @@
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use boolify::{eval, generate_bristol, BoolWire, CircuitOutput, IdGenerator, ValueWire};
+use boolify::{
+    circuit_analysis::{compute_depth, CircuitAnalysisError},
+    circuits::{adder_tree, adder_variants},
+    testing::SplitMix64,
+};
+use boolify::{
+    eval, generate_bristol, generate_bristol_with_options, generate_bristol_with_stats,
+    AdderStrategy, BitOrder, BoolData, BoolWire, BristolCircuitExt, CircuitOutput, CircuitStats,
+    ConstantStrategy, GenerateBristolOptions, GenerateError, IdGenerator, ValueWire,
+};
+use bristol_circuit::BristolCircuit;
 
 #[test]
 fn test_2bit_add() {
@@ -13,13 +23,13 @@ fn test_2bit_add() {
 
     let outputs = vec![CircuitOutput::new("c", c)];
 
-    let circuit = generate_bristol(&outputs);
+    let circuit = generate_bristol(&outputs).unwrap();
 
     let bristol_string = circuit.get_bristol_string().unwrap();
 
     assert_eq!(
         bristol_string,
-        vec![
+        [
             "4 8",
             "2 2 2",
             "1 2",
@@ -35,302 +45,2871 @@ fn test_2bit_add() {
 }
 
 #[test]
-fn test_8bit_xor_and_1() {
+fn test_generate_bristol_errors() {
+    assert_eq!(generate_bristol(&[]).err(), Some(GenerateError::NoOutputs));
+
     let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
 
-    let a = ValueWire::new_input("a", 8, &id_gen);
-    let b = ValueWire::new_input("b", 8, &id_gen);
+    // The default constant strategy needs an input to build constants from
+    assert_eq!(
+        generate_bristol(&[CircuitOutput::new("c", ValueWire::new_const(5, &id_gen))]).err(),
+        Some(GenerateError::NoInputs)
+    );
 
-    let c0 = ValueWire::add(&a, &b);
-    let c = ValueWire::bit_and(&c0, &ValueWire::new_const(1, &id_gen));
+    let a = ValueWire::new_input("a", 2, &id_gen);
 
-    let outputs = vec![CircuitOutput::new("c", c)];
+    assert_eq!(
+        generate_bristol(&[
+            CircuitOutput::new("a", a.clone()),
+            CircuitOutput::new("empty", ValueWire::new_const(0, &id_gen).truncate_silent(0))
+        ])
+        .err(),
+        Some(GenerateError::OutputMissingId)
+    );
 
-    let circuit = generate_bristol(&outputs);
+    // Bypasses BoolWire::and, which would fold the constant away
+    let id = id_gen.borrow_mut().gen();
+
+    let and_const = Rc::new(BoolWire {
+        id_gen: id_gen.clone(),
+        data: BoolData::And(
+            id,
+            a.at(0),
+            Rc::new(BoolWire {
+                id_gen: id_gen.clone(),
+                data: BoolData::Const(true),
+            }),
+        ),
+    });
 
-    let bristol_string = circuit.get_bristol_string().unwrap();
+    assert_eq!(
+        generate_bristol(&[CircuitOutput::new("c", BoolWire::as_value(&and_const))]).err(),
+        Some(GenerateError::InputMissingId)
+    );
+
+    // Both generators start numbering at 0, so x and y claim the same wires
+    let x = ValueWire::new_input("x", 2, &IdGenerator::new_rc_refcell());
+    let y = ValueWire::new_input("y", 2, &IdGenerator::new_rc_refcell());
 
     assert_eq!(
-        bristol_string,
-        vec![
-            "10 26", //
-            "2 8 8",
-            "1 8",
-            "",
-            "2 1 0 8 18 XOR",
-            "2 1 0 0 16 XOR",
-            "1 1 16 17 INV",
-            "1 1 17 19 INV",
-            "1 1 17 20 INV",
-            "1 1 17 21 INV",
-            "1 1 17 22 INV",
-            "1 1 17 23 INV",
-            "1 1 17 24 INV",
-            "1 1 17 25 INV",
-            "",
-        ]
-        .join("\n")
+        generate_bristol(&[CircuitOutput::new("x", x), CircuitOutput::new("y", y)]).err(),
+        Some(GenerateError::InputIdConflict(
+            "x".to_string(),
+            "y".to_string()
+        ))
     );
 }
 
 #[test]
-fn test_2bit_mul() {
+fn test_generate_bristol_is_deterministic() {
+    let build = || {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let a = ValueWire::new_input("a", 16, &id_gen);
+        let b = ValueWire::new_input("b", 16, &id_gen);
+
+        let outputs = vec![
+            CircuitOutput::new("sum", ValueWire::add(&a, &b)),
+            CircuitOutput::new("product", ValueWire::mul(&a, &b)),
+            CircuitOutput::new("a", a.clone()),
+            CircuitOutput::new("const", ValueWire::new_const(0x1234, &id_gen)),
+        ];
+
+        generate_bristol(&outputs)
+            .unwrap()
+            .get_bristol_string()
+            .unwrap()
+    };
+
+    let first = build();
+
+    for _ in 0..5 {
+        assert_eq!(build(), first);
+    }
+}
+
+#[test]
+fn test_2bit_add_stats() {
     let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
 
     let a = ValueWire::new_input("a", 2, &id_gen);
     let b = ValueWire::new_input("b", 2, &id_gen);
 
-    let c = ValueWire::mul(&a, &b);
-
-    let outputs = vec![CircuitOutput::new("c", c)];
-
-    let circuit = generate_bristol(&outputs);
+    let outputs = vec![CircuitOutput::new("c", ValueWire::add(&a, &b))];
 
-    let bristol_string = circuit.get_bristol_string().unwrap();
+    let (circuit, stats) = generate_bristol_with_stats(&outputs).unwrap();
 
+    // Same circuit as test_2bit_add: 3 XOR and 1 AND, with c[1] = (a[1] ^ b[1]) ^ (a[0] & b[0])
+    assert_eq!(circuit, generate_bristol(&outputs).unwrap());
     assert_eq!(
-        bristol_string,
-        vec![
-            "4 8",
-            "2 2 2",
-            "1 2",
-            "",
-            "2 1 0 2 6 AND",
-            "2 1 0 3 4 AND",
-            "2 1 1 2 5 AND",
-            "2 1 4 5 7 XOR",
-            ""
-        ]
-        .join("\n")
+        stats,
+        CircuitStats {
+            and_count: 1,
+            xor_count: 3,
+            inv_count: 0,
+            wire_count: 8,
+            depth: 2,
+        }
     );
 }
 
 #[test]
-fn test_2bit_exp() {
+fn test_circuit_stats_unknown_op() {
     let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
 
     let a = ValueWire::new_input("a", 2, &id_gen);
-    let b = ValueWire::new_const(2, &id_gen);
-
-    let c = ValueWire::exp(&a, &b);
-
-    let outputs = vec![CircuitOutput::new("c", c)];
-
-    let circuit = generate_bristol(&outputs);
+    let b = ValueWire::new_input("b", 2, &id_gen);
 
-    let bristol_string = circuit.get_bristol_string().unwrap();
+    let mut circuit = generate_bristol(&[CircuitOutput::new("c", ValueWire::add(&a, &b))]).unwrap();
+    circuit.gates[0].op = "OR".to_string();
 
     assert_eq!(
-        bristol_string,
-        vec![
-            "4 6",
-            "1 2",
-            "1 2",
-            "",
-            "2 1 0 0 4 AND",
-            "2 1 0 1 2 AND",
-            "2 1 1 0 3 AND",
-            "2 1 2 3 5 XOR",
-            ""
-        ]
-        .join("\n")
+        CircuitStats::from_circuit(&circuit),
+        Err(CircuitAnalysisError::UnknownOp("OR".to_string()))
     );
 }
 
 #[test]
-fn test_2bit_shl() {
+fn test_8bit_xor_and_1() {
     let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
 
-    let a = ValueWire::new_input("a", 2, &id_gen);
-    let b = ValueWire::new_const(1, &id_gen);
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_input("b", 8, &id_gen);
 
-    let c = ValueWire::bit_shl(&a, &b);
+    let c0 = ValueWire::add(&a, &b);
+    let c = ValueWire::bit_and(&c0, &ValueWire::new_const(1, &id_gen));
 
     let outputs = vec![CircuitOutput::new("c", c)];
 
-    let circuit = generate_bristol(&outputs);
+    let circuit = generate_bristol(&outputs).unwrap();
 
     let bristol_string = circuit.get_bristol_string().unwrap();
 
     assert_eq!(
         bristol_string,
         vec![
-            "5 7", //
-            "1 2",
-            "1 2",
+            "8 24", //
+            "2 8 8",
+            "1 8",
+            "",
+            "2 1 0 8 16 XOR",
+            "2 1 0 0 17 XOR",
+            "1 1 17 18 COPY",
+            "1 1 17 19 COPY",
+            "1 1 17 20 COPY",
+            "1 1 17 21 COPY",
+            "1 1 17 22 COPY",
+            "1 1 17 23 COPY",
             "",
-            "2 1 0 0 2 XOR",
-            "1 1 2 3 INV",
-            "1 1 3 5 INV",
-            "1 1 0 4 INV",
-            "1 1 4 6 INV",
-            ""
         ]
         .join("\n")
     );
 }
 
 #[test]
-fn test_2bit_shr() {
+fn test_inv_of_inv_chain() {
     let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+    let x = ValueWire::new_input("x", 1, &id_gen).at(0);
 
-    let a = ValueWire::new_input("a", 2, &id_gen);
-    let b = ValueWire::new_const(1, &id_gen);
+    let inv_x = BoolWire::inv(&x);
+    assert!(Rc::ptr_eq(&BoolWire::inv(&inv_x), &x));
 
-    let c = ValueWire::bit_shr(&a, &b);
+    // Even if a double inversion was built without folding, inverting it again just unwraps one
+    // level, giving INV(x) rather than a triple inversion
+    let double_inv = BoolWire::inv_with_new_id(&inv_x);
+    let triple_inv = BoolWire::inv(&double_inv);
+    assert!(Rc::ptr_eq(&triple_inv, &inv_x));
+}
 
-    let outputs = vec![CircuitOutput::new("c", c)];
+#[test]
+fn test_nand_nor_xnor() {
+    type Gate = fn(&Rc<BoolWire>, &Rc<BoolWire>) -> Rc<BoolWire>;
+    type TruthTable = fn(bool, bool) -> bool;
 
-    let circuit = generate_bristol(&outputs);
+    let gates: [(Gate, TruthTable); 3] = [
+        (BoolWire::nand, |a, b| !(a && b)),
+        (BoolWire::nor, |a, b| !(a || b)),
+        (BoolWire::xnor, |a, b| a == b),
+    ];
 
-    let bristol_string = circuit.get_bristol_string().unwrap();
+    for (gate, expected) in gates {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
 
-    assert_eq!(
-        bristol_string,
-        vec![
-            "5 7", //
-            "1 2",
-            "1 2",
-            "",
-            "1 1 1 2 INV",
-            "1 1 2 5 INV",
-            "2 1 0 0 3 XOR",
-            "1 1 3 4 INV",
-            "1 1 4 6 INV",
-            ""
-        ]
-        .join("\n")
-    );
-}
+        let a = ValueWire::new_input("a", 1, &id_gen).at(0);
+        let b = ValueWire::new_input("b", 1, &id_gen).at(0);
 
-#[test]
-fn test_4bit_mul() {
-    test_4bit_binary_op(ValueWire::mul, |a, b| (a * b) & 0xf);
-}
+        let circuit =
+            generate_bristol(&[CircuitOutput::new("c", BoolWire::as_value(&gate(&a, &b)))])
+                .unwrap();
 
-#[test]
-fn test_4bit_exp() {
-    test_4bit_binary_op_with_const(ValueWire::exp, |a, b| a.pow(b.try_into().unwrap()) & 0xf);
-}
+        for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+            let inputs =
+                HashMap::from([("a".to_string(), a as usize), ("b".to_string(), b as usize)]);
 
-#[test]
-fn test_4bit_shl() {
-    test_4bit_binary_op_with_const(ValueWire::bit_shl, |a, b| (a << b) & 0xf);
-}
+            assert_eq!(
+                eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+                &(expected(a, b) as usize)
+            );
+        }
 
-#[test]
-fn test_4bit_shr() {
-    test_4bit_binary_op_with_const(ValueWire::bit_shr, |a, b| (a >> b) & 0xf);
-}
+        // Constants fold: both constant gives a constant, and one constant gives x, !x or a constant
+        for constant in [false, true] {
+            let c = ValueWire::new_const(constant as usize, &id_gen)
+                .resize(1)
+                .at(0);
+
+            for other in [false, true] {
+                let other_wire = ValueWire::new_const(other as usize, &id_gen)
+                    .resize(1)
+                    .at(0);
+
+                assert!(matches!(
+                    gate(&c, &other_wire).data,
+                    BoolData::Const(value) if value == expected(constant, other)
+                ));
+            }
 
-#[test]
-fn test_4bit_div() {
-    test_4bit_binary_op(ValueWire::div, |a, b| if b == 0 { 0xf } else { a / b });
-}
+            let folded = gate(&c, &a);
 
-#[test]
-fn test_4bit_mod() {
-    test_4bit_binary_op(ValueWire::mod_, |a, b| if b == 0 { a } else { a % b });
-}
+            match (expected(constant, false), expected(constant, true)) {
+                (false, true) => assert!(Rc::ptr_eq(&folded, &a)),
+                (true, false) => {
+                    assert!(matches!(&folded.data, BoolData::Inv(_, x) if Rc::ptr_eq(x, &a)))
+                }
+                (value, _) => assert!(matches!(folded.data, BoolData::Const(v) if v == value)),
+            }
+        }
+    }
 
-#[test]
-fn test_4bit_add() {
-    test_4bit_binary_op(ValueWire::add, |a, b| (a + b) & 0xf);
-}
+    // nor doesn't need an INV on its output
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+    let a = ValueWire::new_input("a", 1, &id_gen).at(0);
+    let b = ValueWire::new_input("b", 1, &id_gen).at(0);
 
-#[test]
-fn test_4bit_sub() {
-    test_4bit_binary_op(ValueWire::sub, |a, b| (a.wrapping_sub(b)) & 0xf);
+    assert!(matches!(BoolWire::nor(&a, &b).data, BoolData::And(..)));
 }
 
 #[test]
-fn test_4bit_and() {
-    test_4bit_binary_op(ValueWire::bit_and, |a, b| a & b);
-}
+fn test_bool_mux() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
 
-#[test]
-fn test_4bit_or() {
-    test_4bit_binary_op(ValueWire::bit_or, |a, b| a | b);
-}
+    let x = ValueWire::new_input("x", 3, &id_gen);
+    let (cond, a, b) = (x.at(0), x.at(1), x.at(2));
+
+    let circuit = generate_bristol_with_options(
+        &[CircuitOutput::new(
+            "c",
+            BoolWire::as_value(&BoolWire::mux(&cond, &a, &b)),
+        )],
+        &GenerateBristolOptions {
+            topological_order: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let ops = circuit
+        .gates
+        .iter()
+        .map(|gate| gate.op.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(ops, ["XOR", "AND", "XOR"]);
+
+    for x in 0..8 {
+        let inputs = HashMap::from([("x".to_string(), x)]);
+        let (cond, a, b) = (x & 1, (x >> 1) & 1, x >> 2);
+
+        let expected = if cond == 1 { a } else { b };
+        assert_eq!(
+            eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+            &expected
+        );
+    }
 
-#[test]
-fn test_4bit_xor() {
-    test_4bit_binary_op(ValueWire::bit_xor, |a, b| a ^ b);
+    let t = ValueWire::new_const(1, &id_gen).at(0);
+    let f = ValueWire::new_const(0, &id_gen).resize(1).at(0);
+
+    assert!(Rc::ptr_eq(&BoolWire::mux(&t, &a, &b), &a));
+    assert!(Rc::ptr_eq(&BoolWire::mux(&f, &a, &b), &b));
+    assert!(Rc::ptr_eq(&BoolWire::mux(&cond, &a, &a), &a));
 }
 
 #[test]
-fn test_4bit_less_than() {
-    test_4bit_binary_op(
-        |a, b| BoolWire::as_value(&ValueWire::less_than(a, b)),
-        |a, b| if a < b { 1 } else { 0 },
-    );
+fn test_n_and_or_xor() {
+    type Reduce = fn(&[Rc<BoolWire>], &Rc<RefCell<IdGenerator>>) -> Rc<BoolWire>;
+    type Expected = fn(usize, usize) -> bool;
+
+    let reductions: [(Reduce, Expected); 3] = [
+        (BoolWire::n_and, |x, len| x == (1 << len) - 1),
+        (BoolWire::n_or, |x, _| x != 0),
+        (BoolWire::n_xor, |x, _| x.count_ones() % 2 == 1),
+    ];
+
+    for (reduce, expected) in reductions {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        // No inputs gives the identity as a constant
+        assert!(matches!(
+            reduce(&[], &id_gen).data,
+            BoolData::Const(value) if value == expected(0, 0)
+        ));
+
+        for len in 1..=8 {
+            let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+            let x = ValueWire::new_input("x", len, &id_gen);
+
+            let result = reduce(&x.bits, &id_gen);
+
+            if len == 1 {
+                assert!(Rc::ptr_eq(&result, &x.bits[0]));
+                continue;
+            }
+
+            let circuit =
+                generate_bristol(&[CircuitOutput::new("c", BoolWire::as_value(&result))]).unwrap();
+
+            for x in 0..(1 << len) {
+                let inputs = HashMap::from([("x".to_string(), x)]);
+
+                assert_eq!(
+                    eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+                    &(expected(x, len) as usize)
+                );
+            }
+        }
+    }
+
+    // Balanced: log2(n) levels for power of two lengths
+    for len in [2, 4, 8, 16] {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+        let x = ValueWire::new_input("x", len, &id_gen);
+
+        for reduce in [BoolWire::n_and, BoolWire::n_xor] {
+            let circuit = generate_bristol(&[CircuitOutput::new(
+                "c",
+                BoolWire::as_value(&reduce(&x.bits, &id_gen)),
+            )])
+            .unwrap();
+
+            assert_eq!(compute_depth(&circuit).unwrap(), len.ilog2() as usize);
+        }
+    }
 }
 
 #[test]
-fn test_4bit_equal() {
-    test_4bit_binary_op(
-        |a, b| BoolWire::as_value(&ValueWire::equal(a, b)),
-        |a, b| if a == b { 1 } else { 0 },
-    );
+#[should_panic(expected = "different IdGenerator")]
+fn test_n_and_mixed_id_gen() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+    let other_id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    // Both generators start at id 0, so these two inputs would share a wire
+    let a = ValueWire::new_input("a", 1, &other_id_gen).at(0);
+    let b = ValueWire::new_input("b", 1, &id_gen).at(0);
+
+    BoolWire::n_and(&[a, b], &id_gen);
 }
 
 #[test]
-fn test_4bit_bool_and() {
-    test_4bit_binary_op(
-        |a, b| BoolWire::as_value(&ValueWire::bool_and(a, b)),
-        |a, b| if a != 0 && b != 0 { 1 } else { 0 },
-    );
+fn test_bool_wire_depth() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 1, &id_gen).at(0);
+    let b = ValueWire::new_input("b", 1, &id_gen).at(0);
+
+    assert_eq!(BoolWire::depth(&a), 0);
+    assert_eq!(BoolWire::depth(&BoolWire::xor(&a, &b)), 1);
+
+    let inputs = ValueWire::new_input("x", 20, &id_gen);
+    let mut chain = a.clone();
+
+    for (i, bit) in inputs.bits.iter().enumerate() {
+        chain = BoolWire::xor(&chain, bit);
+        assert_eq!(BoolWire::depth(&chain), i + 1);
+    }
+
+    let mut long_chain = a.clone();
+
+    for _ in 0..1000 {
+        long_chain = BoolWire::xor(&long_chain, &b);
+    }
+
+    assert_eq!(BoolWire::depth(&long_chain), 1000);
 }
 
 #[test]
-fn test_4bit_5mul() {
-    test_4bit_unary_op(
-        |a| ValueWire::mul(&ValueWire::new_const(5, &a.id_gen), a),
-        |a| (5 * a) & 0xf,
-    );
+fn test_value_wire_depth() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_input("b", 8, &id_gen);
+
+    assert_eq!(a.depth(), 0);
+    assert_eq!(ValueWire::bit_xor(&a, &b).depth(), 1);
+
+    let sum = ValueWire::add(&a, &b);
+    let circuit = generate_bristol(&[CircuitOutput::new("c", sum.clone())]).unwrap();
+
+    // compute_depth also counts the output COPY gates, which the add doesn't need here
+    assert_eq!(sum.depth(), compute_depth(&circuit).unwrap());
 }
 
 #[test]
-fn test_4bit_1add() {
-    test_4bit_unary_op(
-        |a| ValueWire::add(&ValueWire::new_const(1, &a.id_gen), a),
-        |a| (1 + a) & 0xf,
-    );
+fn test_majority3() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let x = ValueWire::new_input("x", 3, &id_gen);
+    let majority = BoolWire::majority3(&x.at(0), &x.at(1), &x.at(2));
+
+    let circuit =
+        generate_bristol(&[CircuitOutput::new("c", BoolWire::as_value(&majority))]).unwrap();
+
+    assert_eq!(circuit.gates.iter().filter(|g| g.op == "AND").count(), 1);
+
+    for x in 0..8usize {
+        let inputs = HashMap::from([("x".to_string(), x)]);
+        let expected = (x.count_ones() >= 2) as usize;
+
+        assert_eq!(
+            eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+            &expected
+        );
+    }
+
+    // With a constant input it's just an AND or an OR of the others, in any position
+    let t = ValueWire::new_const(1, &id_gen).at(0);
+    let f = ValueWire::new_const(0, &id_gen).resize(1).at(0);
+    let (a, b) = (x.at(0), x.at(1));
+
+    for majority in [
+        BoolWire::majority3(&f, &a, &b),
+        BoolWire::majority3(&a, &f, &b),
+        BoolWire::majority3(&a, &b, &f),
+    ] {
+        assert!(matches!(&majority.data, BoolData::And(_, x, y)
+            if Rc::ptr_eq(x, &a) && Rc::ptr_eq(y, &b)));
+    }
+
+    for majority in [
+        BoolWire::majority3(&t, &a, &b),
+        BoolWire::majority3(&a, &t, &b),
+        BoolWire::majority3(&a, &b, &t),
+    ] {
+        // or(a, b) = !(!a & !b)
+        assert!(matches!(&majority.data, BoolData::Inv(_, inner)
+            if matches!(inner.data, BoolData::And(..))));
+    }
+
+    assert!(matches!(
+        BoolWire::majority3(&t, &t, &a).data,
+        BoolData::Const(true)
+    ));
+    assert!(matches!(
+        BoolWire::majority3(&f, &a, &f).data,
+        BoolData::Const(false)
+    ));
+    assert!(Rc::ptr_eq(&BoolWire::majority3(&t, &a, &f), &a));
 }
 
 #[test]
-fn test_4bit_negate() {
-    test_4bit_unary_op(ValueWire::negate, |a| (16 - a) & 0xf);
+fn test_constant_outputs_have_no_inv_chains() {
+    for constant_strategy in [ConstantStrategy::default(), ConstantStrategy::ReservedWires] {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+        let a = ValueWire::new_input("a", 2, &id_gen);
+
+        // special_false and special_true (or the reserved wires) provide the constant bits
+        let c = ValueWire {
+            id_gen: id_gen.clone(),
+            bits: vec![
+                a.at(0),
+                ValueWire::new_const(1, &id_gen).at(0),
+                ValueWire::new_const(0, &id_gen).at(0),
+                BoolWire::inv(&a.at(1)),
+            ],
+        };
+
+        let circuit = generate_bristol_with_options(
+            &[CircuitOutput::new("c", c)],
+            &GenerateBristolOptions {
+                constant_strategy,
+                simplify: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let inv_outputs = circuit
+            .gates
+            .iter()
+            .filter(|gate| gate.op == "INV")
+            .map(|gate| gate.outputs[0])
+            .collect::<Vec<_>>();
+
+        for gate in circuit.gates.iter().filter(|gate| gate.op == "INV") {
+            assert!(!inv_outputs.contains(&gate.inputs[0]));
+        }
+
+        let inputs = HashMap::from([("a".to_string(), 0b10)]);
+        assert_eq!(eval(&circuit, &inputs).unwrap().get("c").unwrap(), &0b0010);
+    }
 }
 
 #[test]
-fn test_4bit_which_is_larger() {
-    test_4bit_binary_op(
-        |a, b| {
-            // Based on summon generation for:
-            // export default (a, b) => a === b ? 0 : a > b ? 1 : 2;
-            // (equivalent to mpc-hello circuit)
+fn test_simplify_removes_inv_pairs() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
 
-            // 2 1 0 1 2 AEq
-            let w2 = ValueWire::equal(a, b);
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_input("b", 8, &id_gen);
 
-            // 2 1 0 1 3 AGt
-            let w3 = ValueWire::greater_than(a, b);
+    // inv_with_new_id skips the folding that BoolWire::inv does
+    let double_inv = |x: &Rc<BoolWire>| BoolWire::inv_with_new_id(&BoolWire::inv_with_new_id(x));
 
-            // 1 1 2 4 ANot
-            let w4 = BoolWire::inv(&w2);
+    let c0 = ValueWire {
+        id_gen: id_gen.clone(),
+        bits: ValueWire::add(&a, &b).bits.iter().map(double_inv).collect(),
+    };
 
-            // 1 1 3 5 ANot
-            let w5 = BoolWire::inv(&w3);
+    let c = ValueWire::bit_and(&c0, &ValueWire::new_const(1, &id_gen));
+    let outputs = vec![CircuitOutput::new("c", c)];
 
-            // 2 1 4 5 6 ABoolAnd
-            let w6 = BoolWire::and(&w4, &w5);
+    let unsimplified = generate_bristol_with_options(
+        &outputs,
+        &GenerateBristolOptions {
+            simplify: false,
+            ..Default::default()
+        },
+    )
+    .unwrap();
 
-            // 2 1 6 7 8 AMul
-            let w8 = ValueWire::mul(
-                &BoolWire::as_value(&w6).resize(4),
-                &ValueWire::new_const(2, &a.id_gen), // w7 is 2
-            );
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    assert_eq!(unsimplified.gates.len(), 10);
+
+    // Same as test_8bit_xor_and_1, which doesn't have the INV pair
+    assert_eq!(
+        circuit.get_bristol_string().unwrap(),
+        vec![
+            "8 24", //
+            "2 8 8",
+            "1 8",
+            "",
+            "2 1 0 8 16 XOR",
+            "2 1 0 0 17 XOR",
+            "1 1 17 18 COPY",
+            "1 1 17 19 COPY",
+            "1 1 17 20 COPY",
+            "1 1 17 21 COPY",
+            "1 1 17 22 COPY",
+            "1 1 17 23 COPY",
+            "",
+        ]
+        .join("\n")
+    );
+
+    for (a, b) in [(0, 0), (1, 2), (3, 200), (255, 255)] {
+        let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+
+        assert_eq!(
+            eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+            &((a + b) & 1)
+        );
+    }
+}
+
+#[test]
+fn test_simplify_identities() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let not_a = ValueWire::bit_not(&a);
+
+    // (a & a) ^ (b & (a & !a)) ^ (a ^ !a) == !a, built without the constructor folding
+    let and_self = ValueWire::bit_and(&a, &a);
+    let and_complement = ValueWire::bit_and(&a, &not_a);
+    let xor_complement = ValueWire::bit_xor(&a, &not_a);
+
+    let c = ValueWire::bit_xor(
+        &ValueWire::bit_xor(&and_self, &ValueWire::bit_and(&b, &and_complement)),
+        &xor_complement,
+    );
+
+    let outputs = vec![CircuitOutput::new("c", c)];
+
+    let unsimplified = generate_bristol_with_options(
+        &outputs,
+        &GenerateBristolOptions {
+            simplify: false,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    assert!(circuit.gates.len() < unsimplified.gates.len());
+    assert!(circuit.gates.iter().all(|g| g.op != "AND"));
+
+    for a in 0..16 {
+        for b in 0..16 {
+            let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+            let expected = !a & 0xf;
+
+            assert_eq!(
+                eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+                &expected
+            );
+            assert_eq!(
+                eval(&unsimplified, &inputs).unwrap().get("c").unwrap(),
+                &expected
+            );
+        }
+    }
+}
+
+#[test]
+fn test_deduplicate_identical_gates() {
+    let build = || {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let a = ValueWire::new_input("a", 4, &id_gen);
+        let b = ValueWire::new_input("b", 4, &id_gen);
+
+        // Two independently built copies of a ^ b
+        let x = ValueWire::bit_xor(&a, &b);
+        let y = ValueWire::bit_xor(&b, &a);
+
+        vec![CircuitOutput::new("c", ValueWire::bit_and(&x, &y))]
+    };
+
+    let count_xors = |options: &GenerateBristolOptions| {
+        let circuit = generate_bristol_with_options(&build(), options).unwrap();
+
+        for a in 0..16 {
+            for b in 0..16 {
+                let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+                assert_eq!(eval(&circuit, &inputs).unwrap().get("c").unwrap(), &(a ^ b));
+            }
+        }
+
+        circuit.gates.iter().filter(|gate| gate.op == "XOR").count()
+    };
+
+    let without = count_xors(&GenerateBristolOptions {
+        deduplicate: false,
+        ..Default::default()
+    });
+
+    let with = count_xors(&GenerateBristolOptions::default());
+
+    // The XORs of one copy are shared with the other
+    assert_eq!((without, with), (8, 4));
+}
+
+#[test]
+fn test_deduplicate_nested_subexpressions() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_input("b", 8, &id_gen);
+
+    // Two independently built adders, where each gate's inputs are only identical once the gates
+    // below it have been merged
+    let single = generate_bristol(&[CircuitOutput::new("c", ValueWire::add(&a, &b))]).unwrap();
+    let outputs = vec![
+        CircuitOutput::new("c", ValueWire::add(&a, &b)),
+        CircuitOutput::new("d", ValueWire::add(&b, &a)),
+    ];
+
+    let circuit = generate_bristol(&outputs).unwrap();
+    let without = generate_bristol_with_options(
+        &outputs,
+        &GenerateBristolOptions {
+            deduplicate: false,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let logic_gates = |circuit: &BristolCircuit| {
+        circuit
+            .gates
+            .iter()
+            .filter(|gate| gate.op != "COPY")
+            .count()
+    };
+
+    // The second adder is merged into the first, apart from the 8 gates writing d's output wires,
+    // which it needs either way (they would otherwise be COPYs)
+    assert_eq!(logic_gates(&circuit), logic_gates(&single) + 8);
+    assert_eq!(logic_gates(&without), 2 * logic_gates(&single));
+
+    let mut rng = SplitMix64::new(1024);
+
+    for _ in 0..20 {
+        let inputs = HashMap::from([
+            ("a".to_string(), rng.below(256)),
+            ("b".to_string(), rng.below(256)),
+        ]);
+
+        assert_eq!(
+            eval(&circuit, &inputs).unwrap(),
+            eval(&without, &inputs).unwrap()
+        );
+    }
+}
+
+fn assert_strict_topological_order(circuit: &BristolCircuit) {
+    for gate in &circuit.gates {
+        assert!(gate.inputs.iter().max() < gate.outputs.iter().min());
+    }
+}
+
+#[test]
+fn test_topological_order_4bit_add() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let outputs = vec![CircuitOutput::new("c", ValueWire::add(&a, &b))];
+
+    let options = GenerateBristolOptions {
+        topological_order: true,
+        ..Default::default()
+    };
+
+    let circuit = generate_bristol_with_options(&outputs, &options).unwrap();
+    assert_strict_topological_order(&circuit);
+
+    for a in 0..16 {
+        for b in 0..16 {
+            let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+            assert_eq!(
+                eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+                &((a + b) % 16)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_topological_order_output_read_by_gate() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    // The bits of c are also inputs to the gates computing d
+    let c = ValueWire::add(&a, &b);
+    let d = ValueWire::mul(&c, &a);
+
+    let outputs = vec![CircuitOutput::new("c", c), CircuitOutput::new("d", d)];
+
+    assert!(!generate_bristol(&outputs)
+        .unwrap()
+        .gates
+        .iter()
+        .all(|gate| gate.inputs.iter().max() < gate.outputs.iter().min()));
+
+    let options = GenerateBristolOptions {
+        topological_order: true,
+        ..Default::default()
+    };
+
+    let circuit = generate_bristol_with_options(&outputs, &options).unwrap();
+    assert_strict_topological_order(&circuit);
+
+    for a in 0..16 {
+        for b in 0..16 {
+            let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+            let outputs = eval(&circuit, &inputs).unwrap();
+
+            assert_eq!(outputs.get("c").unwrap(), &((a + b) % 16));
+            assert_eq!(outputs.get("d").unwrap(), &(((a + b) * a) % 16));
+        }
+    }
+}
+
+#[test]
+fn test_canonical_ordering() {
+    // (a & b) ^ (c & d), built in two different orders
+    let build = |reversed: bool| {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let inputs = ["a", "b", "c", "d"]
+            .iter()
+            .map(|name| ValueWire::new_input(name, 4, &id_gen))
+            .collect::<Vec<_>>();
+
+        let [a, b, c, d] = inputs.as_slice() else {
+            unreachable!()
+        };
+
+        let e = match reversed {
+            false => ValueWire::bit_xor(&ValueWire::bit_and(a, b), &ValueWire::bit_and(c, d)),
+            true => {
+                let cd = ValueWire::bit_and(d, c);
+                let ab = ValueWire::bit_and(b, a);
+                ValueWire::bit_xor(&cd, &ab)
+            }
+        };
+
+        vec![CircuitOutput::new("e", e)]
+    };
+
+    let options = GenerateBristolOptions {
+        canonical_ordering: true,
+        ..Default::default()
+    };
+
+    let forward = build(false);
+    let reversed = build(true);
+
+    assert_ne!(
+        generate_bristol(&forward)
+            .unwrap()
+            .get_bristol_string()
+            .unwrap(),
+        generate_bristol(&reversed)
+            .unwrap()
+            .get_bristol_string()
+            .unwrap()
+    );
+
+    let forward_circuit = generate_bristol_with_options(&forward, &options).unwrap();
+    let reversed_circuit = generate_bristol_with_options(&reversed, &options).unwrap();
+
+    assert_eq!(
+        forward_circuit.get_bristol_string().unwrap(),
+        reversed_circuit.get_bristol_string().unwrap()
+    );
+
+    let inputs = HashMap::from([
+        ("a".to_string(), 0b1100),
+        ("b".to_string(), 0b1010),
+        ("c".to_string(), 0b0110),
+        ("d".to_string(), 0b0011),
+    ]);
+
+    assert_eq!(
+        eval(&forward_circuit, &inputs).unwrap().get("e").unwrap(),
+        &0b1010
+    );
+}
+
+#[test]
+fn test_2bit_mul() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_input("b", 2, &id_gen);
+
+    let c = ValueWire::mul(&a, &b);
+
+    let outputs = vec![CircuitOutput::new("c", c)];
+
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    let bristol_string = circuit.get_bristol_string().unwrap();
+
+    assert_eq!(
+        bristol_string,
+        [
+            "4 8",
+            "2 2 2",
+            "1 2",
+            "",
+            "2 1 0 2 6 AND",
+            "2 1 0 3 4 AND",
+            "2 1 1 2 5 AND",
+            "2 1 4 5 7 XOR",
+            ""
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn test_2bit_exp() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_const(2, &id_gen);
+
+    let c = ValueWire::exp(&a, &b);
+
+    let outputs = vec![CircuitOutput::new("c", c)];
+
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    let bristol_string = circuit.get_bristol_string().unwrap();
+
+    assert_eq!(
+        bristol_string,
+        [
+            "3 5",
+            "1 2",
+            "1 2",
+            "",
+            "1 1 0 3 COPY",
+            "2 1 0 1 2 AND",
+            "2 1 2 2 4 XOR",
+            ""
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn test_2bit_shl() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_const(1, &id_gen);
+
+    let c = ValueWire::bit_shl(&a, &b);
+
+    let outputs = vec![CircuitOutput::new("c", c)];
+
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    let bristol_string = circuit.get_bristol_string().unwrap();
+
+    assert_eq!(
+        bristol_string,
+        vec![
+            "2 4", //
+            "1 2",
+            "1 2",
+            "",
+            "2 1 0 0 2 XOR",
+            "1 1 0 3 COPY",
+            ""
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn test_2bit_shr() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_const(1, &id_gen);
+
+    let c = ValueWire::bit_shr(&a, &b);
+
+    let outputs = vec![CircuitOutput::new("c", c)];
+
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    let bristol_string = circuit.get_bristol_string().unwrap();
+
+    assert_eq!(
+        bristol_string,
+        vec![
+            "2 4", //
+            "1 2",
+            "1 2",
+            "",
+            "1 1 1 2 COPY",
+            "2 1 0 0 3 XOR",
+            ""
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn test_4bit_mul() {
+    test_4bit_binary_op(ValueWire::mul, |a, b| (a * b) & 0xf);
+}
+
+#[test]
+fn test_2bit_partial_product_matrix() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_input("b", 2, &id_gen);
+
+    let matrix = ValueWire::partial_product_matrix(&a, &b);
+
+    assert_eq!(matrix.len(), 2);
+
+    for (i, row) in matrix.iter().enumerate() {
+        assert_eq!(row.len(), 2);
+
+        for (j, product) in row.iter().enumerate() {
+            let BoolData::And(_, x, y) = &product.data else {
+                panic!("Expected AND");
+            };
+
+            assert!(Rc::ptr_eq(x, &a.bits[i]));
+            assert!(Rc::ptr_eq(y, &b.bits[j]));
+        }
+    }
+}
+
+#[test]
+fn test_4bit_partial_product_matrix_sum() {
+    test_4bit_binary_op(
+        |a, b| {
+            let matrix = ValueWire::partial_product_matrix(a, b);
+
+            matrix
+                .into_iter()
+                .enumerate()
+                .map(|(i, row)| {
+                    ValueWire {
+                        id_gen: a.id_gen.clone(),
+                        bits: row,
+                    }
+                    .shift_up_const(i)
+                })
+                .fold(ValueWire::new_const(0, &a.id_gen), |sum, row| {
+                    ValueWire::add(&sum, &row)
+                })
+        },
+        |a, b| (a * b) & 0xf,
+    );
+}
+
+#[test]
+fn test_exp_const_base() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let exp_const = |a: usize, b: usize| {
+        ValueWire::exp(
+            &ValueWire::new_const(a, &id_gen),
+            &ValueWire::new_const(b, &id_gen),
+        )
+        .as_usize()
+    };
+
+    assert_eq!(exp_const(0, 5), Some(0));
+    assert_eq!(exp_const(1, 100), Some(1));
+    assert_eq!(exp_const(0, 0), Some(1));
+}
+
+#[test]
+fn test_4bit_exp() {
+    test_4bit_binary_op_with_const(ValueWire::exp, |a, b| a.pow(b.try_into().unwrap()) & 0xf);
+}
+
+#[test]
+fn test_4bit_shl() {
+    test_4bit_binary_op_with_const(ValueWire::bit_shl, |a, b| (a << b) & 0xf);
+}
+
+#[test]
+fn test_4bit_shr() {
+    test_4bit_binary_op_with_const(ValueWire::bit_shr, |a, b| (a >> b) & 0xf);
+}
+
+#[test]
+fn test_4bit_div() {
+    test_4bit_binary_op(ValueWire::div, |a, b| if b == 0 { 0xf } else { a / b });
+}
+
+#[test]
+fn test_4bit_mod() {
+    test_4bit_binary_op(ValueWire::mod_, |a, b| if b == 0 { a } else { a % b });
+}
+
+#[test]
+fn test_4bit_add() {
+    test_4bit_binary_op(ValueWire::add, |a, b| (a + b) & 0xf);
+}
+
+#[test]
+fn test_4bit_sub() {
+    test_4bit_binary_op(ValueWire::sub, |a, b| (a.wrapping_sub(b)) & 0xf);
+}
+
+#[test]
+fn test_4bit_and() {
+    test_4bit_binary_op(ValueWire::bit_and, |a, b| a & b);
+}
+
+#[test]
+fn test_4bit_or() {
+    test_4bit_binary_op(ValueWire::bit_or, |a, b| a | b);
+}
+
+#[test]
+fn test_4bit_xor() {
+    test_4bit_binary_op(ValueWire::bit_xor, |a, b| a ^ b);
+}
+
+#[test]
+fn test_4bit_less_than() {
+    test_4bit_binary_op(
+        |a, b| BoolWire::as_value(&ValueWire::less_than(a, b)),
+        |a, b| if a < b { 1 } else { 0 },
+    );
+}
+
+#[test]
+fn test_4bit_equal() {
+    test_4bit_binary_op(
+        |a, b| BoolWire::as_value(&ValueWire::equal(a, b)),
+        |a, b| if a == b { 1 } else { 0 },
+    );
+}
+
+#[test]
+fn test_4bit_cmp_ripple() {
+    test_4bit_binary_op(
+        |a, b| BoolWire::as_value(&ValueWire::cmp_ripple(a, b).0),
+        |a, b| if a == b { 1 } else { 0 },
+    );
+
+    test_4bit_binary_op(
+        |a, b| BoolWire::as_value(&ValueWire::cmp_ripple(a, b).1),
+        |a, b| if a < b { 1 } else { 0 },
+    );
+
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let (eq, lt) = ValueWire::cmp_ripple(&a, &b);
+
+    let ripple_circuit = generate_bristol(&[
+        CircuitOutput::new("eq", BoolWire::as_value(&eq)),
+        CircuitOutput::new("lt", BoolWire::as_value(&lt)),
+    ])
+    .unwrap();
+
+    let tree_circuit = generate_bristol(&[
+        CircuitOutput::new("eq", BoolWire::as_value(&ValueWire::equal(&a, &b))),
+        CircuitOutput::new("lt", BoolWire::as_value(&ValueWire::less_than(&a, &b))),
+    ])
+    .unwrap();
+
+    // 21 vs 32 gates
+    assert!(ripple_circuit.gates.len() < tree_circuit.gates.len());
+}
+
+#[test]
+fn test_4bit_equal_const() {
+    for constant in 0..16 {
+        test_4bit_unary_op(
+            |a| BoolWire::as_value(&ValueWire::equal_const(a, constant)),
+            |a| if a == constant { 1 } else { 0 },
+        );
+
+        test_4bit_unary_op(
+            |a| {
+                BoolWire::as_value(&ValueWire::equal(
+                    &ValueWire::new_const(constant, &a.id_gen),
+                    a,
+                ))
+            },
+            |a| if a == constant { 1 } else { 0 },
+        );
+    }
+
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_input("b", 8, &id_gen);
+
+    let const_circuit = generate_bristol(&[CircuitOutput::new(
+        "c",
+        BoolWire::as_value(&ValueWire::equal_const(&a, 0b10010110)),
+    )])
+    .unwrap();
+
+    let var_circuit = generate_bristol(&[CircuitOutput::new(
+        "c",
+        BoolWire::as_value(&ValueWire::equal(&a, &b)),
+    )])
+    .unwrap();
+
+    // 4 INV + 7 AND, compared with 8 XOR + 8 INV + 7 AND
+    assert_eq!(const_circuit.gates.len(), 11);
+    assert!(const_circuit.gates.len() < var_circuit.gates.len());
+}
+
+#[test]
+fn test_mul_bool_tree_one_hot() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let index = ValueWire::new_input("index", 2, &id_gen);
+    let names = ["v0", "v1", "v2", "v3"];
+
+    let values = names
+        .iter()
+        .map(|name| ValueWire::new_input(name, 2, &id_gen))
+        .collect::<Vec<_>>();
+
+    let one_hot = (0..4)
+        .map(|i| ValueWire::equal_const(&index, i))
+        .collect::<Vec<_>>();
+
+    let c = ValueWire::mul_bool_tree(&one_hot, &values);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", c)]).unwrap();
+
+    for index in 0..4 {
+        for packed in 0..256 {
+            let mut inputs = HashMap::new();
+            inputs.insert("index".to_string(), index);
+
+            for (i, name) in names.iter().enumerate() {
+                inputs.insert(name.to_string(), (packed >> (2 * i)) & 3);
+            }
+
+            let result = eval(&circuit, &inputs).unwrap();
+
+            assert_eq!(result.get("c").unwrap(), &inputs[names[index]]);
+        }
+    }
+}
+
+#[test]
+fn test_adder_tree_sum() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+    let names = (0..8).map(|i| format!("v{}", i)).collect::<Vec<_>>();
+
+    // Widen to 7 bits so the sum of eight 4-bit values doesn't wrap
+    let values = names
+        .iter()
+        .map(|name| ValueWire::new_input(name, 4, &id_gen).resize(7))
+        .collect::<Vec<_>>();
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", adder_tree::sum(&values))]).unwrap();
+
+    let mut rng = SplitMix64::new(7);
+
+    for _ in 0..500 {
+        let inputs = names
+            .iter()
+            .map(|name| (name.clone(), rng.below(16)))
+            .collect::<HashMap<String, usize>>();
+
+        let expected = inputs.values().sum::<usize>();
+
+        assert_eq!(
+            eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+            &expected
+        );
+    }
+}
+
+#[test]
+fn test_adder_tree_vs_pairwise() {
+    fn pairwise_sum(values: &[ValueWire]) -> ValueWire {
+        if values.len() == 1 {
+            return values[0].clone();
+        }
+
+        let mid = values.len() / 2;
+        ValueWire::add(&pairwise_sum(&values[..mid]), &pairwise_sum(&values[mid..]))
+    }
+
+    fn and_count(circuit: &BristolCircuit) -> usize {
+        circuit.gates.iter().filter(|g| g.op == "AND").count()
+    }
+
+    for bits in [4, 16] {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let values = (0..8)
+            .map(|i| ValueWire::new_input(&format!("v{}", i), bits, &id_gen).resize(bits + 3))
+            .collect::<Vec<_>>();
+
+        let tree = generate_bristol(&[CircuitOutput::new("c", adder_tree::sum(&values))]).unwrap();
+
+        let pairwise = generate_bristol(&[CircuitOutput::new("c", pairwise_sum(&values))]).unwrap();
+
+        // The carry-save levels need fewer ANDs than full adds at every width
+        assert!(and_count(&tree) < and_count(&pairwise));
+
+        // The depth only wins once the pairwise ripple chains are long (at 4 bits the final
+        // prefix adder costs more than the short ripples it replaces)
+        if bits >= 16 {
+            assert!(compute_depth(&tree).unwrap() < compute_depth(&pairwise).unwrap());
+        }
+
+        let mut rng = SplitMix64::new(926);
+
+        for _ in 0..100 {
+            let inputs = (0..8)
+                .map(|i| (format!("v{}", i), rng.below(1 << bits)))
+                .collect::<HashMap<String, usize>>();
+
+            assert_eq!(
+                eval(&tree, &inputs).unwrap(),
+                eval(&pairwise, &inputs).unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_8bit_mod_power_of_two() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let c = ValueWire::mod_(&a, &ValueWire::new_const(4, &id_gen));
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", c)]).unwrap();
+
+    assert_eq!(circuit.gates.iter().filter(|g| g.op == "AND").count(), 0);
+
+    for a in 0..256 {
+        let inputs = HashMap::from([("a".to_string(), a)]);
+
+        assert_eq!(eval(&circuit, &inputs).unwrap().get("c").unwrap(), &(a & 3));
+    }
+}
+
+#[test]
+fn test_8bit_div_power_of_two() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let c = ValueWire::div(&a, &ValueWire::new_const(4, &id_gen));
+    let shr = ValueWire::bit_shr(&a, &ValueWire::new_const(2, &id_gen));
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", c)]).unwrap();
+    let shr_circuit = generate_bristol(&[CircuitOutput::new("c", shr)]).unwrap();
+
+    // Only the output copies and constant wires, the same as the shift itself
+    assert_eq!(circuit.gates.iter().filter(|g| g.op == "AND").count(), 0);
+    assert_eq!(circuit.gates.len(), shr_circuit.gates.len());
+
+    for a in 0..256 {
+        let inputs = HashMap::from([("a".to_string(), a)]);
+
+        assert_eq!(
+            eval(&circuit, &inputs).unwrap(),
+            eval(&shr_circuit, &inputs).unwrap()
+        );
+        assert_eq!(
+            eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+            &(a >> 2)
+        );
+    }
+}
+
+#[test]
+fn test_8bit_div_const() {
+    for divisor in (1..20).chain([85, 127, 200, 255]) {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let a = ValueWire::new_input("a", 8, &id_gen);
+        let c = ValueWire::div(&a, &ValueWire::new_const(divisor, &id_gen));
+
+        let circuit = generate_bristol(&[CircuitOutput::new("c", c)]).unwrap();
+
+        for a in 0..256 {
+            let inputs = HashMap::from([("a".to_string(), a)]);
+
+            assert_eq!(
+                eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+                &(a / divisor)
+            );
+        }
+    }
+
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_const(10, &id_gen);
+
+    let reciprocal = generate_bristol(&[CircuitOutput::new("c", ValueWire::div(&a, &b))]).unwrap();
+    let general = generate_bristol(&[CircuitOutput::new(
+        "c",
+        ValueWire::quotient_remainder(&a, &b).0,
+    )])
+    .unwrap();
+
+    assert!(reciprocal.gates.len() < general.gates.len());
+}
+
+#[test]
+fn test_div_const_reciprocal_width_limit() {
+    // For a divisor of 3, the reciprocal needs 2^(width + 2), which only fits in a usize up to
+    // width = usize::BITS - 3. One bit wider falls back to the general divider.
+    let limit = usize::BITS as usize - 3;
+
+    for width in [limit, limit + 1] {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let a = ValueWire::new_input("a", width, &id_gen);
+        let b = ValueWire::new_const(3, &id_gen);
+
+        let circuit = generate_bristol(&[CircuitOutput::new("c", ValueWire::div(&a, &b))]).unwrap();
+        let general = generate_bristol(&[CircuitOutput::new(
+            "c",
+            ValueWire::quotient_remainder(&a, &b).0,
+        )])
+        .unwrap();
+
+        match width == limit {
+            true => assert!(circuit.gates.len() < general.gates.len()),
+            false => assert_eq!(circuit.gates.len(), general.gates.len()),
+        }
+
+        let mut rng = SplitMix64::new(928);
+        let max = usize::MAX >> (usize::BITS as usize - width);
+
+        for a in [0, 1, 2, 3, max - 1, max]
+            .into_iter()
+            .chain((0..20).map(|_| rng.below(max)))
+        {
+            let inputs = HashMap::from([("a".to_string(), a)]);
+
+            assert_eq!(eval(&circuit, &inputs).unwrap()["c"], a / 3);
+        }
+    }
+}
+
+#[test]
+fn test_equal_same_wire() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let eq = ValueWire::equal(&a, &a);
+
+    assert!(matches!(eq.data, BoolData::Const(true)));
+
+    // a & (a == a) folds back to a, so only the output copies remain
+    let circuit =
+        generate_bristol(&[CircuitOutput::new("c", ValueWire::mul_bool(&eq, &a))]).unwrap();
+
+    assert!(circuit.gates.iter().all(|g| g.op == "COPY"));
+}
+
+#[test]
+fn test_4bit_conditional_swap() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+    let cond = ValueWire::new_input("cond", 1, &id_gen).at(0);
+
+    let (x, y) = ValueWire::conditional_swap(&a, &b, &cond);
+
+    let swap_circuit =
+        generate_bristol(&[CircuitOutput::new("x", x), CircuitOutput::new("y", y)]).unwrap();
+
+    let mux_circuit =
+        generate_bristol(&[CircuitOutput::new("x", ValueWire::mux(&cond, &b, &a))]).unwrap();
+
+    for a in 0..16 {
+        for b in 0..16 {
+            for cond in 0..2 {
+                let inputs = HashMap::from([
+                    ("a".to_string(), a),
+                    ("b".to_string(), b),
+                    ("cond".to_string(), cond),
+                ]);
+
+                let (x, y) = if cond == 1 { (b, a) } else { (a, b) };
+
+                let result = eval(&swap_circuit, &inputs).unwrap();
+                assert_eq!(result.get("x").unwrap(), &x);
+                assert_eq!(result.get("y").unwrap(), &y);
+
+                assert_eq!(eval(&mux_circuit, &inputs).unwrap().get("x").unwrap(), &x);
+            }
+        }
+    }
+
+    assert!(swap_circuit.gates.len() < 2 * mux_circuit.gates.len());
+}
+
+#[test]
+fn test_4bit_select() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+    let cond = ValueWire::new_input("cond", 1, &id_gen).at(0);
+
+    let select_circuit =
+        generate_bristol(&[CircuitOutput::new("x", ValueWire::select(&cond, &a, &b))]).unwrap();
+
+    // The same selection built directly as two masked paths combined with OR
+    let and_or_circuit = generate_bristol(&[CircuitOutput::new(
+        "x",
+        ValueWire::bit_or(
+            &ValueWire::mul_bool(&cond, &a),
+            &ValueWire::mul_bool(&BoolWire::inv(&cond), &b),
+        ),
+    )])
+    .unwrap();
+
+    for a in 0..16 {
+        for b in 0..16 {
+            for cond in 0..2 {
+                let inputs = HashMap::from([
+                    ("a".to_string(), a),
+                    ("b".to_string(), b),
+                    ("cond".to_string(), cond),
+                ]);
+
+                let x = if cond == 1 { a } else { b };
+
+                assert_eq!(
+                    eval(&select_circuit, &inputs).unwrap().get("x").unwrap(),
+                    &x
+                );
+                assert_eq!(
+                    eval(&and_or_circuit, &inputs).unwrap().get("x").unwrap(),
+                    &x
+                );
+            }
+        }
+    }
+
+    assert!(select_circuit.gates.len() < and_or_circuit.gates.len());
+}
+
+#[test]
+fn test_select_mismatched_widths() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+    let cond = ValueWire::new_input("cond", 1, &id_gen).at(0);
+
+    let x = ValueWire::select(&cond, &a, &b);
+    assert_eq!(x.bits.len(), 4);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("x", x)]).unwrap();
+
+    for a in 0..4 {
+        for b in 0..16 {
+            for cond in 0..2 {
+                let inputs = HashMap::from([
+                    ("a".to_string(), a),
+                    ("b".to_string(), b),
+                    ("cond".to_string(), cond),
+                ]);
+
+                let x = if cond == 1 { a } else { b };
+                assert_eq!(eval(&circuit, &inputs).unwrap().get("x").unwrap(), &x);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_4bit_min_max() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let circuit = generate_bristol(&[
+        CircuitOutput::new("min", ValueWire::min(&a, &b)),
+        CircuitOutput::new("max", ValueWire::max(&a, &b)),
+    ])
+    .unwrap();
+
+    for a in 0..16 {
+        for b in 0..16 {
+            let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+            let result = eval(&circuit, &inputs).unwrap();
+
+            assert_eq!(result.get("min").unwrap(), &a.min(b));
+            assert_eq!(result.get("max").unwrap(), &a.max(b));
+        }
+    }
+}
+
+#[test]
+fn test_min_max_array() {
+    let mut rng = SplitMix64::new(1002);
+
+    for len in [1, 2, 3, 4, 8] {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let values = (0..len)
+            .map(|i| ValueWire::new_input(&format!("v{}", i), 4, &id_gen))
+            .collect::<Vec<_>>();
+
+        let circuit = generate_bristol(&[
+            CircuitOutput::new("min", ValueWire::min_array(&values)),
+            CircuitOutput::new("max", ValueWire::max_array(&values)),
+        ])
+        .unwrap();
+
+        for _ in 0..20 {
+            let inputs = (0..len)
+                .map(|i| (format!("v{}", i), rng.below(16)))
+                .collect::<HashMap<_, _>>();
+
+            let result = eval(&circuit, &inputs).unwrap();
+
+            assert_eq!(result.get("min").unwrap(), inputs.values().min().unwrap());
+            assert_eq!(result.get("max").unwrap(), inputs.values().max().unwrap());
+        }
+    }
+}
+
+#[test]
+fn test_popcount() {
+    // AND gates recorded when popcount was added, so a change to the adders that makes it more
+    // expensive shows up here
+    for (width, max_and_gates) in [(4, 5), (8, 15), (16, 37)] {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let a = ValueWire::new_input("a", width, &id_gen);
+        let count = a.popcount();
+        assert_eq!(
+            count.bits.len(),
+            (usize::BITS - width.leading_zeros()) as usize
+        );
+
+        let circuit = generate_bristol(&[CircuitOutput::new("count", count)]).unwrap();
+
+        for a in 0..(1 << width) {
+            let inputs = HashMap::from([("a".to_string(), a)]);
+
+            assert_eq!(
+                eval(&circuit, &inputs).unwrap().get("count").unwrap(),
+                &(a.count_ones() as usize)
+            );
+        }
+
+        let and_gates = circuit.gates.iter().filter(|g| g.op == "AND").count();
+        assert!(and_gates <= max_and_gates);
+    }
+}
+
+#[test]
+fn test_4bit_clz_ctz() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+
+    let circuit = generate_bristol(&[
+        CircuitOutput::new("clz", a.clz()),
+        CircuitOutput::new("ctz", a.ctz()),
+    ])
+    .unwrap();
+
+    for a in 0..16usize {
+        let result = eval(&circuit, &HashMap::from([("a".to_string(), a)])).unwrap();
+
+        assert_eq!(
+            result.get("clz").unwrap(),
+            &((a.leading_zeros() - (usize::BITS - 4)) as usize)
+        );
+        assert_eq!(
+            result.get("ctz").unwrap(),
+            &(a.trailing_zeros().min(4) as usize)
+        );
+    }
+}
+
+#[test]
+fn test_clz_ctz_wide() {
+    let mut rng = SplitMix64::new(1004);
+
+    for width in [8, 13, 16, 32] {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let a = ValueWire::new_input("a", width, &id_gen);
+
+        let clz_circuit = generate_bristol(&[CircuitOutput::new("clz", a.clz())]).unwrap();
+        let ctz_circuit = generate_bristol(&[CircuitOutput::new("ctz", a.ctz())]).unwrap();
+
+        let values = (0..width)
+            .map(|i| 1 << i)
+            .chain([0, (1 << width) - 1])
+            .chain((0..20).map(|_| rng.below(1 << width)));
+
+        for a in values {
+            let inputs = HashMap::from([("a".to_string(), a)]);
+
+            assert_eq!(
+                eval(&clz_circuit, &inputs).unwrap().get("clz").unwrap(),
+                &((a.leading_zeros() - (usize::BITS - width as u32)) as usize)
+            );
+            assert_eq!(
+                eval(&ctz_circuit, &inputs).unwrap().get("ctz").unwrap(),
+                &(a.trailing_zeros().min(width as u32) as usize)
+            );
+        }
+
+        // A constant number of gate layers per halving
+        let max_depth = 5 * (usize::BITS - (width - 1).leading_zeros()) as usize;
+        assert!(compute_depth(&clz_circuit).unwrap() <= max_depth);
+        assert!(compute_depth(&ctz_circuit).unwrap() <= max_depth);
+    }
+}
+
+#[test]
+fn test_4bit_rotate() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+
+    let left = a.rotate_left(1);
+    let right = a.rotate_right(1);
+
+    // Only rewiring, no gates
+    for bit in left.bits.iter().chain(&right.bits) {
+        assert!(matches!(bit.data, BoolData::Input(..)));
+    }
+
+    // Outputs need their own wires, so the only gates are COPYs to them
+    let left_circuit = generate_bristol(&[CircuitOutput::new("c", left.clone())]).unwrap();
+    assert_eq!(left_circuit.wire_count, 8);
+    assert!(left_circuit.gates.iter().all(|gate| gate.op == "COPY"));
+
+    let circuit = generate_bristol(&[
+        CircuitOutput::new("left", left),
+        CircuitOutput::new("right", right),
+    ])
+    .unwrap();
+
+    for a in 0..16 {
+        let result = eval(&circuit, &HashMap::from([("a".to_string(), a)])).unwrap();
+
+        assert_eq!(result.get("left").unwrap(), &(((a << 1) | (a >> 3)) & 0xf));
+        assert_eq!(result.get("right").unwrap(), &(((a >> 1) | (a << 3)) & 0xf));
+    }
+
+    // Rotating by the width (or a multiple of it) is the identity
+    for amount in [0, 4, 8] {
+        assert!(a
+            .rotate_left(amount)
+            .bits
+            .iter()
+            .zip(&a.bits)
+            .all(|(x, y)| Rc::ptr_eq(x, y)));
+    }
+}
+
+#[test]
+fn test_4bit_rotate_var() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let amount = ValueWire::new_input("amount", 4, &id_gen);
+
+    let circuit = generate_bristol(&[
+        CircuitOutput::new("left", ValueWire::rotate_left_var(&a, &amount)),
+        CircuitOutput::new("right", ValueWire::rotate_right_var(&a, &amount)),
+    ])
+    .unwrap();
+
+    for a in 0..16 {
+        for amount in 0..16 {
+            let inputs = HashMap::from([("a".to_string(), a), ("amount".to_string(), amount)]);
+            let result = eval(&circuit, &inputs).unwrap();
+
+            let n = amount % 4;
+            let left = ((a << n) | (a >> ((4 - n) % 4))) & 0xf;
+            let right = ((a >> n) | (a << ((4 - n) % 4))) & 0xf;
+
+            assert_eq!(result.get("left").unwrap(), &left);
+            assert_eq!(result.get("right").unwrap(), &right);
+        }
+    }
+}
+
+#[test]
+fn test_32bit_rotate_var() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 32, &id_gen);
+    let amount = ValueWire::new_input("amount", 5, &id_gen);
+
+    let circuit = generate_bristol(&[CircuitOutput::new(
+        "c",
+        ValueWire::rotate_left_var(&a, &amount),
+    )])
+    .unwrap();
+
+    let mut rng = SplitMix64::new(1006);
+
+    for _ in 0..20 {
+        let a = rng.below(1 << 32);
+        let amount = rng.below(32);
+
+        let inputs = HashMap::from([("a".to_string(), a), ("amount".to_string(), amount)]);
+
+        assert_eq!(
+            eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+            &((a as u32).rotate_left(amount as u32) as usize)
+        );
+    }
+
+    // One layer of 32 muxes per amount bit, except that rotating by 16 swaps the halves, so bits i
+    // and i + 16 share the same c & (x ^ y) and deduplication halves the last layer
+    let and_gates = circuit.gates.iter().filter(|g| g.op == "AND").count();
+    assert_eq!(and_gates, 4 * 32 + 16);
+    // Each mux is XOR, AND, XOR
+    assert_eq!(compute_depth(&circuit).unwrap(), 5 * 3);
+}
+
+#[test]
+fn test_4bit_arith_shr() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 3, &id_gen);
+
+    let variable_circuit =
+        generate_bristol(&[CircuitOutput::new("c", ValueWire::arith_shr(&a, &b))]).unwrap();
+
+    let to_signed = |x: usize| (x as i64) << 60 >> 60;
+
+    for b in 0..8 {
+        let const_circuit = generate_bristol(&[CircuitOutput::new(
+            "c",
+            ValueWire::arith_shr(&a, &ValueWire::new_const(b, &id_gen)),
+        )])
+        .unwrap();
+
+        for a in 0..16 {
+            let expected = ((to_signed(a) >> b) & 0xf) as usize;
+
+            let inputs = HashMap::from([("a".to_string(), a)]);
+            assert_eq!(
+                eval(&const_circuit, &inputs).unwrap().get("c").unwrap(),
+                &expected
+            );
+
+            let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+            assert_eq!(
+                eval(&variable_circuit, &inputs).unwrap().get("c").unwrap(),
+                &expected
+            );
+        }
+    }
+
+    // -4 >> 1 == -2
+    let inputs = HashMap::from([("a".to_string(), 0b1100), ("b".to_string(), 1)]);
+    assert_eq!(
+        eval(&variable_circuit, &inputs).unwrap().get("c").unwrap(),
+        &0b1110
+    );
+}
+
+#[test]
+fn test_sign_extend() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let extended = ValueWire::sign_extend(&a, 8);
+    assert_eq!(extended.bits.len(), 8);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", extended)]).unwrap();
+
+    for a in 0..16 {
+        let expected = (((a as i64) << 60 >> 60) & 0xff) as usize;
+        let inputs = HashMap::from([("a".to_string(), a)]);
+
+        assert_eq!(
+            eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+            &expected
+        );
+    }
+
+    let inputs = |a| HashMap::from([("a".to_string(), a)]);
+
+    // -5 stays -5
+    assert_eq!(
+        eval(&circuit, &inputs(0b1011)).unwrap().get("c").unwrap(),
+        &0b11111011
+    );
+
+    // Positive values are zero-filled
+    assert_eq!(
+        eval(&circuit, &inputs(0b0101)).unwrap().get("c").unwrap(),
+        &0b00000101
+    );
+
+    // Constants stay constant
+    assert_eq!(
+        ValueWire::sign_extend(&ValueWire::new_const(0b1011, &id_gen), 8).as_usize(),
+        Some(0b11111011)
+    );
+}
+
+#[test]
+#[should_panic(expected = "sign_extend would truncate")]
+fn test_sign_extend_narrower() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    ValueWire::sign_extend(&ValueWire::new_input("a", 4, &id_gen), 3);
+}
+
+#[test]
+fn test_concat_slice() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_input("b", 8, &id_gen);
+
+    let low = ValueWire::slice(&a, 0, 4);
+    let high = ValueWire::slice(&a, 4, 8);
+    let rejoined = ValueWire::concat(&low, &high);
+
+    assert!(rejoined
+        .bits
+        .iter()
+        .zip(&a.bits)
+        .all(|(x, y)| Rc::ptr_eq(x, y)));
+
+    let original = generate_bristol(&[CircuitOutput::new("c", ValueWire::add(&a, &b))]).unwrap();
+    let circuit =
+        generate_bristol(&[CircuitOutput::new("c", ValueWire::add(&rejoined, &b))]).unwrap();
+
+    assert_eq!(circuit.gates.len(), original.gates.len());
+
+    // Swapping the nibbles
+    let swapped =
+        generate_bristol(&[CircuitOutput::new("c", ValueWire::concat(&high, &low))]).unwrap();
+
+    for a in [0x00, 0x12, 0xa5, 0xff] {
+        let inputs = HashMap::from([("a".to_string(), a)]);
+
+        assert_eq!(
+            eval(&swapped, &inputs).unwrap().get("c").unwrap(),
+            &(((a & 0xf) << 4) | (a >> 4))
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "out of range")]
+fn test_slice_out_of_range() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    ValueWire::slice(&ValueWire::new_input("a", 8, &id_gen), 4, 9);
+}
+
+#[test]
+fn test_byte_swap() {
+    let mut rng = SplitMix64::new(1010);
+
+    for width in [16, 32] {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let a = ValueWire::new_input("a", width, &id_gen);
+        let swapped = ValueWire::byte_swap(&a);
+
+        // Bit i of byte j moves to bit i of byte (n - 1 - j)
+        for (k, bit) in swapped.bits.iter().enumerate() {
+            let source = (width / 8 - 1 - k / 8) * 8 + k % 8;
+            assert!(Rc::ptr_eq(bit, &a.bits[source]));
+        }
+
+        let circuit = generate_bristol(&[CircuitOutput::new("c", swapped)]).unwrap();
+
+        // Outputs need their own wires, so the only gates are COPYs to them
+        assert!(circuit.gates.iter().all(|gate| gate.op == "COPY"));
+
+        for _ in 0..10 {
+            let a = rng.below(1 << width);
+            let inputs = HashMap::from([("a".to_string(), a)]);
+
+            let expected = match width {
+                16 => (a as u16).swap_bytes() as usize,
+                _ => (a as u32).swap_bytes() as usize,
+            };
+
+            assert_eq!(
+                eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+                &expected
+            );
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "multiple of 8")]
+fn test_byte_swap_partial_byte() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    ValueWire::byte_swap(&ValueWire::new_input("a", 12, &id_gen));
+}
+
+#[test]
+fn test_4bit_bit_reverse() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let reversed = ValueWire::bit_reverse(&a);
+
+    // Reversing twice gives back the same wires
+    assert!(ValueWire::bit_reverse(&reversed)
+        .bits
+        .iter()
+        .zip(&a.bits)
+        .all(|(x, y)| Rc::ptr_eq(x, y)));
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", reversed)]).unwrap();
+
+    // An input wire and an output wire (via COPY) per bit
+    assert!(circuit.gates.iter().all(|gate| gate.op == "COPY"));
+    assert_eq!(circuit.wire_count, 2 * 4);
+
+    for a in 0..16usize {
+        let inputs = HashMap::from([("a".to_string(), a)]);
+
+        assert_eq!(
+            eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+            &(a.reverse_bits() >> (usize::BITS - 4))
+        );
+    }
+}
+
+#[test]
+fn test_4bit_div_rem() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let (q, r) = ValueWire::div_rem(&a, &b);
+    let outputs = vec![CircuitOutput::new("q", q), CircuitOutput::new("r", r)];
+
+    let separate_outputs = vec![
+        CircuitOutput::new("q", ValueWire::div(&a, &b)),
+        CircuitOutput::new("r", ValueWire::mod_(&a, &b)),
+    ];
+
+    let circuit = generate_bristol(&outputs).unwrap();
+    let separate = generate_bristol(&separate_outputs).unwrap();
+
+    for a in 0..16 {
+        for b in 0..16 {
+            let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+
+            let result = eval(&circuit, &inputs).unwrap();
+            let separate_result = eval(&separate, &inputs).unwrap();
+
+            assert_eq!(result, separate_result);
+
+            if let (Some(q), Some(r)) = (a.checked_div(b), a.checked_rem(b)) {
+                assert_eq!(result.get("q").unwrap(), &q);
+                assert_eq!(result.get("r").unwrap(), &r);
+            }
+        }
+    }
+
+    // Deduplication merges the two dividers, but div_rem's shared subtraction is still smaller
+    assert!(circuit.gates.len() < separate.gates.len());
+
+    for divisor in [3, 10] {
+        let b = ValueWire::new_const(divisor, &id_gen);
+        let (q, r) = ValueWire::div_rem(&a, &b);
+
+        let circuit =
+            generate_bristol(&[CircuitOutput::new("q", q), CircuitOutput::new("r", r)]).unwrap();
+
+        let separate = generate_bristol(&[
+            CircuitOutput::new("q", ValueWire::div(&a, &b)),
+            CircuitOutput::new("r", ValueWire::mod_(&a, &b)),
+        ])
+        .unwrap();
+
+        assert!(circuit.gates.len() < separate.gates.len());
+
+        for a in 0..16 {
+            let result = eval(&circuit, &HashMap::from([("a".to_string(), a)])).unwrap();
+
+            assert_eq!(result["q"], a / divisor);
+            assert_eq!(result["r"], a % divisor);
+        }
+    }
+
+    // Power of two divisors stay as rewiring
+    let (q, r) = ValueWire::div_rem(&a, &ValueWire::new_const(4, &id_gen));
+    assert!(Rc::ptr_eq(&q.bits[0], &a.bits[2]) && Rc::ptr_eq(&q.bits[1], &a.bits[3]));
+    assert!(Rc::ptr_eq(&r.bits[0], &a.bits[0]) && Rc::ptr_eq(&r.bits[1], &a.bits[1]));
+}
+
+#[test]
+fn test_count_ones_bool_carries() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+    let c = ValueWire::new_input("c", 4, &id_gen);
+
+    // Carry out of each 4-bit addition
+    let carries = [(&a, &b), (&b, &c), (&a, &c)]
+        .into_iter()
+        .map(|(x, y)| ValueWire::add(&x.resize(5), &y.resize(5)).at(4));
+
+    let count = ValueWire::count_ones_bool(carries, &id_gen);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("count", count)]).unwrap();
+
+    for a in 0..16 {
+        for b in 0..16 {
+            for c in 0..16 {
+                let inputs = HashMap::from([
+                    ("a".to_string(), a),
+                    ("b".to_string(), b),
+                    ("c".to_string(), c),
+                ]);
+
+                let expected = [a + b, b + c, a + c].iter().filter(|&&s| s >= 16).count();
+
+                assert_eq!(
+                    eval(&circuit, &inputs).unwrap().get("count").unwrap(),
+                    &expected
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_array_add() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = (0..4)
+        .map(|i| ValueWire::new_input(&format!("a{}", i), 4, &id_gen))
+        .collect::<Vec<_>>();
+
+    let b = (0..4)
+        .map(|i| ValueWire::new_input(&format!("b{}", i), 4, &id_gen))
+        .collect::<Vec<_>>();
+
+    let outputs = ValueWire::array_add(&a, &b)
+        .into_iter()
+        .enumerate()
+        .map(|(i, c)| CircuitOutput::new(&format!("c{}", i), c))
+        .collect::<Vec<_>>();
+
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    let mut rng = SplitMix64::new(3);
+
+    for _ in 0..200 {
+        let values = (0..8).map(|_| rng.below(16)).collect::<Vec<_>>();
+
+        let inputs = (0..4)
+            .flat_map(|i| {
+                [
+                    (format!("a{}", i), values[i]),
+                    (format!("b{}", i), values[i + 4]),
+                ]
+            })
+            .collect::<HashMap<String, usize>>();
+
+        let result = eval(&circuit, &inputs).unwrap();
+
+        for i in 0..4 {
+            assert_eq!(
+                result.get(&format!("c{}", i)).unwrap(),
+                &((values[i] + values[i + 4]) & 0xf)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_lazy_and_or() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 1, &id_gen).at(0);
+    let f = ValueWire::new_const(0, &id_gen).at(0);
+    let t = BoolWire::inv(&f);
+
+    let unreachable = || -> Rc<BoolWire> { panic!("b_thunk should not be called") };
+
+    assert!(matches!(
+        BoolWire::lazy_and(&f, unreachable).data,
+        BoolData::Const(false)
+    ));
+
+    assert!(matches!(
+        BoolWire::lazy_or(&t, unreachable).data,
+        BoolData::Const(true)
+    ));
+
+    assert!(Rc::ptr_eq(&BoolWire::lazy_and(&t, || a.clone()), &a));
+    assert!(Rc::ptr_eq(&BoolWire::lazy_or(&f, || a.clone()), &a));
+}
+
+#[test]
+fn test_4bit_eq_mask() {
+    test_4bit_binary_op(ValueWire::eq_mask, |a, b| if a == b { 0xf } else { 0 });
+}
+
+#[test]
+fn test_4bit_divmod_nr() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let (q, r) = ValueWire::divmod_nr(&a, &b);
+    let (restoring_q, restoring_r) = ValueWire::quotient_remainder(&a, &b);
+
+    let circuit =
+        generate_bristol(&[CircuitOutput::new("q", q), CircuitOutput::new("r", r)]).unwrap();
+
+    let restoring = generate_bristol(&[
+        CircuitOutput::new("q", restoring_q),
+        CircuitOutput::new("r", restoring_r),
+    ])
+    .unwrap();
+
+    for a in 0..16 {
+        for b in 1..16 {
+            let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+
+            let result = eval(&circuit, &inputs).unwrap();
+
+            assert_eq!(result.get("q").unwrap(), &(a / b));
+            assert_eq!(result.get("r").unwrap(), &(a % b));
+            assert_eq!(result, eval(&restoring, &inputs).unwrap());
+        }
+    }
+
+    // Fewer gates, but deeper since each step waits for the full carry chain of the previous one
+    assert!(circuit.gates.len() < restoring.gates.len());
+    assert!(compute_depth(&circuit).unwrap() > compute_depth(&restoring).unwrap());
+}
+
+#[test]
+fn test_4bit_signed_abs_diff() {
+    // Sign extend 4-bit two's complement to i8
+    let signed = |x: usize| ((x as i8) << 4) >> 4;
+
+    test_4bit_binary_op(ValueWire::signed_abs_diff, |a, b| {
+        signed(a).wrapping_sub(signed(b)).unsigned_abs() as usize
+    });
+}
+
+#[test]
+fn test_4bit_is_power_of_two() {
+    test_4bit_unary_op(
+        |a| BoolWire::as_value(&ValueWire::is_power_of_two(a)),
+        // Like usize::is_power_of_two, 0 is not a power of two
+        |a| a.is_power_of_two() as usize,
+    );
+}
+
+#[test]
+fn test_add_with_carry() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let no_carry = ValueWire::new_const(0, &id_gen).at(0);
+
+    assert_eq!(
+        generate_bristol(&[CircuitOutput::new(
+            "c",
+            ValueWire::add_with_carry(&a, &b, &no_carry)
+        )])
+        .unwrap()
+        .get_bristol_string()
+        .unwrap(),
+        generate_bristol(&[CircuitOutput::new("c", ValueWire::add(&a, &b))])
+            .unwrap()
+            .get_bristol_string()
+            .unwrap()
+    );
+
+    let carry = ValueWire::new_const(1, &id_gen).at(0);
+
+    test_4bit_binary_op(
+        |a, b| ValueWire::add_with_carry(a, b, &carry),
+        |a, b| (a + b + 1) % 16,
+    );
+}
+
+#[test]
+fn test_4bit_conditional_zero() {
+    test_4bit_binary_op(
+        |a, b| ValueWire::conditional_zero(a, &b.at(0)),
+        |a, b| if b & 1 == 1 { a } else { 0 },
+    );
+
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+    let a = ValueWire::new_input("a", 4, &id_gen);
+
+    // A constant false condition folds to a constant 0, so there are no gates at all
+    let zero = ValueWire::conditional_zero(&a, &ValueWire::new_const(0, &id_gen).at(0));
+    assert_eq!(zero.bits.len(), 4);
+    assert_eq!(zero.as_usize(), Some(0));
+
+    let kept = ValueWire::conditional_zero(&a, &ValueWire::new_const(1, &id_gen).at(0));
+    let circuit = generate_bristol(&[CircuitOutput::new("c", kept)]).unwrap();
+    assert!(circuit.gates.iter().all(|gate| gate.op != "AND"));
+
+    let inputs = HashMap::from([("a".to_string(), 0b1011)]);
+    assert_eq!(eval(&circuit, &inputs).unwrap().get("c").unwrap(), &0b1011);
+}
+
+#[test]
+fn test_4bit_mul_add() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+    let c = ValueWire::new_input("c", 4, &id_gen);
+
+    let circuit =
+        generate_bristol(&[CircuitOutput::new("d", ValueWire::mul_add(&a, &b, &c))]).unwrap();
+
+    for a in 0..16 {
+        for b in 0..16 {
+            for c in 0..16 {
+                let inputs = HashMap::from([
+                    ("a".to_string(), a),
+                    ("b".to_string(), b),
+                    ("c".to_string(), c),
+                ]);
+
+                assert_eq!(
+                    eval(&circuit, &inputs).unwrap().get("d").unwrap(),
+                    &((a * b + c) % 16)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_mul_add_vs_unfused() {
+    let and_count =
+        |circuit: &BristolCircuit| circuit.gates.iter().filter(|gate| gate.op == "AND").count();
+
+    for width in [4, 8, 16] {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let a = ValueWire::new_input("a", width, &id_gen);
+        let b = ValueWire::new_input("b", width, &id_gen);
+        let c = ValueWire::new_input("c", width, &id_gen);
+
+        let fused =
+            generate_bristol(&[CircuitOutput::new("d", ValueWire::mul_add(&a, &b, &c))]).unwrap();
+
+        let unfused = generate_bristol(&[CircuitOutput::new(
+            "d",
+            ValueWire::add(&ValueWire::mul(&a, &b), &c),
+        )])
+        .unwrap();
+
+        // The fused full adders need one AND each where the ripple adds need two, and there's
+        // never more gates in total
+        assert!(and_count(&fused) < and_count(&unfused));
+        assert!(fused.gates.len() <= unfused.gates.len());
+    }
+}
+
+#[test]
+fn test_4bit_sub_with_overflow() {
+    test_4bit_binary_op(
+        |a, b| ValueWire::sub_with_overflow(a, b).0,
+        |a, b| a.wrapping_sub(b) % 16,
+    );
+
+    test_4bit_binary_op(
+        |a, b| BoolWire::as_value(&ValueWire::sub_with_overflow(a, b).1),
+        |a, b| (a < b) as usize,
+    );
+
+    // Saturating subtraction zeroes the difference when it underflows
+    test_4bit_binary_op(
+        |a, b| {
+            let (diff, borrow) = ValueWire::sub_with_overflow(a, b);
+            ValueWire::conditional_zero(&diff, &BoolWire::inv(&borrow))
+        },
+        |a, b| a.saturating_sub(b),
+    );
+}
+
+#[test]
+fn test_4bit_add_carry() {
+    test_4bit_binary_op(|a, b| ValueWire::add_carry(a, b).0, |a, b| (a + b) % 16);
+
+    test_4bit_binary_op(
+        |a, b| BoolWire::as_value(&ValueWire::add_carry(a, b).1),
+        |a, b| (a + b) >> 4,
+    );
+}
+
+#[test]
+fn test_4bit_sub_borrow() {
+    test_4bit_binary_op(
+        |a, b| ValueWire::sub_borrow(a, b).0,
+        |a, b| a.wrapping_sub(b) % 16,
+    );
+
+    test_4bit_binary_op(
+        |a, b| BoolWire::as_value(&ValueWire::sub_borrow(a, b).1),
+        |a, b| (a < b) as usize,
+    );
+}
+
+#[test]
+fn test_8bit_add_carry_out() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_input("b", 8, &id_gen);
+
+    let (sum, carry) = ValueWire::add_carry(&a, &b);
+    assert_eq!(sum.bits.len(), 8);
+
+    let circuit = generate_bristol(&[
+        CircuitOutput::new("sum", sum),
+        CircuitOutput::new("carry", BoolWire::as_value(&carry)),
+    ])
+    .unwrap();
+
+    let carry_info = circuit.io_lookup().get_output_info("carry").unwrap();
+    assert!(circuit
+        .gates
+        .iter()
+        .any(|gate| gate.outputs[0] == carry_info.address));
+
+    let inputs = HashMap::from([("a".to_string(), 0xff), ("b".to_string(), 0x01)]);
+    let result = eval(&circuit, &inputs).unwrap();
+
+    assert_eq!(result.get("sum").unwrap(), &0x00);
+    assert_eq!(result.get("carry").unwrap(), &1);
+
+    // Chaining two 8 bit limbs gives a 16 bit add
+    let (a_hi, b_hi) = (
+        ValueWire::new_input("a_hi", 8, &id_gen),
+        ValueWire::new_input("b_hi", 8, &id_gen),
+    );
+
+    let (low, carry) = ValueWire::add_carry(&a, &b);
+    let high = ValueWire::add_with_carry(&a_hi, &b_hi, &carry);
+
+    let circuit =
+        generate_bristol(&[CircuitOutput::new("c", ValueWire::concat(&low, &high))]).unwrap();
+
+    let mut rng = SplitMix64::new(1013);
+
+    for _ in 0..20 {
+        let (a, b) = (rng.below(1 << 16), rng.below(1 << 16));
+
+        let inputs = HashMap::from([
+            ("a".to_string(), a & 0xff),
+            ("b".to_string(), b & 0xff),
+            ("a_hi".to_string(), a >> 8),
+            ("b_hi".to_string(), b >> 8),
+        ]);
+
+        assert_eq!(
+            eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+            &((a + b) % (1 << 16))
+        );
+    }
+}
+
+#[test]
+fn test_4bit_saturating_add_sub() {
+    test_4bit_binary_op(ValueWire::saturating_add, |a, b| std::cmp::min(a + b, 15));
+    test_4bit_binary_op(ValueWire::saturating_sub, |a, b| a.saturating_sub(b));
+}
+
+#[test]
+fn test_8bit_saturating_add_sub() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_input("b", 8, &id_gen);
+
+    let circuit = generate_bristol(&[
+        CircuitOutput::new("add", ValueWire::saturating_add(&a, &b)),
+        CircuitOutput::new("sub", ValueWire::saturating_sub(&a, &b)),
+    ])
+    .unwrap();
+
+    for (a, b) in [(250, 10), (3, 10), (100, 27), (255, 255), (0, 0)] {
+        let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+        let result = eval(&circuit, &inputs).unwrap();
+
+        assert_eq!(
+            result.get("add").unwrap(),
+            &(a as u8).saturating_add(b as u8).into()
+        );
+        assert_eq!(
+            result.get("sub").unwrap(),
+            &(a as u8).saturating_sub(b as u8).into()
+        );
+    }
+
+    let and_count = |value: ValueWire| {
+        generate_bristol(&[CircuitOutput::new("c", value)])
+            .unwrap()
+            .gates
+            .iter()
+            .filter(|gate| gate.op == "AND")
+            .count()
+    };
+
+    // Two ANDs for the carry out that add drops, then one per bit to apply the saturation
+    let add = and_count(ValueWire::add(&a, &b));
+    assert_eq!(and_count(ValueWire::saturating_add(&a, &b)), add + 8 + 2);
+    assert_eq!(and_count(ValueWire::saturating_sub(&a, &b)), add + 8 + 2);
+}
+
+#[test]
+fn test_4bit_overflow_add() {
+    let to_signed = |x: usize| (x as i64) << 60 >> 60;
+
+    test_4bit_binary_op(
+        |a, b| BoolWire::as_value(&ValueWire::overflow_add(a, b)),
+        |a, b| {
+            let sum = to_signed(a) + to_signed(b);
+            !(-8..8).contains(&sum) as usize
+        },
+    );
+
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+    let overflows = |a, b| {
+        ValueWire::overflow_add(
+            &ValueWire::new_const(a, &id_gen).resize(4),
+            &ValueWire::new_const(b, &id_gen).resize(4),
+        )
+    };
+
+    // 7 + 1 wraps to -8, 3 + 2 fits
+    assert!(matches!(
+        overflows(0b0111, 0b0001).data,
+        BoolData::Const(true)
+    ));
+    assert!(matches!(
+        overflows(0b0011, 0b0010).data,
+        BoolData::Const(false)
+    ));
+}
+
+#[test]
+fn test_mul_const_optimized() {
+    let and_count =
+        |circuit: &BristolCircuit| circuit.gates.iter().filter(|gate| gate.op == "AND").count();
+
+    for k in [0, 1, 2, 4, 5, 7, 10, 15, 255, 0x5a5a] {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let a = ValueWire::new_input("a", 8, &id_gen);
+        let product = ValueWire::mul_const_optimized(&a, k);
+        assert_eq!(product.bits.len(), 8);
+
+        if product.as_usize() == Some(0) {
+            assert_eq!(k % 256, 0);
+            continue;
+        }
+
+        let circuit = generate_bristol(&[CircuitOutput::new("c", product)]).unwrap();
+
+        let mul_circuit = generate_bristol(&[CircuitOutput::new(
+            "c",
+            ValueWire::mul(&a, &ValueWire::new_const(k, &id_gen)).truncate_silent(8),
+        )])
+        .unwrap();
+
+        for a in 0..256 {
+            let inputs = HashMap::from([("a".to_string(), a)]);
+
+            assert_eq!(
+                eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+                &(a.wrapping_mul(k) % 256)
+            );
+        }
+
+        assert!(and_count(&circuit) <= and_count(&mul_circuit));
+
+        if k.is_power_of_two() {
+            assert_eq!(and_count(&circuit), 0);
+        }
+    }
+
+    // A run of set bits is an add and a subtract rather than one add per bit
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+    let a = ValueWire::new_input("a", 8, &id_gen);
+
+    let optimized = generate_bristol(&[CircuitOutput::new(
+        "c",
+        ValueWire::mul_const_optimized(&a, 0x7f),
+    )])
+    .unwrap();
+
+    let mul = generate_bristol(&[CircuitOutput::new(
+        "c",
+        ValueWire::mul(&a, &ValueWire::new_const(0x7f, &id_gen)),
+    )])
+    .unwrap();
+
+    assert!(and_count(&optimized) < and_count(&mul));
+}
+
+#[test]
+fn test_4bit_mul_const_by_4() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let circuit = generate_bristol(&[CircuitOutput::new(
+        "c",
+        ValueWire::mul_const_optimized(&a, 4),
+    )])
+    .unwrap();
+
+    assert!(circuit.gates.iter().all(|gate| gate.op != "AND"));
+
+    for a in 0..16 {
+        let inputs = HashMap::from([("a".to_string(), a)]);
+        assert_eq!(
+            eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+            &((a * 4) % 16)
+        );
+    }
+}
+
+#[test]
+fn test_karatsuba_mul() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let product = ValueWire::karatsuba_mul(&a, &b);
+    assert_eq!(product.bits.len(), 8);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", product)]).unwrap();
+
+    for a in 0..16 {
+        for b in 0..16 {
+            let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+            assert_eq!(eval(&circuit, &inputs).unwrap().get("c").unwrap(), &(a * b));
+        }
+    }
+
+    let mut rng = SplitMix64::new(1017);
+
+    // Odd widths split unevenly, and a and b don't need the same width
+    for (a_width, b_width) in [(16, 16), (13, 13), (32, 32), (20, 9)] {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let a = ValueWire::new_input("a", a_width, &id_gen);
+        let b = ValueWire::new_input("b", b_width, &id_gen);
+
+        let circuit =
+            generate_bristol(&[CircuitOutput::new("c", ValueWire::karatsuba_mul(&a, &b))]).unwrap();
+
+        for _ in 0..20 {
+            let a = rng.below(1 << a_width);
+            let b = rng.below(1 << b_width);
+
+            let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+            assert_eq!(eval(&circuit, &inputs).unwrap().get("c").unwrap(), &(a * b));
+        }
+    }
+}
+
+#[test]
+fn test_karatsuba_mul_and_gates() {
+    let and_count =
+        |circuit: &BristolCircuit| circuit.gates.iter().filter(|gate| gate.op == "AND").count();
+
+    for width in [16, 32] {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let a = ValueWire::new_input("a", width, &id_gen);
+        let b = ValueWire::new_input("b", width, &id_gen);
+
+        let karatsuba =
+            generate_bristol(&[CircuitOutput::new("c", ValueWire::karatsuba_mul(&a, &b))]).unwrap();
+
+        let schoolbook = generate_bristol(&[CircuitOutput::new(
+            "c",
+            ValueWire::mul(&a.resize(2 * width), &b.resize(2 * width)),
+        )])
+        .unwrap();
+
+        assert!(and_count(&karatsuba) < and_count(&schoolbook));
+    }
+}
+
+#[test]
+fn test_mul_wallace() {
+    test_4bit_binary_op(ValueWire::mul_wallace, |a, b| (a * b) % 16);
+
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_input("b", 8, &id_gen);
+
+    let wallace =
+        generate_bristol(&[CircuitOutput::new("c", ValueWire::mul_wallace(&a, &b))]).unwrap();
+
+    let schoolbook = generate_bristol(&[CircuitOutput::new("c", ValueWire::mul(&a, &b))]).unwrap();
+
+    let mut rng = SplitMix64::new(1019);
+
+    for _ in 0..50 {
+        let inputs = HashMap::from([
+            ("a".to_string(), rng.below(256)),
+            ("b".to_string(), rng.below(256)),
+        ]);
+
+        assert_eq!(
+            eval(&wallace, &inputs).unwrap(),
+            eval(&schoolbook, &inputs).unwrap()
+        );
+    }
+
+    assert!(compute_depth(&wallace).unwrap() < compute_depth(&schoolbook).unwrap());
+}
+
+#[test]
+fn test_4bit_conditional_set_and_clear_bit() {
+    for index in 0..4 {
+        // The condition is the low bit of b
+        test_4bit_binary_op(
+            |a, b| ValueWire::conditional_set_bit(a, index, &b.at(0)),
+            |a, b| (a & !(1 << index)) | ((b & 1) << index),
+        );
+
+        test_4bit_binary_op(
+            |a, b| ValueWire::conditional_clear_bit(a, index, &b.at(0)),
+            |a, b| if b & 1 == 1 { a & !(1 << index) } else { a },
+        );
+    }
+}
+
+#[test]
+fn test_8bit_sub_const() {
+    let build = |constant: usize, special: bool| {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+        let a = ValueWire::new_input("a", 8, &id_gen);
+
+        let c = match special {
+            true => ValueWire::sub_const(&a, constant),
+            false => ValueWire::sub(&a, &ValueWire::new_const(constant, &id_gen)),
+        };
+
+        generate_bristol(&[CircuitOutput::new("c", c)]).unwrap()
+    };
+
+    let and_count =
+        |circuit: &BristolCircuit| circuit.gates.iter().filter(|gate| gate.op == "AND").count();
+
+    for constant in [1, 2, 3, 64, 100, 192, 255] {
+        let circuit = build(constant, true);
+        let reference = build(constant, false);
+
+        for a in 0..256 {
+            let inputs = HashMap::from([("a".to_string(), a)]);
+
+            assert_eq!(
+                eval(&circuit, &inputs).unwrap(),
+                eval(&reference, &inputs).unwrap()
+            );
+            assert_eq!(
+                eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+                &(a.wrapping_sub(constant) % 256)
+            );
+        }
+
+        assert!(and_count(&circuit) <= and_count(&reference));
+        assert!(circuit.gates.len() <= reference.gates.len());
+    }
+
+    // Powers of two skip the full subtractor. The borrow chain still needs one AND per bit, so the
+    // saving is in XOR/INV gates.
+    for constant in [1, 2, 4] {
+        assert!(build(constant, true).gates.len() < build(constant, false).gates.len());
+    }
+}
+
+#[test]
+fn test_to_bool_1bit() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+    let a = ValueWire::new_input("a", 1, &id_gen);
+
+    let next_id = id_gen.borrow().peek();
+
+    for bit in [a.to_bool(), ValueWire::to_bool_tree(&a)] {
+        // The bit is returned directly, without creating any new wires
+        assert!(Rc::ptr_eq(&bit, &a.bits[0]));
+        assert_eq!(id_gen.borrow().peek(), next_id);
+    }
+
+    test_4bit_unary_op(
+        |a| BoolWire::as_value(&ValueWire::to_bool_tree(a)),
+        |a| (a != 0) as usize,
+    );
+}
+
+#[test]
+fn test_4bit_bool_and() {
+    test_4bit_binary_op(
+        |a, b| BoolWire::as_value(&ValueWire::bool_and(a, b)),
+        |a, b| if a != 0 && b != 0 { 1 } else { 0 },
+    );
+}
+
+#[test]
+fn test_4bit_5mul() {
+    test_4bit_unary_op(
+        |a| ValueWire::mul(&ValueWire::new_const(5, &a.id_gen), a),
+        |a| (5 * a) & 0xf,
+    );
+}
+
+#[test]
+fn test_4bit_1add() {
+    test_4bit_unary_op(
+        |a| ValueWire::add(&ValueWire::new_const(1, &a.id_gen), a),
+        |a| (1 + a) & 0xf,
+    );
+}
+
+#[test]
+fn test_4bit_negate() {
+    test_4bit_unary_op(ValueWire::negate, |a| (16 - a) & 0xf);
+}
+
+#[test]
+fn test_4bit_which_is_larger() {
+    test_4bit_binary_op(
+        |a, b| {
+            // Based on summon generation for:
+            // export default (a, b) => a === b ? 0 : a > b ? 1 : 2;
+            // (equivalent to mpc-hello circuit)
+
+            // 2 1 0 1 2 AEq
+            let w2 = ValueWire::equal(a, b);
+
+            // 2 1 0 1 3 AGt
+            let w3 = ValueWire::greater_than(a, b);
+
+            // 1 1 2 4 ANot
+            let w4 = BoolWire::inv(&w2);
+
+            // 1 1 3 5 ANot
+            let w5 = BoolWire::inv(&w3);
+
+            // 2 1 4 5 6 ABoolAnd
+            let w6 = BoolWire::and(&w4, &w5);
+
+            // 2 1 6 7 8 AMul
+            let w8 = ValueWire::mul(
+                &BoolWire::as_value(&w6).resize(4),
+                &ValueWire::new_const(2, &a.id_gen), // w7 is 2
+            );
 
             // 2 1 2 6 9 ABoolOr
             let w9 = BoolWire::or(&w2, &w6);
@@ -364,30 +2943,603 @@ fn test_4bit_which_is_larger() {
     );
 }
 
+#[test]
+fn test_4bit_bit_interleave() {
+    test_4bit_binary_op(ValueWire::bit_interleave, |a, b| {
+        (0..4)
+            .map(|i| (((a >> i) & 1) << (2 * i)) | (((b >> i) & 1) << (2 * i + 1)))
+            .sum()
+    });
+}
+
+#[test]
+fn test_4bit_zip() {
+    // Every output bit: a's bits at the even positions and b's at the odd ones
+    test_4bit_binary_op(ValueWire::zip, |a, b| {
+        (0..4)
+            .map(|i| (((a >> i) & 1) << (2 * i)) | (((b >> i) & 1) << (2 * i + 1)))
+            .sum()
+    });
+}
+
+#[test]
+fn test_4bit_zip_with() {
+    test_4bit_binary_op(
+        |a, b| ValueWire::zip_with(a, b, |x, y| BoolWire::inv(&BoolWire::and(x, y))),
+        |a, b| !(a & b) & 0xf,
+    );
+}
+
+#[test]
+fn test_4bit_unzip() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let (e, o) = ValueWire::unzip(&ValueWire::bit_interleave(&a, &b));
+
+    let outputs = vec![CircuitOutput::new("e", e), CircuitOutput::new("o", o)];
+
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    for a in 0..16 {
+        for b in 0..16 {
+            let inputs = vec![("a", a), ("b", b)]
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect::<HashMap<String, usize>>();
+
+            let result = eval(&circuit, &inputs).unwrap();
+
+            assert_eq!(result.get("e").unwrap(), &a);
+            assert_eq!(result.get("o").unwrap(), &b);
+        }
+    }
+}
+
+#[test]
+fn test_4bit_bit_or_disjoint() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let c = ValueWire::bit_or_disjoint(&a, &b);
+
+    let outputs = vec![CircuitOutput::new("c", c)];
+
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    assert!(circuit.gates.iter().all(|gate| gate.op == "XOR"));
+
+    for a in 0..16 {
+        for b in 0..16 {
+            if a & b != 0 {
+                continue;
+            }
+
+            let inputs = vec![("a", a), ("b", b)]
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect::<HashMap<String, usize>>();
+
+            let result = eval(&circuit, &inputs).unwrap();
+
+            assert_eq!(result.get("c").unwrap(), &(a | b));
+        }
+    }
+
+    let c = ValueWire::bit_or_disjoint(
+        &ValueWire::new_const(0b1010, &id_gen),
+        &ValueWire::new_const(0b0101, &id_gen),
+    );
+
+    assert_eq!(c.as_usize(), Some(0b1111));
+}
+
+#[test]
+fn test_4bit_bit_or_disjoint_masked() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+    let m = ValueWire::new_input("m", 4, &id_gen);
+
+    // Disjoint for every input since each bit comes from either a or b depending on m
+    let lhs = ValueWire::bit_and(&a, &m);
+    let rhs = ValueWire::bit_and(&b, &ValueWire::bit_not(&m));
+
+    let outputs = vec![
+        CircuitOutput::new("overlap", ValueWire::bit_and(&lhs, &rhs)),
+        CircuitOutput::new("c", ValueWire::bit_or_disjoint(&lhs, &rhs)),
+        CircuitOutput::new("expected", ValueWire::bit_or(&lhs, &rhs)),
+    ];
+
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    for a in 0..16 {
+        for b in 0..16 {
+            for m in 0..16 {
+                let inputs = vec![("a", a), ("b", b), ("m", m)]
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value))
+                    .collect::<HashMap<String, usize>>();
+
+                let result = eval(&circuit, &inputs).unwrap();
+
+                assert_eq!(result.get("overlap"), Some(&0));
+                assert_eq!(result.get("c"), result.get("expected"));
+            }
+        }
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "bit_or_disjoint operands overlap")]
+fn test_bit_or_disjoint_const_overlap() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    ValueWire::bit_or_disjoint(
+        &ValueWire::new_const(0b0110, &id_gen),
+        &ValueWire::new_const(0b0101, &id_gen),
+    );
+}
+
+#[test]
+fn test_reserved_wires_no_constants() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let outputs = vec![CircuitOutput::new("c", ValueWire::add(&a, &b))];
+
+    let default_circuit = generate_bristol(&outputs).unwrap();
+
+    let circuit = generate_bristol_with_options(
+        &outputs,
+        &GenerateBristolOptions {
+            constant_strategy: ConstantStrategy::ReservedWires,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(circuit.gates.len(), default_circuit.gates.len());
+    assert_eq!(circuit.wire_count, default_circuit.wire_count + 2);
+    assert_eq!(circuit.info.constants.len(), 2);
+    assert_eq!(circuit.info.inputs[0].address, 2);
+
+    for a in 0..16 {
+        for b in 0..16 {
+            let inputs = vec![("a", a), ("b", b)]
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect::<HashMap<String, usize>>();
+
+            let result = eval(&circuit, &inputs).unwrap();
+
+            assert_eq!(result.get("c").unwrap(), &((a + b) & 0xf));
+        }
+    }
+}
+
+#[test]
+fn test_constant_strategies_many_constants() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+
+    // Upper bits are all constant false, plus a constant true output
+    let c = ValueWire::bit_and(&a, &ValueWire::new_const(1, &id_gen)).resize(8);
+    let d = ValueWire::new_const(0b101, &id_gen);
+
+    let outputs = vec![CircuitOutput::new("c", c), CircuitOutput::new("d", d)];
+
+    let reserved = generate_bristol_with_options(
+        &outputs,
+        &GenerateBristolOptions {
+            constant_strategy: ConstantStrategy::ReservedWires,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // One copy per output bit, and no XOR/INV to build the constants
+    assert_eq!(reserved.gates.len(), 11);
+    assert!(reserved.gates.iter().all(|gate| gate.op == "COPY"));
+    assert!(reserved
+        .gates
+        .iter()
+        .all(|gate| gate.inputs[0] == 0 || gate.inputs[0] == 1 || gate.inputs[0] == 2));
+
+    let inline = generate_bristol_with_options(
+        &outputs,
+        &GenerateBristolOptions {
+            constant_strategy: ConstantStrategy::Inline,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // Only the copy of a's lowest bit is needed
+    assert_eq!(inline.gates.len(), 1);
+    assert_eq!(inline.info.constants.len(), 10);
+
+    for circuit in [&reserved, &inline] {
+        for a in 0..256 {
+            let inputs = vec![("a".to_string(), a)]
+                .into_iter()
+                .collect::<HashMap<String, usize>>();
+
+            let result = eval(circuit, &inputs).unwrap();
+
+            assert_eq!(result.get("c").unwrap(), &(a & 1));
+            assert_eq!(result.get("d").unwrap(), &0b101);
+        }
+    }
+}
+
+#[test]
+fn test_input_bit_order() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input_with_order("a", 4, BitOrder::LittleEndian, &id_gen);
+    let b = ValueWire::new_input_with_order("b", 4, BitOrder::BigEndian, &id_gen);
+
+    let a_ids = a
+        .bits
+        .iter()
+        .map(|bit| bit.id().unwrap())
+        .collect::<Vec<_>>();
+    let b_ids = b
+        .bits
+        .iter()
+        .map(|bit| bit.id().unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(a_ids, vec![0, 1, 2, 3]);
+    assert_eq!(b_ids, vec![7, 6, 5, 4]);
+
+    let outputs = vec![
+        CircuitOutput::new("c", ValueWire::sub(&a, &b)),
+        CircuitOutput::new("a_msb", BoolWire::as_value(&a.at(3))),
+        CircuitOutput::new("b_msb", BoolWire::as_value(&b.at(3))),
+    ];
+
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    // The outputs that are input bits are copies, whose input is the wire position of that bit
+    let copied_wire = |name: &str| {
+        let address = circuit
+            .info
+            .outputs
+            .iter()
+            .find(|o| o.name == name)
+            .unwrap()
+            .address;
+
+        let gate = circuit
+            .gates
+            .iter()
+            .find(|gate| gate.outputs[0] == address)
+            .unwrap();
+
+        assert_eq!(gate.op, "COPY");
+        gate.inputs[0]
+    };
+
+    let a_address = circuit.info.inputs[0].address;
+    let b_address = circuit.info.inputs[1].address;
+
+    // a is LSB-first, b is MSB-first
+    assert_eq!(copied_wire("a_msb"), a_address + 3);
+    assert_eq!(copied_wire("b_msb"), b_address);
+
+    let reverse_4bit = |value: usize| (0..4).map(|i| ((value >> i) & 1) << (3 - i)).sum::<usize>();
+
+    for a in 0..16 {
+        for b in 0..16 {
+            // eval reads each value LSB-first from its address, so b's wires take its bits reversed
+            let inputs = vec![("a", a), ("b", reverse_4bit(b))]
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect::<HashMap<String, usize>>();
+
+            let result = eval(&circuit, &inputs).unwrap();
+
+            assert_eq!(result.get("c").unwrap(), &(a.wrapping_sub(b) & 0xf));
+            assert_eq!(result.get("a_msb").unwrap(), &(a >> 3));
+            assert_eq!(result.get("b_msb").unwrap(), &(b >> 3));
+        }
+    }
+}
+
+#[test]
+fn test_select_bits_const_mask() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let c = ValueWire::select_bits(
+        &ValueWire::new_const(0b10110101, &id_gen),
+        &ValueWire::new_const(0b11110000, &id_gen),
+    );
+
+    assert_eq!(c.bits.len(), 4);
+    assert_eq!(c.as_usize(), Some(0b1011));
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let c = ValueWire::select_bits(&a, &ValueWire::new_const(0b10100110, &id_gen));
+
+    assert_eq!(c.bits.len(), 4);
+
+    let outputs = vec![CircuitOutput::new("c", c)];
+
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    // Only copies of the input bits
+    assert!(circuit.gates.iter().all(|gate| gate.op == "COPY"));
+
+    for a in 0..256 {
+        let inputs = HashMap::from([("a".to_string(), a)]);
+        let result = eval(&circuit, &inputs).unwrap();
+
+        assert_eq!(result.get("c").unwrap(), &pext(a, 0b10100110));
+    }
+}
+
+#[test]
+fn test_4bit_select_bits() {
+    test_4bit_binary_op(ValueWire::select_bits, pext);
+}
+
+#[test]
+fn test_deposit_bits() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let c = ValueWire::deposit_bits(&ValueWire::new_const(0b1011, &id_gen), 0b11110000);
+
+    assert_eq!(c.bits.len(), 8);
+    assert_eq!(c.as_usize(), Some(0b10110000));
+
+    test_4bit_unary_op(
+        |a| ValueWire::deposit_bits(a, 0b1001010),
+        |a| (a & 1) << 1 | ((a >> 1) & 1) << 3 | ((a >> 2) & 1) << 6,
+    );
+
+    // Inverse of select_bits with the same mask
+    test_4bit_unary_op(
+        |a| {
+            ValueWire::select_bits(
+                &ValueWire::deposit_bits(a, 0b110011),
+                &ValueWire::new_const(0b110011, &a.id_gen),
+            )
+        },
+        |a| a,
+    );
+}
+
+fn pext(a: usize, mask: usize) -> usize {
+    let mut result = 0;
+    let mut j = 0;
+
+    for i in 0..usize::BITS {
+        if (mask >> i) & 1 == 1 {
+            result |= ((a >> i) & 1) << j;
+            j += 1;
+        }
+    }
+
+    result
+}
+
+const ADDER_STRATEGIES: [AdderStrategy; 5] = [
+    AdderStrategy::RippleCarry,
+    AdderStrategy::CarryLookahead,
+    AdderStrategy::KoggeStone,
+    AdderStrategy::Sklansky,
+    AdderStrategy::BrentKung,
+];
+
+#[test]
+fn test_8bit_add_with() {
+    for strategy in ADDER_STRATEGIES {
+        test_nbit_binary_op(
+            8,
+            |a, b| ValueWire::add_with(a, b, strategy),
+            |a, b| (a + b) & 0xff,
+        );
+    }
+}
+
+#[test]
+fn test_add_with_mixed_widths() {
+    for strategy in ADDER_STRATEGIES {
+        test_4bit_binary_op(
+            |a, b| ValueWire::add_with(a, &b.truncate_silent(2), strategy),
+            |a, b| (a + (b & 0b11)) & 0xf,
+        );
+    }
+}
+
+#[test]
+fn test_32bit_add_with_depth() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 32, &id_gen);
+    let b = ValueWire::new_input("b", 32, &id_gen);
+
+    let depths = ADDER_STRATEGIES.map(|strategy| {
+        let c = ValueWire::add_with(&a, &b, strategy);
+        compute_depth(&generate_bristol(&[CircuitOutput::new("c", c)]).unwrap()).unwrap()
+    });
+
+    let ripple_depth = depths[0];
+
+    for depth in &depths[1..] {
+        assert!(*depth < ripple_depth);
+    }
+}
+
+#[test]
+fn test_add_cla() {
+    test_4bit_binary_op(ValueWire::add_cla, |a, b| (a + b) % 16);
+
+    // Depth grows by a constant for each doubling of the width, while ripple carry doubles
+    let depth = |width: usize, add: fn(&ValueWire, &ValueWire) -> ValueWire| {
+        let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+        let a = ValueWire::new_input("a", width, &id_gen);
+        let b = ValueWire::new_input("b", width, &id_gen);
+
+        compute_depth(&generate_bristol(&[CircuitOutput::new("c", add(&a, &b))]).unwrap()).unwrap()
+    };
+
+    let cla_depths = [8, 16, 32, 64].map(|width| depth(width, ValueWire::add_cla));
+    let ripple_depths = [8, 16, 32, 64].map(|width| depth(width, ValueWire::add));
+
+    for i in 1..4 {
+        assert!(cla_depths[i] - cla_depths[i - 1] <= 2);
+        assert!(ripple_depths[i] >= 2 * ripple_depths[i - 1] - 2);
+    }
+}
+
+#[test]
+fn test_8bit_carry_skip_adder() {
+    test_nbit_binary_op(
+        8,
+        |a, b| ValueWire::carry_skip_adder(a, b, 2),
+        |a, b| (a + b) & 0xff,
+    );
+
+    test_nbit_binary_op(
+        8,
+        |a, b| ValueWire::carry_skip_adder(a, b, 3),
+        |a, b| (a + b) & 0xff,
+    );
+}
+
+#[test]
+fn test_8bit_adder_variants() {
+    for (_, adder) in adder_variants::VARIANTS {
+        test_nbit_binary_op(8, adder, |a, b| (a + b) % 256);
+    }
+}
+
+#[test]
+fn test_compare_adders() {
+    let comparison = adder_variants::compare_adders(32);
+
+    let stats = |name: &str| {
+        let stats = comparison
+            .adders
+            .iter()
+            .find(|stats| stats.name == name)
+            .unwrap();
+
+        (stats.gates, stats.and_gates, stats.depth)
+    };
+
+    // Same as the AdderStrategy table in the README
+    assert_eq!(stats("ripple_carry"), (154, 61, 62));
+    assert_eq!(stats("carry_lookahead"), (214, 101, 22));
+    assert_eq!(stats("prefix"), (289, 151, 12));
+
+    // Ripple carry has the fewest gates and the prefix adder has the lowest depth
+    for stats in &comparison.adders {
+        assert!(stats.gates >= 154);
+        assert!(stats.depth >= 12);
+    }
+}
+
+#[test]
+fn test_16bit_carry_skip_adder_depth() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 16, &id_gen);
+    let b = ValueWire::new_input("b", 16, &id_gen);
+
+    let ripple = generate_bristol(&[CircuitOutput::new("c", ValueWire::add(&a, &b))]).unwrap();
+
+    let skip = generate_bristol(&[CircuitOutput::new(
+        "c",
+        ValueWire::carry_skip_adder(&a, &b, 4),
+    )])
+    .unwrap();
+
+    assert!(compute_depth(&skip).unwrap() < compute_depth(&ripple).unwrap());
+}
+
+#[test]
+fn test_8bit_add_carry_select() {
+    test_nbit_binary_op(
+        8,
+        |a, b| ValueWire::add_carry_select(a, b, 2),
+        |a, b| (a + b) & 0xff,
+    );
+
+    test_nbit_binary_op(
+        8,
+        |a, b| ValueWire::add_carry_select(a, b, 3),
+        |a, b| (a + b) & 0xff,
+    );
+}
+
+#[test]
+fn test_32bit_add_carry_select_depth() {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+
+    let a = ValueWire::new_input("a", 32, &id_gen);
+    let b = ValueWire::new_input("b", 32, &id_gen);
+
+    let lookahead = generate_bristol(&[CircuitOutput::new(
+        "c",
+        ValueWire::add_with(&a, &b, AdderStrategy::CarryLookahead),
+    )])
+    .unwrap();
+
+    let select = generate_bristol(&[CircuitOutput::new(
+        "c",
+        ValueWire::add_carry_select(&a, &b, 4),
+    )])
+    .unwrap();
+
+    assert!(compute_depth(&select).unwrap() < compute_depth(&lookahead).unwrap());
+}
+
 fn test_4bit_binary_op<F, G>(wire_op: F, op: G)
+where
+    F: Fn(&ValueWire, &ValueWire) -> ValueWire,
+    G: Fn(usize, usize) -> usize,
+{
+    test_nbit_binary_op(4, wire_op, op);
+}
+
+fn test_nbit_binary_op<F, G>(bits: usize, wire_op: F, op: G)
 where
     F: Fn(&ValueWire, &ValueWire) -> ValueWire,
     G: Fn(usize, usize) -> usize,
 {
     let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
 
-    let a = ValueWire::new_input("a", 4, &id_gen);
-    let b = ValueWire::new_input("b", 4, &id_gen);
+    let a = ValueWire::new_input("a", bits, &id_gen);
+    let b = ValueWire::new_input("b", bits, &id_gen);
 
     let c = wire_op(&a, &b);
 
     let outputs = vec![CircuitOutput::new("c", c)];
 
-    let circuit = generate_bristol(&outputs);
+    let circuit = generate_bristol(&outputs).unwrap();
 
-    for a in 0..16 {
-        for b in 0..16 {
+    for a in 0..(1 << bits) {
+        for b in 0..(1 << bits) {
             let inputs = vec![("a", a), ("b", b)]
                 .into_iter()
                 .map(|(name, value)| (name.to_string(), value))
                 .collect::<HashMap<String, usize>>();
 
-            let result = eval(&circuit, &inputs);
+            let result = eval(&circuit, &inputs).unwrap();
 
             let expected = op(a, b);
 
@@ -410,7 +3562,7 @@ where
 
     let outputs = vec![CircuitOutput::new("c", c)];
 
-    let circuit = generate_bristol(&outputs);
+    let circuit = generate_bristol(&outputs).unwrap();
 
     for a in 0..16 {
         let inputs = vec![("a", a), ("b", b.as_usize().unwrap())]
@@ -418,7 +3570,7 @@ where
             .map(|(name, value)| (name.to_string(), value))
             .collect::<HashMap<String, usize>>();
 
-        let result = eval(&circuit, &inputs);
+        let result = eval(&circuit, &inputs).unwrap();
 
         let expected = op(a, b.as_usize().unwrap());
 
@@ -438,7 +3590,7 @@ where
 
     let outputs = vec![CircuitOutput::new("out", out)];
 
-    let circuit = generate_bristol(&outputs);
+    let circuit = generate_bristol(&outputs).unwrap();
 
     for in_ in 0..16 {
         let inputs = vec![("in", in_)]
@@ -446,7 +3598,7 @@ where
             .map(|(name, value)| (name.to_string(), value))
             .collect::<HashMap<String, usize>>();
 
-        let result = eval(&circuit, &inputs);
+        let result = eval(&circuit, &inputs).unwrap();
         let expected = op(in_);
 
         assert_eq!(result.get("out").unwrap(), &expected);
@@ -0,0 +1,74 @@
+use boolify::{
+    circuit_analysis::CircuitAnalysisError, circuit_cost, generate_bristol, CircuitOutput,
+    CostModel, IdGenerator, ValueWire,
+};
+use bristol_circuit::{BristolCircuit, CircuitInfo, Gate};
+
+fn gate(inputs: Vec<usize>, output: usize, op: &str) -> Gate {
+    Gate {
+        inputs,
+        outputs: vec![output],
+        op: op.to_string(),
+    }
+}
+
+#[test]
+fn test_garbled_circuit_cost() {
+    let circuit = BristolCircuit {
+        wire_count: 7,
+        info: CircuitInfo::default(),
+        gates: vec![
+            gate(vec![0, 1], 2, "AND"),
+            gate(vec![0, 1], 3, "XOR"),
+            gate(vec![3], 4, "INV"),
+            gate(vec![2, 4], 5, "AND"),
+            gate(vec![5], 6, "COPY"),
+        ],
+    };
+
+    assert_eq!(
+        circuit_cost(&circuit, &CostModel::garbled_circuit()),
+        Ok(2.0)
+    );
+    assert_eq!(circuit_cost(&circuit, &CostModel::spdz()), Ok(4.0));
+
+    let model = CostModel {
+        and_cost: 10.0,
+        xor_cost: 1.0,
+        inv_cost: 0.5,
+    };
+
+    assert_eq!(circuit_cost(&circuit, &model), Ok(21.5));
+}
+
+#[test]
+fn test_garbled_circuit_cost_is_multiplicative_complexity() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 16, &id_gen);
+    let b = ValueWire::new_input("b", 16, &id_gen);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", ValueWire::mul(&a, &b))]).unwrap();
+
+    let and_count = circuit.gates.iter().filter(|gate| gate.op == "AND").count();
+
+    assert!(and_count < circuit.gates.len());
+    assert_eq!(
+        circuit_cost(&circuit, &CostModel::garbled_circuit()),
+        Ok(and_count as f64)
+    );
+}
+
+#[test]
+fn test_circuit_cost_unknown_op() {
+    let circuit = BristolCircuit {
+        wire_count: 4,
+        info: CircuitInfo::default(),
+        gates: vec![gate(vec![0, 1], 2, "AND"), gate(vec![0, 2], 3, "OR")],
+    };
+
+    assert_eq!(
+        circuit_cost(&circuit, &CostModel::garbled_circuit()),
+        Err(CircuitAnalysisError::UnknownOp("OR".to_string()))
+    );
+}
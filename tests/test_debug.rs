@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use boolify::{
+    debug::{count_reachable, to_dot, wire_ref_stats},
+    eval, generate_bristol, generate_bristol_with_options, CircuitOutput, GenerateBristolOptions,
+    IdGenerator, ValueWire,
+};
+
+#[test]
+fn test_wire_ref_stats_2bit_add() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_input("b", 2, &id_gen);
+
+    let outputs = vec![CircuitOutput::new("c", ValueWire::add(&a, &b))];
+
+    let stats = wire_ref_stats(&outputs);
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    let input_count = 4;
+    let constant_count = 0;
+
+    assert_eq!(
+        stats.total_nodes,
+        circuit.gates.len() + input_count + constant_count
+    );
+
+    // a[0] and b[0] feed the XOR and AND of the first bit (and are still held by a and b)
+    assert!(stats.shared_nodes >= 4);
+    assert!(stats.max_ref_count >= 3);
+
+    // a[1] ^ b[1] ^ (a[0] & b[0])
+    assert_eq!(stats.max_depth, 2);
+}
+
+#[test]
+fn test_wire_ref_stats_constants() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+
+    let outputs = vec![
+        CircuitOutput::new("a", a.clone()),
+        CircuitOutput::new("c", ValueWire::new_const(0b101, &id_gen)),
+    ];
+
+    let stats = wire_ref_stats(&outputs);
+
+    // 4 inputs + 3 constants, no gates
+    assert_eq!(stats.total_nodes, 7);
+    assert_eq!(stats.max_depth, 0);
+}
+
+#[test]
+fn test_unused_wires_generate_no_gates() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_input("b", 8, &id_gen);
+
+    let used = ValueWire::bit_xor(&a, &b);
+    let outputs = vec![CircuitOutput::new("c", used)];
+
+    let (reachable_before, allocated_before) = count_reachable(&outputs);
+
+    // Built but never given to an output
+    let unused = ValueWire::add(&ValueWire::mul(&a, &b), &a);
+
+    let (reachable, allocated) = count_reachable(&outputs);
+    assert_eq!(reachable, reachable_before);
+    assert!(allocated > allocated_before);
+
+    let circuit = generate_bristol(&outputs).unwrap();
+
+    // 8 XORs and nothing from unused
+    assert_eq!(reachable, 8);
+    assert_eq!(circuit.gates.len(), 8);
+    assert!(circuit.gates.iter().all(|gate| gate.op == "XOR"));
+    assert_eq!(circuit.wire_count, 16 + 8);
+
+    let inputs = HashMap::from([("a".to_string(), 0x5a), ("b".to_string(), 0x0f)]);
+    assert_eq!(
+        eval(&circuit, &inputs).unwrap().get("c").unwrap(),
+        &(0x5a ^ 0x0f)
+    );
+
+    // Including it brings its gates in
+    let (with_unused, _) = count_reachable(&[
+        CircuitOutput::new("c", ValueWire::bit_xor(&a, &b)),
+        CircuitOutput::new("d", unused),
+    ]);
+
+    assert!(with_unused > reachable);
+}
+
+#[test]
+fn test_generated_counts_match_reachable() {
+    type Build = fn(&ValueWire, &ValueWire) -> ValueWire;
+
+    let builds: [(&str, Build); 5] = [
+        ("add", ValueWire::add),
+        ("sub", ValueWire::sub),
+        ("mul", ValueWire::mul),
+        ("xor", ValueWire::bit_xor),
+        ("mul_add", |a, b| ValueWire::add(&ValueWire::mul(a, b), a)),
+    ];
+
+    let exact = GenerateBristolOptions {
+        simplify: false,
+        deduplicate: false,
+        ..Default::default()
+    };
+
+    for (name, build) in builds {
+        let id_gen = IdGenerator::new_rc_refcell();
+
+        let a = ValueWire::new_input("a", 8, &id_gen);
+        let b = ValueWire::new_input("b", 8, &id_gen);
+
+        let outputs = vec![CircuitOutput::new("c", build(&a, &b))];
+
+        // Built but never given to an output, so none of this should be emitted
+        ValueWire::mul(&ValueWire::add(&a, &b), &b);
+
+        let (reachable, allocated) = count_reachable(&outputs);
+        let input_bits = 16;
+
+        assert!(reachable + input_bits < allocated, "{}", name);
+
+        // Without simplification or deduplication, every reachable gate becomes exactly one gate
+        // and one wire, and nothing else is emitted
+        let circuit = generate_bristol_with_options(&outputs, &exact).unwrap();
+
+        assert_eq!(circuit.gates.len(), reachable, "{}", name);
+        assert_eq!(circuit.wire_count, input_bits + reachable, "{}", name);
+
+        // Those passes only ever remove gates
+        let circuit = generate_bristol(&outputs).unwrap();
+
+        assert!(circuit.gates.len() <= reachable, "{}", name);
+        assert!(circuit.wire_count <= input_bits + reachable, "{}", name);
+    }
+}
+
+#[test]
+fn test_to_dot_2bit_add() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_input("b", 2, &id_gen);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", ValueWire::add(&a, &b))]).unwrap();
+    let dot = to_dot(&circuit);
+
+    let lines = dot.lines().map(str::trim).collect::<Vec<_>>();
+
+    let nodes = lines
+        .iter()
+        .filter(|line| line.contains(" [label="))
+        .count();
+    let edges = lines.iter().filter(|line| line.contains(" -> ")).count();
+
+    // 4 input bits, 4 gates (3 XOR, 1 AND) and 2 output bits
+    assert_eq!(nodes, 4 + 4 + 2);
+
+    // 2 inputs per gate, and one edge into each output
+    assert_eq!(edges, 4 * 2 + 2);
+
+    assert_eq!(
+        lines
+            .iter()
+            .filter(|line| line.contains("color=green"))
+            .count(),
+        3
+    );
+    assert_eq!(
+        lines
+            .iter()
+            .filter(|line| line.contains("color=blue"))
+            .count(),
+        1
+    );
+
+    assert!(lines.contains(&"w0 [label=\"a[0]\", shape=box];"));
+    assert!(lines.contains(&"out7 [label=\"c[1]\", shape=box];"));
+
+    // Well formed: a single digraph with balanced braces and every statement terminated
+    assert_eq!(lines.first(), Some(&"digraph circuit {"));
+    assert_eq!(lines.last(), Some(&"}"));
+    assert_eq!(dot.matches('{').count(), dot.matches('}').count());
+
+    for line in &lines {
+        assert!(line.ends_with(';') || line.ends_with('{') || *line == "}");
+    }
+}
+
+#[test]
+fn test_to_dot_undriven_wire() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_input("b", 2, &id_gen);
+
+    let mut circuit =
+        generate_bristol(&[CircuitOutput::new("c", ValueWire::bit_xor(&a, &b))]).unwrap();
+
+    // Nothing drives wire 100
+    circuit.gates[0].inputs[0] = 100;
+
+    let dot = to_dot(&circuit);
+
+    assert!(dot.contains("  w100 -> g0;"));
+    assert!(dot.contains("  w100 [label=\"undriven w100\", shape=box, style=dashed];"));
+}
@@ -0,0 +1,41 @@
+use boolify::{
+    dependency_analysis::find_inputs, CircuitOutput, GenerateError, IdGenerator, ValueWire,
+};
+
+#[test]
+fn test_find_inputs_skips_unused() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let _b = ValueWire::new_input("b", 4, &id_gen);
+    let c = ValueWire::new_input("c", 4, &id_gen);
+
+    let outputs = vec![
+        CircuitOutput::new("x", ValueWire::add(&c, &a)),
+        CircuitOutput::new("y", ValueWire::bit_not(&a)),
+    ];
+
+    let names = find_inputs(&outputs)
+        .unwrap()
+        .iter()
+        .map(|input| input.name.clone())
+        .collect::<Vec<_>>();
+
+    assert_eq!(names, vec!["a", "c"]);
+}
+
+#[test]
+fn test_find_inputs_id_conflict() {
+    let a = ValueWire::new_input("a", 4, &IdGenerator::new_rc_refcell());
+    let b = ValueWire::new_input("b", 4, &IdGenerator::new_rc_refcell());
+
+    let outputs = vec![CircuitOutput::new("a", a), CircuitOutput::new("b", b)];
+
+    assert_eq!(
+        find_inputs(&outputs).err(),
+        Some(GenerateError::InputIdConflict(
+            "a".to_string(),
+            "b".to_string()
+        ))
+    );
+}
@@ -0,0 +1,572 @@
+use std::collections::HashMap;
+
+use boolify::{
+    eval, eval_batch, eval_bits, eval_checked, eval_fixed_point, eval_from_bits, eval_traced,
+    eval_u64, generate_bristol, BoolWire, BristolCircuitExt, CircuitOutput, EvalError, IdGenerator,
+    PreparedCircuit, ValueWire,
+};
+use bristol_circuit::{BristolCircuit, CircuitInfo, Gate, IOInfo};
+use serde_json::json;
+
+#[test]
+fn test_eval_copy() {
+    let circuit = BristolCircuit {
+        wire_count: 4,
+        info: CircuitInfo {
+            inputs: vec![IOInfo {
+                name: "a".to_string(),
+                type_: json!("number"),
+                address: 0,
+                width: 2,
+            }],
+            outputs: vec![IOInfo {
+                name: "c".to_string(),
+                type_: json!("number"),
+                address: 2,
+                width: 2,
+            }],
+            ..Default::default()
+        },
+        gates: vec![
+            // Swap the bits of a
+            Gate {
+                inputs: vec![1],
+                outputs: vec![2],
+                op: "COPY".to_string(),
+            },
+            Gate {
+                inputs: vec![0],
+                outputs: vec![3],
+                op: "COPY".to_string(),
+            },
+        ],
+    };
+
+    for a in 0..4 {
+        let inputs = HashMap::from([("a".to_string(), a)]);
+        let result = eval(&circuit, &inputs).unwrap();
+
+        assert_eq!(result.get("c").unwrap(), &(((a & 1) << 1) | (a >> 1)));
+    }
+}
+
+#[test]
+fn test_io_lookup() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 3, &id_gen);
+
+    let circuit = generate_bristol(&[
+        CircuitOutput::new("sum", ValueWire::add(&a, &b)),
+        CircuitOutput::new("lt", BoolWire::as_value(&ValueWire::less_than(&a, &b))),
+    ])
+    .unwrap();
+
+    let lookup = circuit.io_lookup();
+
+    for input in &circuit.info.inputs {
+        let found = lookup.get_input_info(&input.name).unwrap();
+        assert_eq!((found.address, found.width), (input.address, input.width));
+    }
+
+    for output in &circuit.info.outputs {
+        let found = lookup.get_output_info(&output.name).unwrap();
+        assert_eq!((found.address, found.width), (output.address, output.width));
+    }
+
+    assert!(lookup.get_input_info("sum").is_none());
+    assert!(lookup.get_output_info("a").is_none());
+
+    let b_info = lookup.get_input_info("b").unwrap();
+    assert_eq!(BristolCircuit::wire_value_range(b_info), 4..7);
+
+    let lt_info = lookup.get_output_info("lt").unwrap();
+    assert_eq!(BristolCircuit::wire_value_range(lt_info).len(), 1);
+}
+
+#[test]
+fn test_eval_fixed_point_mul() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    // 4.4 * 4.4 has 8 fractional bits, drop 4 of them for an 8.4 result
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_input("b", 8, &id_gen);
+    let c = ValueWire::mul(&a.resize(16), &b.resize(16))
+        .shift_down_const(4)
+        .truncate_silent(12);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", c)]).unwrap();
+    let fractional_bits = HashMap::from([("c".to_string(), 4)]);
+
+    for (a, b, expected) in [
+        (2.5, 1.25, 3.125),
+        (0.5, 0.5, 0.25),
+        (15.9375, 15.9375, 254.0),
+    ] {
+        let inputs = HashMap::from([
+            ("a".to_string(), (a * 16.0) as usize),
+            ("b".to_string(), (b * 16.0) as usize),
+        ]);
+
+        let result = eval_fixed_point(&circuit, &inputs, &fractional_bits).unwrap();
+
+        assert_eq!(result.get("c").unwrap(), &expected);
+    }
+}
+
+#[test]
+fn test_eval_u64_full_range() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 64, &id_gen);
+    let c = ValueWire::bit_xor(&a, &ValueWire::new_const(0xf0f0_0000_0000_000f, &id_gen));
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", c)]).unwrap();
+
+    for a in [0, 1, u64::MAX >> 1, 1 << 63, u64::MAX] {
+        let inputs = HashMap::from([("a".to_string(), a)]);
+
+        assert_eq!(
+            eval_u64(&circuit, &inputs).unwrap().get("c").unwrap(),
+            &(a ^ 0xf0f0_0000_0000_000f)
+        );
+    }
+}
+
+#[test]
+fn test_eval_checked() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", ValueWire::add(&a, &b))]).unwrap();
+
+    let inputs = |pairs: &[(&str, usize)]| {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        eval_checked(&circuit, &inputs(&[("a", 3), ("b", 5)])),
+        Ok(HashMap::from([("c".to_string(), 8)]))
+    );
+
+    assert_eq!(
+        eval_checked(&circuit, &inputs(&[("a", 3), ("b", 5), ("a", 4)])),
+        Err(EvalError::DuplicateInput("a".to_string()))
+    );
+
+    assert_eq!(
+        eval_checked(&circuit, &inputs(&[("b", 5)])),
+        Err(EvalError::InputMissing("a".to_string()))
+    );
+
+    assert_eq!(
+        eval_checked(&circuit, &inputs(&[("a", 3), ("b", 5), ("d", 1)])),
+        Err(EvalError::UnexpectedInput("d".to_string()))
+    );
+}
+
+#[test]
+fn test_eval_bits_matches_eval() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let circuit = generate_bristol(&[
+        CircuitOutput::new("sum", ValueWire::add(&a, &b)),
+        CircuitOutput::new("product", ValueWire::mul(&a.resize(8), &b.resize(8))),
+        CircuitOutput::new("lt", BoolWire::as_value(&ValueWire::less_than(&a, &b))),
+    ])
+    .unwrap();
+
+    for a in 0..16 {
+        for b in 0..16 {
+            let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+
+            let values = eval(&circuit, &inputs).unwrap();
+            let bits = eval_bits(&circuit, &inputs).unwrap();
+
+            assert_eq!(bits["sum"].len(), 4);
+            assert_eq!(bits["product"].len(), 8);
+            assert_eq!(bits["lt"].len(), 1);
+
+            for (name, value) in &values {
+                let decoded = bits[name]
+                    .iter()
+                    .enumerate()
+                    .map(|(j, bit)| (*bit as usize) << j)
+                    .sum::<usize>();
+
+                assert_eq!(decoded, *value);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_eval_from_bits_wide() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    // Wider than usize, so only representable as bits
+    let a = ValueWire::new_input("a", 100, &id_gen);
+    let b = ValueWire::new_input("b", 100, &id_gen);
+
+    let circuit = generate_bristol(&[
+        CircuitOutput::new("a", a.clone()),
+        CircuitOutput::new("xor", ValueWire::bit_xor(&a, &b)),
+    ])
+    .unwrap();
+
+    let a_bits = (0..100).map(|j| j % 3 == 0).collect::<Vec<_>>();
+    let b_bits = (0..100).map(|j| j % 7 < 2).collect::<Vec<_>>();
+
+    let outputs = eval_from_bits(
+        &circuit,
+        &HashMap::from([
+            ("a".to_string(), a_bits.clone()),
+            ("b".to_string(), b_bits.clone()),
+        ]),
+    )
+    .unwrap();
+
+    assert_eq!(outputs["a"], a_bits);
+    assert_eq!(
+        outputs["xor"],
+        a_bits
+            .iter()
+            .zip(&b_bits)
+            .map(|(a, b)| a ^ b)
+            .collect::<Vec<_>>()
+    );
+
+    // eval_bits zero-extends the usize inputs
+    let outputs = eval_bits(
+        &circuit,
+        &HashMap::from([("a".to_string(), 0b101), ("b".to_string(), usize::MAX)]),
+    )
+    .unwrap();
+
+    let mut expected_xor = vec![true; usize::BITS as usize];
+    expected_xor.resize(100, false);
+    expected_xor[0] = false;
+    expected_xor[2] = false;
+
+    assert_eq!(outputs["xor"], expected_xor);
+}
+
+#[test]
+fn test_eval_batch_matches_eval() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let circuit = generate_bristol(&[
+        CircuitOutput::new("sum", ValueWire::add(&a, &b)),
+        CircuitOutput::new("product", ValueWire::mul(&a, &b)),
+        CircuitOutput::new("lt", BoolWire::as_value(&ValueWire::less_than(&a, &b))),
+        CircuitOutput::new("not_a", ValueWire::bit_not(&a)),
+        CircuitOutput::new("const", ValueWire::new_const(0b1001, &id_gen)),
+    ])
+    .unwrap();
+
+    let prepared = PreparedCircuit::new(&circuit).unwrap();
+
+    let pairs = (0..16)
+        .flat_map(|a| (0..16).map(move |b| (a, b)))
+        .collect::<Vec<_>>();
+
+    // 256 is a whole number of 64-lane chunks, 100 and 1 are not
+    for count in [256, 100, 1, 0] {
+        let inputs = HashMap::from([
+            (
+                "a".to_string(),
+                pairs[..count].iter().map(|(a, _)| *a).collect(),
+            ),
+            (
+                "b".to_string(),
+                pairs[..count].iter().map(|(_, b)| *b).collect(),
+            ),
+        ]);
+
+        let outputs = eval_batch(&prepared, &inputs).unwrap();
+
+        for (name, column) in &outputs {
+            assert_eq!(column.len(), count, "{}", name);
+        }
+
+        for (k, (a, b)) in pairs[..count].iter().enumerate() {
+            let expected = eval(
+                &circuit,
+                &HashMap::from([("a".to_string(), *a), ("b".to_string(), *b)]),
+            )
+            .unwrap();
+
+            assert_eq!(expected.len(), outputs.len());
+
+            for (name, value) in expected {
+                assert_eq!(outputs[&name][k], value);
+            }
+        }
+    }
+
+    assert_eq!(
+        eval_batch(
+            &prepared,
+            &HashMap::from([("a".to_string(), vec![1, 2]), ("b".to_string(), vec![3])])
+        ),
+        Err(EvalError::BatchLengthMismatch("b".to_string()))
+    );
+}
+
+#[test]
+fn test_eval_traced() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", ValueWire::mul(&a, &b))]).unwrap();
+    let inputs = HashMap::from([("a".to_string(), 6), ("b".to_string(), 7)]);
+
+    let (outputs, trace) = eval_traced(&circuit, &inputs).unwrap();
+
+    assert_eq!(outputs, eval(&circuit, &inputs).unwrap());
+    assert_eq!(trace.len(), circuit.wire_count);
+
+    for gate in &circuit.gates {
+        assert!(trace[gate.outputs[0]].is_some());
+    }
+
+    // a is wires 0..4 and b is wires 4..8
+    let a_bits = trace[0..4]
+        .iter()
+        .map(|bit| bit.unwrap())
+        .collect::<Vec<_>>();
+    let b_bits = trace[4..8]
+        .iter()
+        .map(|bit| bit.unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(a_bits, vec![false, true, true, false]);
+    assert_eq!(b_bits, vec![true, true, true, false]);
+}
+
+#[test]
+fn test_eval_traced_unassigned_wire() {
+    // Wire 2 is declared but nothing drives it
+    let circuit = BristolCircuit {
+        wire_count: 4,
+        info: CircuitInfo {
+            inputs: vec![IOInfo {
+                name: "a".to_string(),
+                type_: json!("number"),
+                address: 0,
+                width: 2,
+            }],
+            outputs: vec![IOInfo {
+                name: "c".to_string(),
+                type_: json!("number"),
+                address: 3,
+                width: 1,
+            }],
+            ..Default::default()
+        },
+        gates: vec![Gate {
+            inputs: vec![0, 1],
+            outputs: vec![3],
+            op: "AND".to_string(),
+        }],
+    };
+
+    let (outputs, trace) = eval_traced(&circuit, &HashMap::from([("a".to_string(), 3)])).unwrap();
+
+    assert_eq!(outputs, HashMap::from([("c".to_string(), 1)]));
+    assert_eq!(trace, vec![Some(true), Some(true), None, Some(true)]);
+}
+
+fn single_gate_circuit(op: &str, inputs: Vec<usize>) -> BristolCircuit {
+    BristolCircuit {
+        wire_count: 4,
+        info: CircuitInfo {
+            inputs: vec![IOInfo {
+                name: "a".to_string(),
+                type_: json!("number"),
+                address: 0,
+                width: 2,
+            }],
+            outputs: vec![IOInfo {
+                name: "c".to_string(),
+                type_: json!("number"),
+                address: 3,
+                width: 1,
+            }],
+            ..Default::default()
+        },
+        gates: vec![Gate {
+            inputs,
+            outputs: vec![3],
+            op: op.to_string(),
+        }],
+    }
+}
+
+#[test]
+fn test_eval_errors() {
+    let circuit = single_gate_circuit("AND", vec![0, 1]);
+
+    assert_eq!(
+        eval(&circuit, &HashMap::from([("a".to_string(), 3)])),
+        Ok(HashMap::from([("c".to_string(), 1)]))
+    );
+
+    assert_eq!(
+        eval(&circuit, &HashMap::new()),
+        Err(EvalError::InputMissing("a".to_string()))
+    );
+
+    assert_eq!(
+        eval(&circuit, &HashMap::from([("a".to_string(), 4)])),
+        Err(EvalError::InputTooLarge {
+            name: "a".to_string(),
+            value: 4,
+            width: 2,
+        })
+    );
+
+    assert_eq!(
+        eval(
+            &single_gate_circuit("OR", vec![0, 1]),
+            &HashMap::from([("a".to_string(), 3)])
+        ),
+        Err(EvalError::UnknownOp("OR".to_string()))
+    );
+
+    // Wire 2 is never assigned
+    assert_eq!(
+        eval(
+            &single_gate_circuit("AND", vec![0, 2]),
+            &HashMap::from([("a".to_string(), 3)])
+        ),
+        Err(EvalError::UninitialisedWire(2))
+    );
+
+    assert_eq!(
+        eval_from_bits(&circuit, &HashMap::from([("a".to_string(), vec![true])])),
+        Err(EvalError::InputWrongWidth {
+            name: "a".to_string(),
+            width: 2,
+            bits: 1,
+        })
+    );
+
+    let prepared = PreparedCircuit::new(&circuit).unwrap();
+
+    assert_eq!(
+        eval_batch(&prepared, &HashMap::from([("a".to_string(), vec![3, 2])])),
+        Ok(HashMap::from([("c".to_string(), vec![1, 0])]))
+    );
+
+    assert_eq!(
+        eval_batch(&prepared, &HashMap::new()),
+        Err(EvalError::InputMissing("a".to_string()))
+    );
+
+    assert_eq!(
+        eval_batch(&prepared, &HashMap::from([("a".to_string(), vec![3, 5])])),
+        Err(EvalError::InputTooLarge {
+            name: "a".to_string(),
+            value: 5,
+            width: 2,
+        })
+    );
+}
+
+#[test]
+fn test_eval_out_of_range() {
+    let inputs = HashMap::from([("a".to_string(), 3)]);
+
+    assert_eq!(
+        eval(&single_gate_circuit("AND", vec![0, 9]), &inputs),
+        Err(EvalError::WireOutOfRange(9))
+    );
+
+    assert_eq!(
+        eval(&single_gate_circuit("AND", vec![0]), &inputs),
+        Err(EvalError::MalformedGate(0))
+    );
+
+    let mut circuit = single_gate_circuit("AND", vec![0, 1]);
+    circuit.gates[0].outputs = vec![7];
+
+    assert_eq!(eval(&circuit, &inputs), Err(EvalError::WireOutOfRange(7)));
+
+    circuit.gates[0].outputs = vec![];
+
+    assert_eq!(eval(&circuit, &inputs), Err(EvalError::MalformedGate(0)));
+
+    let mut circuit = single_gate_circuit("AND", vec![0, 1]);
+    circuit.info.inputs[0].address = 3;
+
+    assert_eq!(eval(&circuit, &inputs), Err(EvalError::WireOutOfRange(3)));
+
+    let mut circuit = single_gate_circuit("AND", vec![0, 1]);
+    circuit.info.outputs[0].address = usize::MAX;
+
+    assert_eq!(
+        eval(&circuit, &inputs),
+        Err(EvalError::WireOutOfRange(usize::MAX))
+    );
+}
+
+#[test]
+fn test_prepared_circuit_errors() {
+    // Problems with the circuit itself are found when preparing it, before any evaluation
+    assert!(matches!(
+        PreparedCircuit::new(&single_gate_circuit("AND", vec![0, 2])),
+        Err(EvalError::UninitialisedWire(2))
+    ));
+
+    assert!(matches!(
+        PreparedCircuit::new(&single_gate_circuit("AND", vec![0, 9])),
+        Err(EvalError::WireOutOfRange(9))
+    ));
+
+    assert!(matches!(
+        PreparedCircuit::new(&single_gate_circuit("AND", vec![0])),
+        Err(EvalError::MalformedGate(0))
+    ));
+
+    assert!(matches!(
+        PreparedCircuit::new(&single_gate_circuit("OR", vec![0, 1])),
+        Err(EvalError::UnknownOp(_))
+    ));
+}
+
+#[test]
+fn test_eval_output_too_large() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let c = a.resize(100).shift_up_const(92);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", c)]).unwrap();
+
+    assert_eq!(
+        eval(&circuit, &HashMap::from([("a".to_string(), 0)])),
+        Ok(HashMap::from([("c".to_string(), 0)]))
+    );
+
+    assert_eq!(
+        eval(&circuit, &HashMap::from([("a".to_string(), 1)])),
+        Err(EvalError::OutputTooLarge("c".to_string()))
+    );
+
+    assert!(eval_bits(&circuit, &HashMap::from([("a".to_string(), 1)])).unwrap()["c"][92]);
+}
@@ -0,0 +1,60 @@
+use std::{cell::RefCell, env, process::Command, rc::Rc};
+
+use boolify::{IdGenerator, ValueWire};
+
+const CHILD_ENV: &str = "BOOLIFY_RESIZE_WARNING_CHILD";
+
+// Runs the given test in a child process (with CHILD_ENV set) and returns its stderr
+fn child_stderr(test_name: &str) -> String {
+    let output = Command::new(env::current_exe().unwrap())
+        .args([test_name, "--exact", "--nocapture", "--test-threads=1"])
+        .env(CHILD_ENV, "1")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    String::from_utf8(output.stderr).unwrap()
+}
+
+fn resize_in_child(size: usize) {
+    let id_gen = Rc::new(RefCell::new(IdGenerator::new()));
+    let a = ValueWire::new_input("a", 8, &id_gen);
+
+    assert_eq!(a.resize(size).bits.len(), size);
+    assert_eq!(a.truncate_silent(4).bits.len(), 4);
+}
+
+#[test]
+fn test_resize_down_warns() {
+    if env::var(CHILD_ENV).is_ok() {
+        resize_in_child(4);
+        return;
+    }
+
+    let stderr = child_stderr("test_resize_down_warns");
+
+    if cfg!(debug_assertions) {
+        assert_eq!(
+            stderr
+                .matches("warning: resize truncates 8 bits to 4")
+                .count(),
+            1
+        );
+        assert!(stderr.contains("test_resize_warning.rs"));
+    } else {
+        assert!(!stderr.contains("warning: resize"));
+    }
+}
+
+#[test]
+fn test_resize_up_does_not_warn() {
+    if env::var(CHILD_ENV).is_ok() {
+        resize_in_child(16);
+        return;
+    }
+
+    let stderr = child_stderr("test_resize_up_does_not_warn");
+
+    assert!(!stderr.contains("warning: resize"));
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use boolify::{eval, generate_bristol, CircuitOutput, IdGenerator, SmallValueWire, ValueWire};
+use bristol_circuit::BristolCircuit;
+
+type SmallOp = fn(&SmallValueWire<4>, &SmallValueWire<4>) -> SmallValueWire<4>;
+type ValueOp = fn(&ValueWire, &ValueWire) -> ValueWire;
+
+fn small_circuit(op: SmallOp) -> BristolCircuit {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = SmallValueWire::<4>::new_input("a", &id_gen);
+    let b = SmallValueWire::<4>::new_input("b", &id_gen);
+
+    generate_bristol(&[CircuitOutput::new("c", op(&a, &b).to_value())]).unwrap()
+}
+
+fn value_circuit(op: ValueOp) -> BristolCircuit {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 4, &id_gen);
+    let b = ValueWire::new_input("b", 4, &id_gen);
+
+    generate_bristol(&[CircuitOutput::new("c", op(&a, &b))]).unwrap()
+}
+
+// Every 4-bit input pair gives the same result as ValueWire (and expected), from the same number
+// of gates
+fn test_matches_value_wire(
+    small_op: SmallOp,
+    value_op: ValueOp,
+    expected: fn(usize, usize) -> usize,
+) {
+    let small = small_circuit(small_op);
+    let value = value_circuit(value_op);
+
+    assert_eq!(small.gates.len(), value.gates.len());
+
+    for a in 0..16 {
+        for b in 0..16 {
+            let inputs = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+
+            let result = eval(&small, &inputs).unwrap()["c"];
+
+            assert_eq!(result, expected(a, b) % 16);
+            assert_eq!(result, eval(&value, &inputs).unwrap()["c"]);
+        }
+    }
+}
+
+#[test]
+fn test_small_value_wire_add() {
+    test_matches_value_wire(SmallValueWire::add, ValueWire::add, |a, b| a + b);
+}
+
+#[test]
+fn test_small_value_wire_bitwise() {
+    test_matches_value_wire(SmallValueWire::bit_and, ValueWire::bit_and, |a, b| a & b);
+    test_matches_value_wire(SmallValueWire::bit_or, ValueWire::bit_or, |a, b| a | b);
+    test_matches_value_wire(SmallValueWire::bit_xor, ValueWire::bit_xor, |a, b| a ^ b);
+
+    test_matches_value_wire(
+        |a, _| SmallValueWire::bit_not(a),
+        |a, _| ValueWire::bit_not(a),
+        |a, _| !a,
+    );
+}
+
+#[test]
+fn test_small_value_wire_conversions() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 6, &id_gen);
+
+    // Truncates a to 4 bits, then zero extends the const 3 to 4 bits
+    let sum = SmallValueWire::add(
+        &SmallValueWire::<4>::from_value(&a),
+        &SmallValueWire::from_value(&ValueWire::new_const(3, &id_gen)),
+    );
+
+    let circuit = generate_bristol(&[
+        CircuitOutput::new("sum", sum.to_value()),
+        CircuitOutput::new(
+            "const",
+            SmallValueWire::<4>::new_const(0b1010, &id_gen).to_value(),
+        ),
+    ])
+    .unwrap();
+
+    assert_eq!(circuit.info.outputs[0].width, 4);
+    assert_eq!(circuit.info.outputs[1].width, 4);
+
+    for a in 0..64 {
+        let outputs = eval(&circuit, &HashMap::from([("a".to_string(), a)])).unwrap();
+
+        assert_eq!(outputs["sum"], (a + 3) % 16);
+        assert_eq!(outputs["const"], 0b1010);
+    }
+}
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use boolify::{
+    eval,
+    testing::{random_circuit, SplitMix64},
+};
+
+#[test]
+fn test_random_circuit_eval() {
+    let circuit = random_circuit(1000, 16, 8, 42);
+
+    assert_eq!(circuit.gates.len(), 1000);
+    assert_eq!(circuit.wire_count, 1016);
+
+    for (i, gate) in circuit.gates.iter().enumerate() {
+        assert!(gate.inputs.iter().all(|input| *input < 16 + i));
+        assert_eq!(gate.outputs, vec![16 + i]);
+    }
+
+    let mut rng = SplitMix64::new(7);
+
+    for _ in 0..100 {
+        let inputs = circuit
+            .info
+            .inputs
+            .iter()
+            .map(|input| (input.name.clone(), rng.below(2)))
+            .collect::<HashMap<String, usize>>();
+
+        let outputs = eval(&circuit, &inputs).unwrap();
+
+        assert_eq!(outputs.len(), 8);
+    }
+}
+
+#[test]
+fn test_random_circuit_seed() {
+    let a = random_circuit(100, 4, 2, 1).get_bristol_string().unwrap();
+    let b = random_circuit(100, 4, 2, 1).get_bristol_string().unwrap();
+    let c = random_circuit(100, 4, 2, 2).get_bristol_string().unwrap();
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
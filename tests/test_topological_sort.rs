@@ -0,0 +1,96 @@
+use boolify::{BristolCircuitExt, TopologicalError};
+use bristol_circuit::{BristolCircuit, CircuitInfo, Gate, IOInfo};
+use serde_json::json;
+
+fn gate(inputs: Vec<usize>, output: usize, op: &str) -> Gate {
+    Gate {
+        inputs,
+        outputs: vec![output],
+        op: op.to_string(),
+    }
+}
+
+fn circuit(gates: Vec<Gate>) -> BristolCircuit {
+    let io = |name: &str, address: usize| IOInfo {
+        name: name.to_string(),
+        type_: json!("bool"),
+        address,
+        width: 1,
+    };
+
+    BristolCircuit {
+        wire_count: 6,
+        info: CircuitInfo {
+            inputs: vec![io("a", 0), io("b", 1)],
+            outputs: vec![io("c", 5)],
+            ..Default::default()
+        },
+        gates,
+    }
+}
+
+#[test]
+fn test_topological_sort() {
+    // c = !(a & b) ^ (a ^ b), with the gates listed out of order
+    let circuit = circuit(vec![
+        gate(vec![3, 4], 5, "XOR"),
+        gate(vec![2], 3, "INV"),
+        gate(vec![0, 1], 2, "AND"),
+        gate(vec![0, 1], 4, "XOR"),
+    ]);
+
+    assert_eq!(circuit.topological_sort(), Ok(vec![2, 1, 3, 0]));
+}
+
+#[test]
+fn test_topological_sort_already_ordered() {
+    let circuit = circuit(vec![
+        gate(vec![0, 1], 2, "AND"),
+        gate(vec![2], 3, "INV"),
+        gate(vec![0, 1], 4, "XOR"),
+        gate(vec![3, 4], 5, "XOR"),
+    ]);
+
+    assert_eq!(circuit.topological_sort(), Ok(vec![0, 1, 2, 3]));
+}
+
+#[test]
+fn test_topological_sort_cycle() {
+    // Wire 3 depends on wire 4 and vice versa
+    let circuit = circuit(vec![
+        gate(vec![0, 4], 3, "AND"),
+        gate(vec![1, 3], 4, "XOR"),
+        gate(vec![3, 4], 5, "XOR"),
+    ]);
+
+    assert_eq!(
+        circuit.topological_sort(),
+        Err(TopologicalError::CycleDetected)
+    );
+}
+
+#[test]
+fn test_topological_sort_out_of_range() {
+    let gate_out_of_range = circuit(vec![gate(vec![0, 1], 2, "AND"), gate(vec![2, 9], 5, "XOR")]);
+
+    assert_eq!(
+        gate_out_of_range.topological_sort(),
+        Err(TopologicalError::WireOutOfRange(9))
+    );
+
+    let mut io_out_of_range = circuit(vec![gate(vec![0, 1], 5, "XOR")]);
+    io_out_of_range.info.inputs[1].width = 6;
+
+    assert_eq!(
+        io_out_of_range.topological_sort(),
+        Err(TopologicalError::IOOutOfRange("b".to_string()))
+    );
+
+    let mut address_overflow = circuit(vec![gate(vec![0, 1], 5, "XOR")]);
+    address_overflow.info.outputs[0].address = usize::MAX;
+
+    assert_eq!(
+        address_overflow.topological_sort(),
+        Err(TopologicalError::IOOutOfRange("c".to_string()))
+    );
+}
@@ -0,0 +1,228 @@
+use boolify::{
+    generate_bristol, generate_bristol_with_options, to_verilog_netlist, BoolWire, CircuitOutput,
+    ConstantStrategy, ExportError, GenerateBristolOptions, IdGenerator, ValueWire,
+};
+use serde_json::json;
+
+// The lines of the circuit's own module (not the and_gate/xor_gate definitions after it)
+fn main_module<'a>(verilog: &'a str, module_name: &str) -> Vec<&'a str> {
+    let lines = verilog.lines().collect::<Vec<_>>();
+
+    assert_eq!(lines[0], format!("module {} (", module_name));
+
+    let end = lines
+        .iter()
+        .position(|line| *line == "endmodule")
+        .expect("module should end");
+
+    lines[..end].to_vec()
+}
+
+#[test]
+fn test_verilog_2bit_add() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_input("b", 2, &id_gen);
+
+    let circuit = generate_bristol(&[CircuitOutput::new("c", ValueWire::add(&a, &b))]).unwrap();
+
+    assert_eq!(
+        to_verilog_netlist(&circuit, "add2").unwrap(),
+        vec![
+            "module add2 (",
+            "  input [1:0] a,",
+            "  input [1:0] b,",
+            "  output [1:0] c",
+            ");",
+            "  wire w4;",
+            "  wire w5;",
+            "  xor_gate g0 (.a(a[0]), .b(b[0]), .y(c[0]));",
+            "  xor_gate g1 (.a(a[1]), .b(b[1]), .y(w4));",
+            "  and_gate g2 (.a(a[0]), .b(b[0]), .y(w5));",
+            "  xor_gate g3 (.a(w4), .b(w5), .y(c[1]));",
+            "endmodule",
+            "",
+            "module and_gate (input a, input b, output y);",
+            "  assign y = a & b;",
+            "endmodule",
+            "",
+            "module xor_gate (input a, input b, output y);",
+            "  assign y = a ^ b;",
+            "endmodule",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn test_verilog_ports_and_gate_lines() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 8, &id_gen);
+    let b = ValueWire::new_input("b", 8, &id_gen);
+    let flag = ValueWire::new_input("flag", 1, &id_gen);
+
+    let outputs = vec![
+        CircuitOutput::new(
+            "sum",
+            ValueWire::select(&flag.at(0), &ValueWire::add(&a, &b), &a),
+        ),
+        CircuitOutput::new("not_b", ValueWire::bit_not(&b)),
+        CircuitOutput::new("less", BoolWire::as_value(&ValueWire::less_than(&a, &b))),
+    ];
+
+    let circuit = generate_bristol(&outputs).unwrap();
+    let verilog = to_verilog_netlist(&circuit, "mixed").unwrap();
+    let lines = main_module(&verilog, "mixed");
+
+    for io in &circuit.info.inputs {
+        let expected = match io.width {
+            1 => format!("  input {}", io.name),
+            width => format!("  input [{}:0] {}", width - 1, io.name),
+        };
+
+        assert!(lines
+            .iter()
+            .any(|line| line.trim_end_matches(',') == expected));
+    }
+
+    for io in &circuit.info.outputs {
+        let expected = match io.width {
+            1 => format!("  output {}", io.name),
+            width => format!("  output [{}:0] {}", width - 1, io.name),
+        };
+
+        assert!(lines
+            .iter()
+            .any(|line| line.trim_end_matches(',') == expected));
+    }
+
+    assert!(lines.contains(&"  input flag,"));
+    assert!(lines.contains(&"  output [7:0] sum,"));
+    assert!(lines.contains(&"  output less"));
+
+    let gate_lines = lines
+        .iter()
+        .filter(|line| {
+            line.starts_with("  assign ")
+                || line.starts_with("  and_gate ")
+                || line.starts_with("  xor_gate ")
+        })
+        .count();
+
+    assert_eq!(gate_lines, circuit.gates.len());
+
+    let and_lines = lines
+        .iter()
+        .filter(|line| line.starts_with("  and_gate "))
+        .count();
+
+    assert_eq!(
+        and_lines,
+        circuit.gates.iter().filter(|gate| gate.op == "AND").count()
+    );
+
+    // Every wire that isn't a port bit is declared
+    let port_bits = circuit
+        .info
+        .inputs
+        .iter()
+        .chain(&circuit.info.outputs)
+        .map(|io| io.width)
+        .sum::<usize>();
+
+    let wire_lines = lines
+        .iter()
+        .filter(|line| line.starts_with("  wire "))
+        .count();
+
+    assert_eq!(wire_lines + port_bits, circuit.wire_count);
+}
+
+#[test]
+fn test_verilog_constants() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+
+    let outputs = vec![
+        CircuitOutput::new("a", a.clone()),
+        CircuitOutput::new("c", ValueWire::new_const(0b10, &id_gen)),
+    ];
+
+    let circuit = generate_bristol_with_options(
+        &outputs,
+        &GenerateBristolOptions {
+            constant_strategy: ConstantStrategy::Inline,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let verilog = to_verilog_netlist(&circuit, "consts").unwrap();
+    let lines = main_module(&verilog, "consts");
+
+    assert!(lines.contains(&"  assign c[0] = 1'b0;"));
+    assert!(lines.contains(&"  assign c[1] = 1'b1;"));
+}
+
+#[test]
+fn test_verilog_errors() {
+    let id_gen = IdGenerator::new_rc_refcell();
+
+    let a = ValueWire::new_input("a", 2, &id_gen);
+    let b = ValueWire::new_input("b", 2, &id_gen);
+
+    let circuit = generate_bristol_with_options(
+        &[
+            CircuitOutput::new("c", ValueWire::add(&a, &b)),
+            CircuitOutput::new("d", ValueWire::new_const(0b10, &id_gen)),
+        ],
+        &GenerateBristolOptions {
+            constant_strategy: ConstantStrategy::Inline,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert!(to_verilog_netlist(&circuit, "ok").is_ok());
+
+    let mut unknown_op = circuit.clone();
+    unknown_op.gates[0].op = "OR".to_string();
+    assert_eq!(
+        to_verilog_netlist(&unknown_op, "bad"),
+        Err(ExportError::UnknownOp("OR".to_string()))
+    );
+
+    let mut malformed = circuit.clone();
+    malformed.gates[0].inputs.pop();
+    assert_eq!(
+        to_verilog_netlist(&malformed, "bad"),
+        Err(ExportError::MalformedGate(0))
+    );
+
+    let mut invalid_constant = circuit.clone();
+    invalid_constant.info.constants[0].value = json!("x");
+    assert_eq!(
+        to_verilog_netlist(&invalid_constant, "bad"),
+        Err(ExportError::InvalidConstant(
+            circuit.info.constants[0].address
+        ))
+    );
+
+    let mut out_of_range = circuit.clone();
+    out_of_range.info.outputs[0].width = circuit.wire_count;
+    assert_eq!(
+        to_verilog_netlist(&out_of_range, "bad"),
+        Err(ExportError::IOOutOfRange("c".to_string()))
+    );
+
+    let mut empty = circuit.clone();
+    empty.info.inputs[0].width = 0;
+    assert_eq!(
+        to_verilog_netlist(&empty, "bad"),
+        Err(ExportError::EmptyPort("a".to_string()))
+    );
+}
@@ -0,0 +1,33 @@
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use boolify::wasm::{eval, generate_bristol_binary_op};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn test_wasm_2bit_add() {
+    let circuit = generate_bristol_binary_op("add", 2).unwrap();
+
+    assert_eq!(
+        circuit.bristol(),
+        vec![
+            "4 8",
+            "2 2 2",
+            "1 2",
+            "",
+            "2 1 0 2 6 XOR",
+            "2 1 1 3 4 XOR",
+            "2 1 0 2 5 AND",
+            "2 1 4 5 7 XOR",
+            "",
+        ]
+        .join("\n")
+    );
+
+    for a in 0..4 {
+        for b in 0..4 {
+            let outputs = eval(&circuit, &format!("{{\"a\": {}, \"b\": {}}}", a, b)).unwrap();
+
+            assert_eq!(outputs, format!("{{\"c\":{}}}", (a + b) & 3));
+        }
+    }
+}
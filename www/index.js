@@ -0,0 +1,29 @@
+// Build the package first (from the repository root):
+//   wasm-pack build --target web --features wasm
+// then serve the repository root (eg `npx serve .`) and open /www/.
+
+import init, { Circuit, boolify, evalCircuit } from "../pkg/boolify.js";
+
+await init();
+
+// The arithmetic circuit from the README: c = a + b
+const arithCircuit = new Circuit(
+  JSON.stringify({
+    constants: [],
+    inputs: [
+      { name: "a", type: "number", address: 0, width: 1 },
+      { name: "b", type: "number", address: 1, width: 1 },
+    ],
+    outputs: [{ name: "c", type: "number", address: 2, width: 1 }],
+  }),
+  ["1 3", "2 1 1", "1 1", "", "2 1 0 1 2 AAdd", ""].join("\n"),
+);
+
+const boolCircuit = boolify(arithCircuit, 4);
+const outputs = JSON.parse(evalCircuit(boolCircuit, JSON.stringify({ a: 3, b: 5 })));
+
+document.getElementById("output").textContent = [
+  boolCircuit.bristol,
+  boolCircuit.info,
+  `3 + 5 = ${outputs.c}`,
+].join("\n");